@@ -0,0 +1,19 @@
+// Time validating a large batch of records, e.g., "cargo bench --bench validate".
+// The patterns are compiled once, so each record should take microseconds.
+use libmdrmeta::metav1::MetaV1;
+use std::{hint::black_box, time::Instant};
+
+const NUM_RECORDS: u32 = 10_000;
+
+fn main() {
+    let meta = MetaV1::example();
+    let start = Instant::now();
+    for _ in 0..NUM_RECORDS {
+        black_box(meta.find_errors());
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "find_errors: {NUM_RECORDS} records in {elapsed:.2?}, {:.2?} each",
+        elapsed / NUM_RECORDS
+    );
+}
@@ -0,0 +1,195 @@
+use crate::common::{parse_pages, Author, Numlike};
+
+// The fields of a V1 or V2 paper needed to cite it
+pub(crate) struct Citation<'a> {
+    pub title: &'a str,
+    pub authors: &'a str,
+    pub author_list: Option<&'a [Author]>,
+    pub journal: &'a str,
+    pub volume: &'a Numlike,
+    pub number: Option<&'a Numlike>,
+    pub year: u32,
+    pub pages: Option<String>,
+    pub doi: Option<&'a str>,
+}
+
+// Split an author list such as "Okada, T., Sugihara, M. et al." into
+// ["Okada, T.", "Sugihara, M."] and whether it was truncated with "et al."
+pub(crate) fn split_authors(authors: &str) -> (Vec<String>, bool) {
+    let authors = authors.trim().trim_end_matches('.');
+    let (authors, et_al) = match authors.strip_suffix("et al") {
+        Some(rest) => (rest.trim().trim_end_matches(','), true),
+        _ => (authors, false),
+    };
+
+    // Semicolons, when used, separate whole names that contain commas
+    let separator = if authors.contains(';') { ';' } else { ',' };
+    let tokens: Vec<&str> = authors
+        .split(separator)
+        .flat_map(|part| part.split(" and "))
+        .flat_map(|part| part.split('&'))
+        .collect();
+
+    // "Last, I." is one author, so rejoin a surname with its initials
+    let mut names: Vec<String> = vec![];
+    for token in tokens.iter().map(|token| token.trim()) {
+        if token.is_empty() {
+            continue;
+        }
+        match names.last_mut() {
+            Some(last) if is_initials(token) && !last.contains(',') => {
+                last.push_str(", ");
+                last.push_str(token);
+            }
+            _ => names.push(token.to_string()),
+        }
+    }
+
+    // Restore the period trimmed from the final initials
+    if let Some(last) = names.last_mut()
+        && last.contains(',')
+        && !last.ends_with('.')
+    {
+        last.push('.');
+    }
+
+    (names, et_al)
+}
+
+// The structured author list when there is one, else the split string
+fn citation_authors(citation: &Citation) -> (Vec<String>, bool) {
+    match citation.author_list {
+        Some(list) if !list.is_empty() => {
+            (list.iter().map(Author::cited_name).collect(), false)
+        }
+        _ => split_authors(citation.authors),
+    }
+}
+
+// E.g., "T.", "J.K.S.", or "J" once the final period is trimmed
+fn is_initials(token: &str) -> bool {
+    token.len() <= 8
+        && token
+            .chars()
+            .all(|c| c.is_uppercase() || matches!(c, '.' | '-' | ' '))
+}
+
+// Escape the characters BibTeX treats specially
+fn escape_bibtex(val: &str) -> String {
+    let mut escaped = String::with_capacity(val.len());
+    for c in val.chars() {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '~' => escaped.push_str(r"\textasciitilde{}"),
+            '^' => escaped.push_str(r"\textasciicircum{}"),
+            '\\' => escaped.push_str(r"\textbackslash{}"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// The DOI when there is one, else the first author's surname and the year
+fn bibtex_key(citation: &Citation, authors: &[String], index: usize) -> String {
+    if let Some(doi) = citation.doi {
+        return doi.to_string();
+    }
+
+    let surname: String = authors
+        .first()
+        .and_then(|author| author.split(',').next())
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect();
+    if surname.is_empty() {
+        format!("paper{}", index + 1)
+    } else {
+        format!("{surname}{}", citation.year)
+    }
+}
+
+// One @article entry per paper
+pub(crate) fn to_bibtex(citations: &[Citation]) -> String {
+    citations
+        .iter()
+        .enumerate()
+        .map(|(index, citation)| {
+            let (mut authors, et_al) = citation_authors(citation);
+            let key = bibtex_key(citation, &authors, index);
+            if et_al {
+                authors.push("others".to_string());
+            }
+
+            let mut fields = vec![
+                ("title", escape_bibtex(citation.title)),
+                ("author", escape_bibtex(&authors.join(" and "))),
+                ("journal", escape_bibtex(citation.journal)),
+                ("volume", escape_bibtex(&citation.volume.as_str())),
+            ];
+            if let Some(number) = citation.number {
+                fields.push(("number", escape_bibtex(&number.as_str())));
+            }
+            fields.push(("year", citation.year.to_string()));
+            if let Some(pages) = &citation.pages {
+                let pages = pages.replace(['-', '–', '—'], "--");
+                fields.push(("pages", escape_bibtex(&pages)));
+            }
+            if let Some(doi) = citation.doi {
+                fields.push(("doi", escape_bibtex(doi)));
+            }
+
+            let body: String = fields
+                .into_iter()
+                .map(|(name, val)| format!("  {name} = {{{val}}},\n"))
+                .collect();
+            format!("@article{{{key},\n{body}}}\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// One RIS record per paper for reference managers such as EndNote and Zotero
+pub(crate) fn to_ris(citations: &[Citation]) -> String {
+    citations
+        .iter()
+        .map(|citation| {
+            let mut tags = vec![
+                ("TY", "JOUR".to_string()),
+                ("TI", citation.title.to_string()),
+            ];
+            let (authors, _) = citation_authors(citation);
+            tags.extend(authors.into_iter().map(|author| ("AU", author)));
+            tags.push(("JO", citation.journal.to_string()));
+            tags.push(("VL", citation.volume.as_str()));
+            if let Some(number) = citation.number {
+                tags.push(("IS", number.as_str()));
+            }
+            tags.push(("PY", citation.year.to_string()));
+            if let Some(pages) = &citation.pages {
+                match parse_pages(pages) {
+                    Some((start, end)) => {
+                        tags.push(("SP", start.to_string()));
+                        if let Some(end) = end {
+                            tags.push(("EP", end.to_string()));
+                        }
+                    }
+                    _ => tags.push(("SP", pages.to_string())),
+                }
+            }
+            if let Some(doi) = citation.doi {
+                tags.push(("DO", doi.to_string()));
+            }
+
+            let body: String = tags
+                .into_iter()
+                .map(|(tag, val)| format!("{tag}  - {val}\n"))
+                .collect();
+            format!("{body}ER  - \n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -1,33 +1,311 @@
+use crate::{
+    cite::split_authors,
+    config::ValidationConfig,
+    error::{ErrorCode, MdrMetaError, MetaError},
+    validate::{validate_pdb_id, validate_uniprot_id, ORCID_PATTERN, YMD_RE},
+};
+use anyhow::Result;
+use chrono::NaiveDate;
+use flate2::read::GzDecoder;
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
 use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    env, fmt,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
 use toml::value::Value as TomlValue;
+use url::Url;
 
 pub const MIN_TEMP_K: u32 = 273;
 pub const MAX_TEMP_K: u32 = 374;
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+// Protein ID types, spelled as to_canon writes them and compared
+// case-sensitively
+pub const MOLECULE_ID_TYPES: &[&str] = &["PDB", "Uniprot", "Unknown"];
+
+// Plausible densities for liquid water
+pub const MIN_WATER_DENSITY_KG_M3: f32 = 800.;
+pub const MAX_WATER_DENSITY_KG_M3: f32 = 1200.;
+
+// The pH scale for aqueous solutions, and the values a simulation is likely
+// to use
+pub const MIN_PH: f64 = 0.;
+pub const MAX_PH: f64 = 14.;
+pub const MIN_PLAUSIBLE_PH: f64 = 1.;
+pub const MAX_PLAUSIBLE_PH: f64 = 13.;
+
+// Integration timesteps longer than this need hydrogen mass repartitioning,
+// and none are plausible beyond the second
+pub const MAX_TIMESTEP_FS: f64 = 4.;
+pub const MAX_HMR_TIMESTEP_FS: f64 = 10.;
+
+// Water models we recognize, compared case-insensitively
+pub const WATER_MODELS: &[&str] = &[
+    "TIP3P",
+    "TIP3P-FB",
+    "TIP4P",
+    "TIP4P-Ew",
+    "TIP4P/2005",
+    "TIP4P-FB",
+    "TIP5P",
+    "SPC",
+    "SPC/E",
+    "OPC",
+    "OPC3",
+];
+
+// Common MD forcefields; custom ones are legitimate, so unknown names only warn
+pub const FORCEFIELDS: &[&str] = &[
+    "AMBER",
+    "Amber99SB",
+    "Amber99SB-ILDN",
+    "Amber14SB",
+    "Amber19SB",
+    "CHARMM",
+    "CHARMM27",
+    "charmm36",
+    "CHARMM36m",
+    "OPLS-AA",
+    "OPLS-AA/M",
+    "GROMOS",
+    "GROMOS54a7",
+    "Martini",
+    // Ligand parameterizations, listed alongside the protein forcefield
+    "GAFF",
+    "GAFF2",
+    "CGenFF",
+    "OpenFF",
+];
+
+// Tools for assigning protonation states, plus "pKa" for manual assignment
+pub const PROTONATION_METHODS: &[&str] = &["PROPKA", "H++", "PDB2PQR", "pKa"];
+
+// Charges of the ions commonly added to a box, by name or symbol, compared
+// case-insensitively
+pub const ION_CHARGES: &[(&str, i32)] = &[
+    ("Sodium", 1),
+    ("Na+", 1),
+    ("Potassium", 1),
+    ("K+", 1),
+    ("Lithium", 1),
+    ("Li+", 1),
+    ("Cesium", 1),
+    ("Cs+", 1),
+    ("Ammonium", 1),
+    ("NH4+", 1),
+    ("Magnesium", 2),
+    ("Mg2+", 2),
+    ("Calcium", 2),
+    ("Ca2+", 2),
+    ("Zinc", 2),
+    ("Zn2+", 2),
+    ("Chloride", -1),
+    ("Cl-", -1),
+    ("Bromide", -1),
+    ("Br-", -1),
+    ("Iodide", -1),
+    ("I-", -1),
+    ("Fluoride", -1),
+    ("F-", -1),
+    ("Sulfate", -2),
+    ("SO42-", -2),
+];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SchemaVersion {
+    // Nested tables, e.g., "[initial]", "[temperature]"
+    V1,
+
+    // Flat top-level keys, e.g., "lead_contributor_orcid"
+    V2,
+}
+
+// A serialization format for from_reader/to_writer
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Format {
+    Json,
+    Toml,
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+impl Format {
+    // E.g., "toml" for "example.toml"
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext {
+            "json" => Some(Format::Json),
+            "toml" => Some(Format::Toml),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Some(Format::Yaml),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(untagged)]
 pub enum Datelike {
     Stringy(String),
-    TomlDate(toml::value::Datetime),
+    TomlDate(#[schemars(with = "String")] toml::value::Datetime),
 }
 
-impl Datelike {
-    pub fn to_string(&self) -> String {
+impl fmt::Display for Datelike {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self {
-            Datelike::TomlDate(dt) => dt.to_string(),
-            Datelike::Stringy(val) => val.clone(),
+            Datelike::TomlDate(dt) => write!(f, "{dt}"),
+            Datelike::Stringy(val) => write!(f, "{val}"),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+// A single name or a list of them, e.g., forcefield = "charmm36" or
+// forcefield = ["Amber99SB-ILDN", "GAFF"]
+#[derive(Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+// For an Option<Vec<String>> field with #[serde(default, with = "one_or_many")].
+// One name is written back as a plain string so existing files round-trip.
+pub(crate) mod one_or_many {
+    use super::OneOrMany;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        names: &Option<Vec<String>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match names.as_deref() {
+            Some([name]) => name.serialize(serializer),
+            _ => names.serialize(serializer),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<String>>, D::Error> {
+        Ok(
+            Option::<OneOrMany>::deserialize(deserializer)?.map(|names| match names {
+                OneOrMany::One(name) => vec![name],
+                OneOrMany::Many(names) => names,
+            }),
+        )
+    }
+}
+
+// The JSON pointer to each of the names, indexed only when there's a list,
+// e.g., "/forcefield" or "/forcefield/1"
+pub(crate) fn one_or_many_paths<'a>(
+    path: &'a str,
+    names: &'a [String],
+) -> impl Iterator<Item = (String, &'a String)> {
+    names.iter().enumerate().map(move |(i, name)| {
+        if names.len() == 1 {
+            (path.to_string(), name)
+        } else {
+            (format!("{path}/{i}"), name)
+        }
+    })
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(untagged)]
 pub enum Numlike {
     Stringy(String),
-    TomlVal(TomlValue),
+    TomlVal(#[schemars(with = "f64")] TomlValue),
+}
+
+impl Numlike {
+    // The stringified value, e.g., "17" whether given as 17 or "17"
+    pub fn as_str(&self) -> String {
+        match self {
+            Numlike::TomlVal(TomlValue::String(val)) | Numlike::Stringy(val) => {
+                val.clone()
+            }
+            Numlike::TomlVal(val) => val.to_string(),
+        }
+    }
+
+    // E.g., 17 for 17 or "17" but None for "17A"
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Numlike::TomlVal(TomlValue::Integer(val)) => Some(*val),
+            Numlike::TomlVal(TomlValue::String(val)) | Numlike::Stringy(val) => {
+                val.trim().parse().ok()
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Numlike::TomlVal(TomlValue::Integer(val)) => Some(*val as f64),
+            Numlike::TomlVal(TomlValue::Float(val)) => Some(*val),
+            Numlike::TomlVal(TomlValue::String(val)) | Numlike::Stringy(val) => {
+                val.trim().parse().ok()
+            }
+            _ => None,
+        }
+    }
+
+    // An integer that reads back the same, e.g., "17" but not "017" or
+    // "17A", becomes a number; anything else stays a string
+    pub fn to_numeric(&self) -> Numlike {
+        let val = self.as_str();
+        match self.as_i64() {
+            Some(num) if num.to_string() == val => {
+                Numlike::TomlVal(TomlValue::Integer(num))
+            }
+            _ => Numlike::Stringy(val),
+        }
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+impl fmt::Display for Numlike {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// A temperature such as 300 or 298.15; whole numbers are written back
+// without a fraction
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Copy, JsonSchema)]
+#[serde(untagged)]
+pub enum Templike {
+    Integer(i64),
+    Float(f64),
+}
+
+impl Templike {
+    pub fn as_f64(self) -> f64 {
+        match self {
+            Templike::Integer(val) => val as f64,
+            Templike::Float(val) => val,
+        }
+    }
+
+    // Always a float as 0 °C is 273.15 K
+    pub fn celsius_to_kelvin(self) -> Self {
+        Templike::Float(self.as_f64() + 273.15)
+    }
+}
+
+impl fmt::Display for Templike {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            Templike::Integer(val) => write!(f, "{val}"),
+            Templike::Float(val) => write!(f, "{val}"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Software {
     pub name: String,
@@ -36,7 +314,55 @@ pub struct Software {
     pub version: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+// One author of a paper, e.g., from a [[papers.author]] table
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Author {
+    pub family: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub given: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(regex(pattern = ORCID_PATTERN))]
+    pub orcid: Option<String>,
+}
+
+impl Author {
+    // Best effort on a legacy author string, e.g., "Ring, A., Manglik, A.
+    // et al." => "Ring"/"A." and "Manglik"/"A."; a name without a comma
+    // such as "Jane Doe" is taken to end with the family name
+    pub fn parse_list(authors: &str) -> Vec<Author> {
+        split_authors(authors)
+            .0
+            .into_iter()
+            .map(|name| {
+                let (family, given) = match name.split_once(',') {
+                    Some((family, given)) => (family.trim(), given.trim()),
+                    _ => match name.rsplit_once(' ') {
+                        Some((given, family)) => (family.trim(), given.trim()),
+                        _ => (name.trim(), ""),
+                    },
+                };
+                Author {
+                    family: family.to_string(),
+                    given: (!given.is_empty()).then(|| given.to_string()),
+                    orcid: None,
+                }
+            })
+            .collect()
+    }
+
+    // As cited, e.g., "Ring, A."
+    pub fn cited_name(&self) -> String {
+        match &self.given {
+            Some(given) => format!("{}, {given}", self.family),
+            _ => self.family.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct RequiredFile {
     pub trajectory_file_name: String,
@@ -45,3 +371,628 @@ pub struct RequiredFile {
 
     pub topology_file_name: String,
 }
+
+// Open a file along with its format from the extension, transparently
+// decompressing ".gz" files by the inner extension, e.g., "example.toml.gz"
+pub(crate) fn open_input(
+    filename: &str,
+) -> Result<(Box<dyn Read>, Format), MdrMetaError> {
+    let path = Path::new(filename);
+    let Some(ext) = path.extension() else {
+        return Err(MdrMetaError::UnknownExtension(None));
+    };
+
+    // E.g., "example.gz" has no extension inside
+    let is_gzip = ext == "gz";
+    let ext = if is_gzip {
+        match path.file_stem().map(Path::new).and_then(Path::extension) {
+            Some(inner) => inner,
+            _ => return Err(MdrMetaError::UnknownExtension(None)),
+        }
+    } else {
+        ext
+    };
+
+    let file = File::open(path)?;
+    let format = match ext.to_str().and_then(Format::from_extension) {
+        Some(format) => format,
+        _ => {
+            return Err(MdrMetaError::UnknownExtension(Some(
+                ext.to_string_lossy().to_string(),
+            )));
+        }
+    };
+
+    if is_gzip {
+        if file.metadata()?.len() == 0 {
+            return Err(MdrMetaError::Empty);
+        }
+        let mut contents = vec![];
+        GzDecoder::new(file)
+            .read_to_end(&mut contents)
+            .map_err(|e| {
+                io::Error::new(e.kind(), format!("Truncated or invalid gzip file: {e}"))
+            })?;
+        Ok((Box::new(io::Cursor::new(contents)), format))
+    } else {
+        Ok((Box::new(file), format))
+    }
+}
+
+// E.g., "C", "°C", or "Celsius"
+pub fn is_celsius(units: &str) -> bool {
+    matches!(
+        units.trim().to_lowercase().as_str(),
+        "c" | "°c" | "degc" | "deg c" | "celsius"
+    )
+}
+
+// The factor converting a density in these units to kg/m^3, e.g., 1000 for
+// "g/cm^3"; None when the units aren't recognized
+pub fn density_factor_to_kg_m3(units: &str) -> Option<f32> {
+    let units = units
+        .trim()
+        .to_lowercase()
+        .replace('³', "3")
+        .replace(['^', ' '], "");
+    match units.as_str() {
+        "kg/m3" | "g/l" => Some(1.),
+        "g/cm3" | "g/ml" | "g/cc" | "kg/l" => Some(1000.),
+        "g/m3" => Some(0.001),
+        _ => None,
+    }
+}
+
+// The factor converting a timestep in these units to femtoseconds, e.g.,
+// 1000 for "ps"; None when the units aren't recognized
+pub fn timestep_factor_to_fs(units: &str) -> Option<f64> {
+    match units.trim().to_lowercase().as_str() {
+        "fs" | "femtosecond" | "femtoseconds" => Some(1.),
+        "ps" | "picosecond" | "picoseconds" => Some(1e3),
+        "ns" | "nanosecond" | "nanoseconds" => Some(1e6),
+        _ => None,
+    }
+}
+
+// The divisor converting a concentration in these units to mol/L, e.g.,
+// 1000 for "mM", as dividing by it is exact where multiplying by 0.001 isn't
+pub fn concentration_divisor_to_mol_l(units: &str) -> Option<f64> {
+    let units = units
+        .trim()
+        .replace('³', "3")
+        .replace('μ', "µ")
+        .replace(['^', ' '], "");
+    match units.as_str() {
+        "M" | "mol/L" | "mol/l" | "mol/dm3" | "mmol/mL" | "mmol/ml" => Some(1.),
+        "mM" | "mmol/L" | "mmol/l" => Some(1e3),
+        "µM" | "uM" | "µmol/L" | "µmol/l" | "umol/L" | "umol/l" => Some(1e6),
+        _ => None,
+    }
+}
+
+// The charge of a known ion, e.g., -1 for "Chloride" or "Cl-"
+pub fn ion_charge(name: &str) -> Option<i32> {
+    let name = name.trim();
+    ION_CHARGES
+        .iter()
+        .find(|(ion, _)| ion.eq_ignore_ascii_case(name))
+        .map(|(_, charge)| *charge)
+}
+
+// Split a page range such as "777–787" or "575-579" into its start and
+// end; a single page such as "42" has no end
+pub fn parse_pages(pages: &str) -> Option<(u32, Option<u32>)> {
+    match pages.split_once(['-', '–', '—']) {
+        Some((start, end)) => {
+            Some((start.trim().parse().ok()?, Some(end.trim().parse().ok()?)))
+        }
+        _ => Some((pages.trim().parse().ok()?, None)),
+    }
+}
+
+// Strip surrounding whitespace and an accidental resolver URL or "doi:"
+// prefix, e.g., "https://doi.org/10.1038/nature12572" => "10.1038/nature12572"
+pub fn canon_doi(doi: &str) -> String {
+    let doi = doi.trim();
+    for prefix in [
+        "https://doi.org/",
+        "http://doi.org/",
+        "https://dx.doi.org/",
+        "http://dx.doi.org/",
+        "doi:",
+    ] {
+        if doi
+            .get(..prefix.len())
+            .is_some_and(|val| val.eq_ignore_ascii_case(prefix))
+        {
+            return doi[prefix.len()..].trim().to_string();
+        }
+    }
+    doi.to_string()
+}
+
+// A replicate index must fall within the total, which must be given
+pub(crate) fn check_replicate(
+    path: &str,
+    replicate: Option<u32>,
+    total_replicates: Option<u32>,
+) -> Option<MetaError> {
+    match (replicate, total_replicates) {
+        (Some(replicate), Some(total)) if !(1..=total).contains(&replicate) => {
+            Some(MetaError::error(
+                path,
+                ErrorCode::ReplicateOutOfRange,
+                format!(r#""{replicate}" must be in the range 1-{total}"#),
+            ))
+        }
+        (Some(_), None) => Some(MetaError::error(
+            path,
+            ErrorCode::ReplicateOutOfRange,
+            "set without total_replicates",
+        )),
+        _ => None,
+    }
+}
+
+// An ORCID listed more than once across the contributors and permissions is
+// almost always a copy-paste mistake. Each is given with its list index, and
+// the first repeat is reported.
+pub(crate) fn check_duplicate_orcids(
+    contributor_orcids: &[(usize, &str)],
+    permission_orcids: &[(usize, &str)],
+) -> Vec<MetaError> {
+    let located: Vec<(String, &str)> = contributor_orcids
+        .iter()
+        .map(|(index, orcid)| (format!("/contributors/{index}/orcid"), *orcid))
+        .chain(permission_orcids.iter().map(|(index, orcid)| {
+            (
+                format!("/simulation_permissions/{index}/user_orcid"),
+                *orcid,
+            )
+        }))
+        .collect();
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (_, orcid) in &located {
+        *counts.entry(orcid).or_default() += 1;
+    }
+
+    let mut errors = vec![];
+    let mut seen = HashSet::new();
+    let mut reported = HashSet::new();
+    for (path, orcid) in &located {
+        if !seen.insert(orcid) && reported.insert(orcid) {
+            errors.push(MetaError::error(
+                path,
+                ErrorCode::DuplicateOrcid,
+                format!(
+                    r#"duplicate ORCID "{orcid}" appears {} times"#,
+                    counts[orcid]
+                ),
+            ));
+        }
+    }
+    errors
+}
+
+// Warn on the second item flagged primary in a section, e.g., "/papers/1/primary"
+pub(crate) fn check_single_primary(
+    section: &str,
+    field: &str,
+    flags: impl Iterator<Item = bool>,
+) -> Option<MetaError> {
+    let flagged: Vec<usize> = flags
+        .enumerate()
+        .filter_map(|(index, primary)| primary.then_some(index))
+        .collect();
+    let index = flagged.get(1)?;
+    Some(MetaError::warning(
+        &format!("/{section}/{index}/{field}"),
+        ErrorCode::MultiplePrimary,
+        format!(
+            "{} {section} are flagged primary; expected one",
+            flagged.len()
+        ),
+    ))
+}
+
+// The item flagged primary, else the first
+pub(crate) fn primary_or_first<T>(
+    items: Option<&Vec<T>>,
+    is_primary: impl Fn(&T) -> bool,
+) -> Option<&T> {
+    let items = items?;
+    items.iter().find(|item| is_primary(item)).or(items.first())
+}
+
+// Check a Kelvin temperature against the configured range
+pub(crate) fn check_temperature(
+    path: &str,
+    temp: Templike,
+    config: &ValidationConfig,
+) -> Option<MetaError> {
+    let (min, max) = config.temp_range();
+    (!(min..=max).contains(&temp.as_f64())).then(|| {
+        MetaError::error(
+            path,
+            ErrorCode::TemperatureOutOfRange,
+            format!(r#""{temp}" must be in the range {min}-{max}"#),
+        )
+    })
+}
+
+// A pH must be on the scale, and one near either end is suspect. The
+// error is for find_errors and the warning for find_warnings.
+pub(crate) fn check_ph(path: &str, ph: f64) -> Option<MetaError> {
+    if !ph.is_finite() {
+        Some(MetaError::error(
+            path,
+            ErrorCode::NotFinite,
+            format!("{ph:?} is not a finite value"),
+        ))
+    } else if !(MIN_PH..=MAX_PH).contains(&ph) {
+        Some(MetaError::error(
+            path,
+            ErrorCode::PhOutOfRange,
+            format!("{ph} must be in the range {MIN_PH}-{MAX_PH}"),
+        ))
+    } else if !(MIN_PLAUSIBLE_PH..=MAX_PLAUSIBLE_PH).contains(&ph) {
+        Some(MetaError::warning(
+            path,
+            ErrorCode::ImplausiblePh,
+            format!(
+                "{ph} is outside the usual range \
+                {MIN_PLAUSIBLE_PH}-{MAX_PLAUSIBLE_PH}"
+            ),
+        ))
+    } else {
+        None
+    }
+}
+
+// The same SMILES under two ligands is almost always a copy-paste mistake.
+// Empty SMILES, e.g., from ligands moved out of initial.ligands, are skipped.
+pub(crate) fn check_duplicate_smiles<'a>(
+    smiles: impl Iterator<Item = &'a str>,
+) -> Vec<MetaError> {
+    let mut first_index: HashMap<&str, usize> = HashMap::new();
+    let mut warnings = vec![];
+    for (index, smiles) in smiles.enumerate() {
+        let smiles = smiles.trim();
+        if smiles.is_empty() {
+            continue;
+        }
+        match first_index.get(smiles) {
+            Some(first) => warnings.push(MetaError::warning(
+                &format!("/ligands/{index}/smiles"),
+                ErrorCode::DuplicateSmiles,
+                format!("same SMILES as /ligands/{first}/smiles"),
+            )),
+            _ => {
+                first_index.insert(smiles, index);
+            }
+        }
+    }
+    warnings
+}
+
+// Check the accession format for the protein ID types we know
+pub(crate) fn check_molecule_id(
+    path: &str,
+    molecule_id_type: &str,
+    molecule_id: &str,
+) -> Option<MetaError> {
+    let result = match molecule_id_type {
+        "PDB" => validate_pdb_id(molecule_id),
+        "Uniprot" => validate_uniprot_id(molecule_id),
+        _ => Ok(()),
+    };
+    result.err().map(|err| err.at(path))
+}
+
+pub(crate) fn check_molecule_id_type(
+    path: &str,
+    molecule_id_type: &str,
+) -> Option<MetaError> {
+    (!MOLECULE_ID_TYPES.contains(&molecule_id_type)).then(|| {
+        MetaError::error(
+            path,
+            ErrorCode::UnknownMoleculeIdType,
+            format!(
+                r#"unrecognized value "{molecule_id_type}", expected one of {}"#,
+                MOLECULE_ID_TYPES.join(", ")
+            ),
+        )
+    })
+}
+
+// The known spelling of a protein ID type given in any case, e.g., "PDB"
+// for "pdb", for lenient parsing
+pub(crate) fn canon_molecule_id_type(molecule_id_type: &str) -> Option<&'static str> {
+    MOLECULE_ID_TYPES
+        .iter()
+        .find(|known| known.eq_ignore_ascii_case(molecule_id_type.trim()))
+        .copied()
+}
+
+// The protein ID types as a JSON Schema enum
+pub(crate) fn molecule_id_type_schema(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({ "type": "string", "enum": MOLECULE_ID_TYPES })
+}
+
+// Warn about a timestep too long to integrate stably
+pub(crate) fn check_timestep(
+    path: &str,
+    timestep_fs: f64,
+    mass_repartitioned: bool,
+) -> Option<MetaError> {
+    if !timestep_fs.is_finite() {
+        None
+    } else if timestep_fs > MAX_HMR_TIMESTEP_FS {
+        Some(MetaError::warning(
+            path,
+            ErrorCode::ImplausibleTimestep,
+            format!("{timestep_fs} fs is not a plausible integration timestep"),
+        ))
+    } else if timestep_fs > MAX_TIMESTEP_FS && !mass_repartitioned {
+        Some(MetaError::warning(
+            path,
+            ErrorCode::LongTimestep,
+            format!(
+                "{timestep_fs} fs exceeds {MAX_TIMESTEP_FS} fs without hydrogen \
+                mass repartitioning"
+            ),
+        ))
+    } else {
+        None
+    }
+}
+
+// E.g., a simulation length or box edge
+pub(crate) fn check_positive(path: &str, val: f64) -> Option<MetaError> {
+    if !val.is_finite() {
+        Some(MetaError::error(
+            path,
+            ErrorCode::NotFinite,
+            format!("{val:?} is not a finite value"),
+        ))
+    } else if val <= 0. {
+        Some(MetaError::error(
+            path,
+            ErrorCode::NotPositive,
+            "must be positive",
+        ))
+    } else {
+        None
+    }
+}
+
+// A free-text name should match one of a curated list, ignoring case
+pub(crate) fn check_known_name(
+    path: &str,
+    code: ErrorCode,
+    name: &str,
+    known: &[&str],
+) -> Option<MetaError> {
+    (!known.iter().any(|val| val.eq_ignore_ascii_case(name))).then(|| {
+        MetaError::warning(path, code, format!(r#"unrecognized value "{name}""#))
+    })
+}
+
+// An absolute http(s) URL with a host
+pub fn validate_url(link: &str) -> bool {
+    Url::parse(link).is_ok_and(|url| {
+        matches!(url.scheme(), "http" | "https")
+            && url.host_str().is_some_and(|host| !host.is_empty())
+    })
+}
+
+// Drop a leading UTF-8 byte order mark and turn CRLF line endings into LF,
+// as some Windows editors write them
+pub(crate) fn normalize_input(contents: &str) -> Cow<'_, str> {
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+    if contents.contains("\r\n") {
+        Cow::Owned(contents.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(contents)
+    }
+}
+
+// YAML opens with a document marker or a "key: value" line where TOML would
+// have a "[table]" or "key = value"
+#[cfg(feature = "yaml")]
+pub(crate) fn looks_like_yaml(contents: &str) -> bool {
+    contents
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .is_some_and(|line| {
+            line.starts_with("---")
+                || (!line.starts_with(['[', '{'])
+                    && line.find(':').is_some_and(|colon| {
+                        line.find('=').is_none_or(|equals| colon < equals)
+                    }))
+        })
+}
+
+// The format of input without a filename, e.g., stdin
+pub(crate) fn sniff_format(contents: &str) -> Format {
+    #[cfg(feature = "yaml")]
+    if looks_like_yaml(contents) {
+        return Format::Yaml;
+    }
+
+    if contents.trim_start().starts_with('{') {
+        Format::Json
+    } else {
+        Format::Toml
+    }
+}
+
+// Whether a "YYYY-MM-DD" string names a real day, e.g., not "2020-02-30"
+pub fn is_calendar_date(date: &str) -> bool {
+    YMD_RE.captures(date).is_some_and(|caps| {
+        match (caps[1].parse(), caps[2].parse(), caps[3].parse()) {
+            (Ok(year), Ok(month), Ok(day)) => {
+                NaiveDate::from_ymd_opt(year, month, day).is_some()
+            }
+            _ => false,
+        }
+    })
+}
+
+// E.g., "2000-02-05" for "Feb 5, 2000"
+pub(crate) fn canon_date(date: &str) -> Result<String, MdrMetaError> {
+    if YMD_RE.is_match(date) && !is_calendar_date(date) {
+        return Err(MdrMetaError::DateParse(format!(
+            r#""{date}" is not a calendar date"#
+        )));
+    }
+    let dt = dateparser::parse_with_timezone(date, &chrono::offset::Utc)
+        .map_err(|e| MdrMetaError::DateParse(e.to_string()))?;
+    Ok(dt.format("%F").to_string())
+}
+
+// Some confusion over dates as quoted strings or unquoted TOML values, but
+// there's no JSON "date" format, so store the "YYYY-MM-DD" string, logging
+// any change. One that won't parse is left as-is for find_errors to report
+// with everything else.
+pub(crate) fn canon_datelike(
+    path: &str,
+    date: &mut Datelike,
+    canon_log: &mut Vec<CanonChange>,
+) {
+    let text = date.to_string();
+    if let Ok(canon) = canon_date(&text) {
+        if canon != text {
+            canon_log.push(CanonChange::new(path, text, &canon));
+        }
+        *date = Datelike::Stringy(canon);
+    }
+}
+
+// Trim the ends and, when collapsing, reduce inner runs of whitespace to one
+// space, logging any change
+pub(crate) fn canon_spaces(
+    path: &str,
+    val: &mut String,
+    collapse: bool,
+    canon_log: &mut Vec<CanonChange>,
+) {
+    let canon = if collapse {
+        val.split_whitespace().collect::<Vec<_>>().join(" ")
+    } else {
+        val.trim().to_string()
+    };
+    if canon != *val {
+        canon_log.push(CanonChange::new(path, val.as_str(), &canon));
+        *val = canon;
+    }
+}
+
+// A value rewritten while canonicalizing a record, with the JSON pointer
+// to it, e.g., "/papers/0/doi"
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct CanonChange {
+    pub path: String,
+
+    pub from: String,
+
+    pub to: String,
+}
+
+impl CanonChange {
+    pub(crate) fn new(
+        path: &str,
+        from: impl Into<String>,
+        to: impl Into<String>,
+    ) -> Self {
+        Self {
+            path: path.to_string(),
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+}
+
+impl fmt::Display for CanonChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} => {}", self.path, self.from, self.to)
+    }
+}
+
+// External identifiers referenced by a record, de-duplicated in order of
+// first appearance
+#[derive(Debug, Default, Serialize, PartialEq, Clone)]
+pub struct ExternalIds {
+    pub pdb: Vec<String>,
+
+    pub uniprot: Vec<String>,
+
+    pub doi: Vec<String>,
+
+    pub orcid: Vec<String>,
+}
+
+impl ExternalIds {
+    pub(crate) fn add_protein(&mut self, molecule_id_type: &str, molecule_id: &str) {
+        match molecule_id_type {
+            "PDB" => push_unique(&mut self.pdb, molecule_id),
+            "Uniprot" => push_unique(&mut self.uniprot, molecule_id),
+            _ => (),
+        }
+    }
+
+    pub(crate) fn add_doi(&mut self, doi: &str) {
+        push_unique(&mut self.doi, doi)
+    }
+
+    pub(crate) fn add_orcid(&mut self, orcid: &str) {
+        push_unique(&mut self.orcid, orcid)
+    }
+}
+
+fn push_unique(ids: &mut Vec<String>, id: &str) {
+    if !ids.iter().any(|val| val == id) {
+        ids.push(id.to_string())
+    }
+}
+
+// Whether DOIs resolved at https://doi.org, kept as JSON between runs.
+// DOIs are case-insensitive, so they're keyed in lowercase.
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Clone)]
+pub struct DoiCache(BTreeMap<String, bool>);
+
+impl DoiCache {
+    // E.g., "~/.cache/mdr-meta/dois.json", following $XDG_CACHE_HOME
+    pub fn default_path() -> Option<PathBuf> {
+        env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+            .map(|dir| dir.join("mdr-meta").join("dois.json"))
+    }
+
+    // A missing or unreadable cache is treated as empty
+    pub fn from_file(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn to_file(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, doi: &str) -> Option<bool> {
+        self.0.get(&doi.to_lowercase()).copied()
+    }
+
+    pub fn insert(&mut self, doi: &str, resolves: bool) {
+        self.0.insert(doi.to_lowercase(), resolves);
+    }
+}
@@ -0,0 +1,56 @@
+use crate::common::{MAX_TEMP_K, MIN_TEMP_K};
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+// Settings that tighten validation beyond the schema itself
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ValidationConfig {
+    // Counted in Unicode scalar values, not bytes; unlimited when absent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_description_chars: Option<usize>,
+
+    // Optional top-level sections that must be present and non-empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_sections: Vec<String>,
+
+    // Require explicit V1 temperature/timestep units rather than assuming
+    // Kelvin and fs; V2 carries the units in the field names
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub require_units: bool,
+
+    // Overrides of common::MIN_TEMP_K/MAX_TEMP_K, e.g., for gas-phase or
+    // cryo simulations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temp_min: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temp_max: Option<f64>,
+
+    // Dates after this are rejected; defaults to the current UTC date and is
+    // only settable in code so tests aren't time-dependent
+    #[serde(skip)]
+    pub today: Option<NaiveDate>,
+}
+
+impl ValidationConfig {
+    pub fn from_file(filename: &str) -> Result<Self> {
+        let contents = fs::read_to_string(filename)?;
+        toml::from_str(&contents).map_err(Into::into)
+    }
+
+    // The accepted temperatures in Kelvin, inclusive
+    pub fn temp_range(&self) -> (f64, f64) {
+        (
+            self.temp_min.unwrap_or(f64::from(MIN_TEMP_K)),
+            self.temp_max.unwrap_or(f64::from(MAX_TEMP_K)),
+        )
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.today
+            .unwrap_or_else(|| chrono::Utc::now().date_naive())
+    }
+}
@@ -0,0 +1,195 @@
+use crate::common::{parse_pages, DoiCache, Numlike};
+use serde_json::Value;
+
+// The bibliographic fields of a CrossRef "works" message, e.g., from
+// https://api.crossref.org/works/10.1038/nature12572
+#[derive(Debug)]
+pub(crate) struct CrossrefWork {
+    pub title: Option<String>,
+    pub authors: Option<String>,
+    pub journal: Option<String>,
+    pub volume: Option<String>,
+    pub number: Option<String>,
+    pub pages: Option<String>,
+    pub year: Option<u32>,
+}
+
+// The paper fields that may be filled in, shared by V1 and V2
+pub(crate) struct PaperFields<'a> {
+    pub title: &'a mut String,
+    pub authors: &'a mut String,
+    pub journal: &'a mut String,
+    pub volume: &'a mut Numlike,
+    pub number: &'a mut Option<Numlike>,
+    pub pages: &'a mut Option<String>,
+    pub year: &'a mut u32,
+}
+
+impl CrossrefWork {
+    pub(crate) fn from_message(message: &Value) -> Self {
+        let text = |key: &str| {
+            match &message[key] {
+                // Titles and journals come as one-element lists
+                Value::Array(vals) => vals.first().and_then(Value::as_str),
+                val => val.as_str(),
+            }
+            .map(|val| val.trim().to_string())
+            .filter(|val| !val.is_empty())
+        };
+
+        let authors: Vec<String> = message["author"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|author| {
+                let family = author["family"].as_str()?;
+                let initials: String = author["given"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .split([' ', '-', '.'])
+                    .filter_map(|name| name.chars().next())
+                    .map(|c| format!("{c}."))
+                    .collect();
+                Some(if initials.is_empty() {
+                    family.to_string()
+                } else {
+                    format!("{family}, {initials}")
+                })
+            })
+            .collect();
+
+        let year = ["published", "issued"].iter().find_map(|key| {
+            message[key]["date-parts"][0][0]
+                .as_u64()
+                .and_then(|year| u32::try_from(year).ok())
+        });
+
+        CrossrefWork {
+            title: text("title"),
+            authors: (!authors.is_empty()).then(|| authors.join(", ")),
+            journal: text("container-title"),
+            volume: text("volume"),
+            number: text("issue"),
+            pages: text("page"),
+            year,
+        }
+    }
+
+    // Only empty or missing fields are filled
+    pub(crate) fn fill(self, paper: PaperFields) {
+        for (field, val) in [
+            (paper.title, self.title),
+            (paper.authors, self.authors),
+            (paper.journal, self.journal),
+        ] {
+            if field.trim().is_empty()
+                && let Some(val) = val
+            {
+                *field = val;
+            }
+        }
+
+        if paper.volume.as_str().trim().is_empty()
+            && let Some(volume) = self.volume
+        {
+            *paper.volume = Numlike::Stringy(volume);
+        }
+
+        if paper.number.is_none()
+            && let Some(number) = self.number
+        {
+            *paper.number = Some(Numlike::Stringy(number));
+        }
+
+        if paper.pages.is_none()
+            && let Some(pages) = self.pages
+        {
+            *paper.pages = Some(pages);
+        }
+
+        if *paper.year == 0
+            && let Some(year) = self.year
+        {
+            *paper.year = year;
+        }
+    }
+}
+
+// V2 keeps the parsed page range alongside "pages"
+pub(crate) fn fill_page_range(
+    pages: &Option<String>,
+    page_start: &mut Option<u32>,
+    page_end: &mut Option<u32>,
+) {
+    if page_start.is_none()
+        && page_end.is_none()
+        && let Some((start, end)) = pages.as_deref().and_then(parse_pages)
+    {
+        *page_start = Some(start);
+        *page_end = end;
+    }
+}
+
+// Fetch the "message" of https://api.crossref.org/works/{doi}. The DOI is
+// one percent-encoded path segment as it may hold "/", "?", "#", etc.
+#[cfg(feature = "network")]
+pub(crate) fn fetch_message(doi: &str) -> anyhow::Result<Value> {
+    let mut url = url::Url::parse("https://api.crossref.org/works")?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow::anyhow!("bad CrossRef URL"))?
+        .push(doi);
+    let response: Value = reqwest::blocking::Client::builder()
+        .user_agent(concat!("libmdrmeta/", env!("CARGO_PKG_VERSION")))
+        .build()?
+        .get(url)
+        .send()?
+        .error_for_status()?
+        .json()?;
+    match response.get("message") {
+        Some(message) => Ok(message.clone()),
+        _ => anyhow::bail!("no message in CrossRef response"),
+    }
+}
+
+// Resolvability of each DOI, answered from the cache where possible. Only
+// definite answers are cached so that unknowns are retried next time.
+pub(crate) fn verify_dois<'a>(
+    dois: impl Iterator<Item = &'a str>,
+    cache: &mut DoiCache,
+    mut resolve: impl FnMut(&str) -> Option<bool>,
+) -> Vec<(String, Option<bool>)> {
+    dois.map(|doi| {
+        let resolves = cache.get(doi).or_else(|| {
+            let resolves = resolve(doi);
+            if let Some(val) = resolves {
+                cache.insert(doi, val);
+            }
+            resolves
+        });
+        (doi.to_string(), resolves)
+    })
+    .collect()
+}
+
+// Registered DOIs redirect from https://doi.org and unknown ones are 404.
+// Anything else, e.g., a timeout or server error, is unknown (None).
+#[cfg(feature = "network")]
+pub(crate) fn resolve_doi(doi: &str) -> Option<bool> {
+    let response = reqwest::blocking::Client::builder()
+        .user_agent(concat!("libmdrmeta/", env!("CARGO_PKG_VERSION")))
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .ok()?
+        .head(format!("https://doi.org/{doi}"))
+        .send()
+        .ok()?;
+    let status = response.status();
+    if status.is_redirection() || status.is_success() {
+        Some(true)
+    } else if status == reqwest::StatusCode::NOT_FOUND {
+        Some(false)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,168 @@
+use serde::Serialize;
+use serde_json::{Result, Value};
+use std::{collections::BTreeSet, fmt};
+
+// List elements are matched by the first of these fields they have, so that
+// reordering or inserting one isn't reported as changing the rest. A list
+// with an element lacking a key, or two sharing one, is compared by position.
+const LIST_KEYS: &[(&str, &[&str])] = &[
+    ("additional_files", &["additional_file_name", "file_name"]),
+    ("contributors", &["orcid", "name"]),
+    ("ligands", &["name"]),
+    ("papers", &["doi", "title"]),
+    ("proteins", &["molecule_id", "pdb_id", "uniprot_id"]),
+    ("simulation_permissions", &["user_orcid"]),
+    ("solvents", &["name"]),
+];
+
+// One scalar that differs, e.g., "/temperature/temperature" from "273" to
+// "310". A field only in the original has no "after" and one only in the
+// changed record no "before". The path is a JSON pointer in which elements
+// of keyed lists are named by key, e.g., "/proteins/7QXR/molecule_id_type",
+// with any "~" or "/" in the key escaped as "~0" or "~1", and others by
+// index.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct FieldChange {
+    pub path: String,
+
+    pub before: Option<String>,
+
+    pub after: Option<String>,
+}
+
+// E.g., "/temperature/temperature: 273 -> 310" or "+ /papers/0/doi: ..."
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.before, &self.after) {
+            (Some(before), Some(after)) => {
+                write!(f, "{}: {before} -> {after}", self.path)
+            }
+            (Some(before), None) => write!(f, "- {}: {before}", self.path),
+            (None, Some(after)) => write!(f, "+ {}: {after}", self.path),
+            (None, None) => write!(f, "{}", self.path),
+        }
+    }
+}
+
+// --------------------------------------------------
+// Compare two records field by field, as serialized
+pub(crate) fn diff<T: Serialize>(old: &T, new: &T) -> Result<Vec<FieldChange>> {
+    let mut changes = vec![];
+    let (old, new) = (serde_json::to_value(old)?, serde_json::to_value(new)?);
+    diff_values("", &old, &new, &mut changes);
+    Ok(changes)
+}
+
+// --------------------------------------------------
+fn diff_values(path: &str, old: &Value, new: &Value, changes: &mut Vec<FieldChange>) {
+    match (old, new) {
+        (Value::Object(old_map), Value::Object(new_map)) => {
+            let keys: BTreeSet<_> = old_map.keys().chain(new_map.keys()).collect();
+            for key in keys {
+                let key_path = pointer(path, key);
+                diff_optional(&key_path, old_map.get(key), new_map.get(key), changes);
+            }
+        }
+        (Value::Array(old_vals), Value::Array(new_vals)) => {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            match (list_keys(name, old_vals), list_keys(name, new_vals)) {
+                (Some(old_keys), Some(new_keys)) => {
+                    for (index, key) in old_keys.iter().enumerate() {
+                        let new = new_keys
+                            .iter()
+                            .position(|other| other == key)
+                            .map(|pos| &new_vals[pos]);
+                        let key_path = pointer(path, key);
+                        diff_optional(&key_path, Some(&old_vals[index]), new, changes);
+                    }
+                    for (index, key) in new_keys.iter().enumerate() {
+                        if !old_keys.contains(key) {
+                            let key_path = pointer(path, key);
+                            list_leaves(&key_path, &new_vals[index], false, changes);
+                        }
+                    }
+                }
+                _ => {
+                    for index in 0..old_vals.len().max(new_vals.len()) {
+                        diff_optional(
+                            &format!("{path}/{index}"),
+                            old_vals.get(index),
+                            new_vals.get(index),
+                            changes,
+                        );
+                    }
+                }
+            }
+        }
+        _ if old != new => changes.push(FieldChange {
+            path: path.to_string(),
+            before: Some(text(old)),
+            after: Some(text(new)),
+        }),
+        _ => (),
+    }
+}
+
+// --------------------------------------------------
+fn diff_optional(
+    path: &str,
+    old: Option<&Value>,
+    new: Option<&Value>,
+    changes: &mut Vec<FieldChange>,
+) {
+    match (old, new) {
+        (Some(old), Some(new)) => diff_values(path, old, new, changes),
+        (Some(old), None) => list_leaves(path, old, true, changes),
+        (None, Some(new)) => list_leaves(path, new, false, changes),
+        _ => (),
+    }
+}
+
+// --------------------------------------------------
+// The key of each element when every one has a distinct key
+fn list_keys(name: &str, vals: &[Value]) -> Option<Vec<String>> {
+    let (_, fields) = LIST_KEYS.iter().find(|(list, _)| *list == name)?;
+    let keys: Vec<String> = vals
+        .iter()
+        .map(|val| fields.iter().find_map(|field| val.get(field).map(text)))
+        .collect::<Option<_>>()?;
+    let distinct: BTreeSet<_> = keys.iter().collect();
+    (distinct.len() == keys.len()).then_some(keys)
+}
+
+// --------------------------------------------------
+// Every scalar in a removed or added value
+fn list_leaves(path: &str, val: &Value, removed: bool, changes: &mut Vec<FieldChange>) {
+    match val {
+        Value::Object(map) => {
+            for (key, val) in map {
+                list_leaves(&pointer(path, key), val, removed, changes);
+            }
+        }
+        Value::Array(vals) => {
+            for (index, val) in vals.iter().enumerate() {
+                list_leaves(&format!("{path}/{index}"), val, removed, changes);
+            }
+        }
+        _ => changes.push(FieldChange {
+            path: path.to_string(),
+            before: removed.then(|| text(val)),
+            after: (!removed).then(|| text(val)),
+        }),
+    }
+}
+
+// --------------------------------------------------
+// The JSON pointer to a key within "path", escaped per RFC 6901
+fn pointer(path: &str, key: &str) -> String {
+    format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
+// --------------------------------------------------
+// Strings as they are, without JSON quotes
+fn text(val: &Value) -> String {
+    match val {
+        Value::String(val) => val.clone(),
+        _ => val.to_string(),
+    }
+}
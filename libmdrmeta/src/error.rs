@@ -0,0 +1,258 @@
+use serde::Serialize;
+use std::{fmt, io};
+
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    DescriptionTooLong,
+    DuplicateFileName,
+    DuplicateOrcid,
+    DuplicateSmiles,
+    EditWithoutView,
+    EmptyDescription,
+    ForcefieldIncomplete,
+    FutureDate,
+    ImplausibleDensity,
+    ImplausiblePh,
+    ImplausibleTimestep,
+    InvalidDate,
+    InvalidDoi,
+    InvalidMdrepoId,
+    InvalidOrcid,
+    InvalidPdbId,
+    InvalidSmiles,
+    InvalidUniprotId,
+    InvalidUrl,
+    InvalidYear,
+    LeadNotContributor,
+    LongTimestep,
+    MissingSection,
+    MissingSmiles,
+    MdrepoIdMismatch,
+    MissingUnits,
+    MultiplePrimary,
+    Negative,
+    NotFinite,
+    NotPositive,
+    PagesOutOfOrder,
+    PhOutOfRange,
+    PossiblyCelsius,
+    ReplicateOutOfRange,
+    TemperatureOutOfRange,
+    UnknownConcentration,
+    UnknownField,
+    UnknownForcefield,
+    UnknownMoleculeIdType,
+    UnknownProtonationMethod,
+    UnknownSection,
+    UnknownUnits,
+    UnknownWaterModel,
+    WaterInconsistent,
+}
+
+// Findings that auto_fix can correct without guessing
+pub const AUTO_FIXABLE: [ErrorCode; 2] =
+    [ErrorCode::EmptyDescription, ErrorCode::WaterInconsistent];
+
+// A single validation finding; warnings are reported but only fail
+// validation in strict mode
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct MetaError {
+    // JSON pointer to the field, e.g., "/contributors/3/orcid"
+    pub path: String,
+
+    pub message: String,
+
+    pub severity: Severity,
+
+    pub code: ErrorCode,
+}
+
+impl MetaError {
+    pub fn error(path: &str, code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            path: path.to_string(),
+            message: message.into(),
+            severity: Severity::Error,
+            code,
+        }
+    }
+
+    pub fn warning(path: &str, code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(path, code, message)
+        }
+    }
+
+    pub fn is_warning(&self) -> bool {
+        self.severity == Severity::Warning
+    }
+}
+
+// Renders as the old "field: message" lines
+impl fmt::Display for MetaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+// Why a standalone value failed one of the validators in `validate`
+#[derive(Debug, PartialEq, Clone)]
+pub struct ValidateError {
+    pub code: ErrorCode,
+
+    pub message: String,
+}
+
+impl ValidateError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    // Report the failure as an error on a field of the metadata
+    pub fn at(self, path: &str) -> MetaError {
+        MetaError::error(path, self.code, self.message)
+    }
+}
+
+impl fmt::Display for ValidateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ValidateError {}
+
+// Why metadata couldn't be read or converted, so callers can tell, e.g.,
+// an empty file from bad TOML
+#[derive(Debug, thiserror::Error)]
+pub enum MdrMetaError {
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    // None when the filename has no extension at all
+    #[error("{}", match .0 {
+        Some(ext) => format!(r#"Unknown file extension "{ext}""#),
+        _ => "No file extension".to_string(),
+    })]
+    UnknownExtension(Option<String>),
+
+    #[error("File is empty")]
+    Empty,
+
+    #[error("initial.date {0}")]
+    DateParse(String),
+
+    // A paper volume or number of another type, e.g., a boolean
+    #[error("{field} must be a string or number, got {found}")]
+    NotNumlike { field: String, found: String },
+
+    #[error("Missing required_files")]
+    MissingRequiredFiles,
+
+    // V2 requires the concentration of each solvent, named here
+    #[error(r#"Missing ion_concentration for solvent "{0}""#)]
+    MissingConcentration(String),
+}
+
+// Where a TOML, JSON, or YAML document failed to parse, counted from 1. As
+// with serde_json, a line of 0 means the parser didn't say where.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub line: usize,
+
+    pub column: usize,
+
+    pub message: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(message: impl Into<String>) -> Self {
+        ParseError {
+            line: 0,
+            column: 0,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn from_toml(err: toml::de::Error, contents: &str) -> Self {
+        let Some(span) = err.span() else {
+            return Self::new(err.to_string().trim());
+        };
+        let before = &contents[..span.start.min(contents.len())];
+        let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+        ParseError {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            message: err.message().trim().to_string(),
+        }
+    }
+
+    pub(crate) fn from_json(err: serde_json::Error) -> Self {
+        let message = err.to_string();
+        if err.line() == 0 {
+            return Self::new(message);
+        }
+        let location = format!(" at line {} column {}", err.line(), err.column());
+        ParseError {
+            line: err.line(),
+            column: err.column(),
+            message: message
+                .strip_suffix(&location)
+                .unwrap_or(&message)
+                .to_string(),
+        }
+    }
+
+    #[cfg(feature = "yaml")]
+    pub(crate) fn from_yaml(err: serde_yaml::Error) -> Self {
+        let message = err.to_string();
+        let Some(location) = err.location() else {
+            return Self::new(message);
+        };
+        let suffix =
+            format!(" at line {} column {}", location.line(), location.column());
+        ParseError {
+            line: location.line(),
+            column: location.column(),
+            message: message
+                .strip_suffix(&suffix)
+                .unwrap_or(&message)
+                .to_string(),
+        }
+    }
+}
+
+// E.g., "42:7: invalid type", to follow a filename
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}:{}: {}", self.line, self.column, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// Compatibility with the old (field, message) tuples
+impl From<MetaError> for (String, String) {
+    fn from(err: MetaError) -> Self {
+        (err.path, err.message)
+    }
+}
@@ -0,0 +1,335 @@
+use crate::{
+    common::{normalize_input, open_input, sniff_format, Format},
+    error::{ErrorCode, MdrMetaError, MetaError, ParseError},
+    metav1::MetaV1,
+};
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Read,
+};
+
+// Serde aliases and the fields they stand for, as the schema only lists
+// the latter
+const ALIASES: [(&str, &str); 2] =
+    [("author", "author_list"), ("primary", "is_primary")];
+
+// Keys that a strict parse would reject, by JSON pointer (e.g.,
+// "/initial/experimental_flag"), with the values they held
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct UnknownFields(pub BTreeMap<String, Value>);
+
+impl UnknownFields {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn warnings(&self) -> Vec<MetaError> {
+        self.0
+            .keys()
+            .map(|path| {
+                MetaError::warning(path, ErrorCode::UnknownField, "unrecognized field")
+            })
+            .collect()
+    }
+
+    // Put the keys back where they were found. One whose parent is gone,
+    // e.g., a list that canonicalization shortened, is dropped.
+    pub fn restore(&self, value: &mut Value) {
+        for (path, val) in &self.0 {
+            let Some((parent, key)) = path.rsplit_once('/') else {
+                continue;
+            };
+            if let Some(map) = value.pointer_mut(parent).and_then(Value::as_object_mut)
+            {
+                map.insert(key.replace("~1", "/").replace("~0", "~"), val.clone());
+            }
+        }
+    }
+}
+
+// A V1 record that keeps every key. Only the required tables are broken
+// out; the rest of the record, known or not, is in "extra".
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Clone)]
+pub struct MetaLenient {
+    #[serde(default)]
+    pub initial: Map<String, Value>,
+
+    #[serde(default)]
+    pub software: Map<String, Value>,
+
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl MetaLenient {
+    pub fn from_string(contents: &str) -> Result<Self, MdrMetaError> {
+        let contents = normalize_input(contents);
+        if contents.trim().is_empty() {
+            return Err(MdrMetaError::Empty);
+        }
+        let value = to_value(&contents, sniff_format(&contents))?;
+        serde_json::from_value(value).map_err(|e| ParseError::new(e.to_string()).into())
+    }
+
+    pub fn from_file(filename: &str) -> Result<Self, MdrMetaError> {
+        let (mut reader, format) = open_input(filename)?;
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        let contents = normalize_input(&contents);
+        if contents.trim().is_empty() {
+            return Err(MdrMetaError::Empty);
+        }
+        let value = to_value(&contents, format)?;
+        serde_json::from_value(value).map_err(|e| ParseError::new(e.to_string()).into())
+    }
+
+    // Move each key that belongs to exactly one other table of the schema
+    // there, e.g., "software.scientific_goal" to "initial.scientific_goal",
+    // unless that table is missing or already has the key. Returns the
+    // (from, to) paths of the moves.
+    pub fn relocate(&mut self) -> Vec<(String, String)> {
+        let schema = MetaV1::json_schema();
+        let top_level: Vec<&String> = schema["properties"]
+            .as_object()
+            .map(|props| props.keys().collect())
+            .unwrap_or_default();
+        let tables: Vec<(&str, Vec<&String>)> = top_level
+            .iter()
+            .filter_map(|name| {
+                resolve(&schema["properties"][name.as_str()], &schema)
+                    .into_iter()
+                    .find_map(|cand| cand["properties"].as_object())
+                    .map(|props| (name.as_str(), props.keys().collect()))
+            })
+            .collect();
+
+        // Stray keys at the top level, then in each table
+        let mut strays: Vec<(Option<&str>, String)> = self
+            .extra
+            .keys()
+            .filter(|key| !top_level.contains(key))
+            .map(|key| (None, key.clone()))
+            .collect();
+        for (name, fields) in &tables {
+            if let Some(table) = self.table(name) {
+                strays.extend(
+                    table
+                        .keys()
+                        .filter(|key| !fields.contains(key))
+                        .map(|key| (Some(*name), key.clone())),
+                );
+            }
+        }
+        strays.sort();
+
+        let mut moved = vec![];
+        for (from, key) in strays {
+            let homes: Vec<&str> = tables
+                .iter()
+                .filter(|(name, fields)| Some(*name) != from && fields.contains(&&key))
+                .map(|(name, _)| *name)
+                .collect();
+            let [home] = homes[..] else {
+                continue;
+            };
+            if self
+                .table(home)
+                .is_none_or(|table| table.contains_key(&key))
+            {
+                continue;
+            }
+
+            let val = match from {
+                Some(name) => self.table_mut(name).and_then(|table| table.remove(&key)),
+                _ => self.extra.remove(&key),
+            };
+            if let (Some(val), Some(table)) = (val, self.table_mut(home)) {
+                table.insert(key.clone(), val);
+                let from = from.map_or(key.clone(), |name| format!("{name}.{key}"));
+                moved.push((from, format!("{home}.{key}")));
+            }
+        }
+        moved
+    }
+
+    fn table(&self, name: &str) -> Option<&Map<String, Value>> {
+        match name {
+            "initial" => Some(&self.initial),
+            "software" => Some(&self.software),
+            _ => self.extra.get(name).and_then(Value::as_object),
+        }
+    }
+
+    fn table_mut(&mut self, name: &str) -> Option<&mut Map<String, Value>> {
+        match name {
+            "initial" => Some(&mut self.initial),
+            "software" => Some(&mut self.software),
+            _ => self.extra.get_mut(name).and_then(Value::as_object_mut),
+        }
+    }
+}
+
+// --------------------------------------------------
+// Parse into the schema's type after moving aside any keys its structs
+// would deny. When there are none, the text is parsed as usual so that
+// errors keep their line and column.
+pub(crate) fn from_str_lenient<T: DeserializeOwned + JsonSchema>(
+    contents: &str,
+    format: Format,
+) -> Result<(T, UnknownFields), MdrMetaError> {
+    let contents = normalize_input(contents);
+    if contents.trim().is_empty() {
+        return Err(MdrMetaError::Empty);
+    }
+
+    let mut value = to_value(&contents, format)?;
+    let schema = schemars::schema_for!(T).to_value();
+    let mut unknown = UnknownFields::default();
+    strip_unknown(&mut value, &schema, &schema, "", &mut unknown);
+    let meta = if unknown.is_empty() {
+        match format {
+            Format::Json => {
+                serde_json::from_str(&contents).map_err(ParseError::from_json)?
+            }
+            Format::Toml => toml::from_str(&contents)
+                .map_err(|e| ParseError::from_toml(e, &contents))?,
+            #[cfg(feature = "yaml")]
+            Format::Yaml => {
+                serde_yaml::from_str(&contents).map_err(ParseError::from_yaml)?
+            }
+        }
+    } else {
+        serde_json::from_value(value).map_err(|e| ParseError::new(e.to_string()))?
+    };
+    Ok((meta, unknown))
+}
+
+// --------------------------------------------------
+pub(crate) fn from_value_lenient<T: DeserializeOwned + JsonSchema>(
+    mut value: Value,
+) -> Result<(T, UnknownFields), MdrMetaError> {
+    let schema = schemars::schema_for!(T).to_value();
+    let mut unknown = UnknownFields::default();
+    strip_unknown(&mut value, &schema, &schema, "", &mut unknown);
+    let meta =
+        serde_json::from_value(value).map_err(|e| ParseError::new(e.to_string()))?;
+    Ok((meta, unknown))
+}
+
+// --------------------------------------------------
+// TOML dates become strings, as they would be written in JSON
+fn to_value(contents: &str, format: Format) -> Result<Value, MdrMetaError> {
+    match format {
+        Format::Json => {
+            serde_json::from_str(contents).map_err(|e| ParseError::from_json(e).into())
+        }
+        Format::Toml => toml::from_str::<toml::Table>(contents)
+            .map(|table| toml_to_json(toml::Value::Table(table)))
+            .map_err(|e| ParseError::from_toml(e, contents).into()),
+        #[cfg(feature = "yaml")]
+        Format::Yaml => {
+            serde_yaml::from_str(contents).map_err(|e| ParseError::from_yaml(e).into())
+        }
+    }
+}
+
+// --------------------------------------------------
+fn toml_to_json(val: toml::Value) -> Value {
+    match val {
+        toml::Value::String(text) => Value::String(text),
+        toml::Value::Integer(num) => Value::from(num),
+        toml::Value::Float(num) => Value::from(num),
+        toml::Value::Boolean(flag) => Value::Bool(flag),
+        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Array(vals) => vals.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, val)| (key, toml_to_json(val)))
+                .collect::<Map<_, _>>(),
+        ),
+    }
+}
+
+// --------------------------------------------------
+// Walk the value alongside its schema, removing the keys of objects that
+// don't allow additional properties
+fn strip_unknown(
+    value: &mut Value,
+    schema: &Value,
+    root: &Value,
+    path: &str,
+    unknown: &mut UnknownFields,
+) {
+    let candidates = resolve(schema, root);
+    match value {
+        Value::Object(map) => {
+            let denies = candidates
+                .iter()
+                .any(|cand| cand["additionalProperties"] == Value::Bool(false));
+            let keys: Vec<String> = map.keys().cloned().collect();
+            for key in keys {
+                let name = ALIASES
+                    .iter()
+                    .find(|(alias, _)| *alias == key)
+                    .map_or(key.as_str(), |(_, name)| name);
+                let key_path =
+                    format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"));
+                let prop = candidates.iter().find_map(|cand| {
+                    cand["properties"]
+                        .get(&key)
+                        .or(cand["properties"].get(name))
+                });
+                match prop {
+                    Some(prop) => {
+                        if let Some(val) = map.get_mut(&key) {
+                            strip_unknown(val, prop, root, &key_path, unknown);
+                        }
+                    }
+                    None if denies => {
+                        if let Some(val) = map.remove(&key) {
+                            unknown.0.insert(key_path, val);
+                        }
+                    }
+                    None => (),
+                }
+            }
+        }
+        Value::Array(vals) => {
+            if let Some(items) = candidates.iter().find_map(|cand| cand.get("items")) {
+                for (index, val) in vals.iter_mut().enumerate() {
+                    strip_unknown(
+                        val,
+                        items,
+                        root,
+                        &format!("{path}/{index}"),
+                        unknown,
+                    );
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+// --------------------------------------------------
+// The schemas a value might match, following "$ref" and "anyOf"
+fn resolve<'a>(schema: &'a Value, root: &'a Value) -> Vec<&'a Value> {
+    if let Some(name) = schema["$ref"]
+        .as_str()
+        .and_then(|reference| reference.strip_prefix("#/$defs/"))
+    {
+        return resolve(&root["$defs"][name], root);
+    }
+
+    let mut candidates = vec![schema];
+    for key in ["anyOf", "oneOf", "allOf"] {
+        for sub in schema[key].as_array().into_iter().flatten() {
+            candidates.extend(resolve(sub, root));
+        }
+    }
+    candidates
+}
@@ -1,3 +1,172 @@
+mod cite;
 pub mod common;
+pub mod config;
+mod crossref;
+pub mod diff;
+pub mod error;
+pub mod lenient;
 pub mod metav1;
 pub mod metav2;
+mod protein_names;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+pub mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+use anyhow::Result;
+use common::{ExternalIds, Format, SchemaVersion};
+use config::ValidationConfig;
+use diff::FieldChange;
+use error::MetaError;
+use metav2::MetaV2;
+use std::io::Write;
+
+// The current schema; validation lives in one place so `Meta` and `MetaV1`
+// can never disagree (e.g., on the temperature range in `common`)
+pub use metav1::MetaV1 as Meta;
+
+// Metadata in whichever schema a file was written
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnyMeta {
+    V1(Meta),
+    V2(MetaV2),
+}
+
+impl AnyMeta {
+    pub fn version(&self) -> SchemaVersion {
+        match self {
+            AnyMeta::V1(_) => SchemaVersion::V1,
+            AnyMeta::V2(_) => SchemaVersion::V2,
+        }
+    }
+
+    pub fn mdrepo_id(&self) -> Option<&str> {
+        match self {
+            AnyMeta::V1(meta) => meta.mdrepo_id.as_deref(),
+            AnyMeta::V2(meta) => meta.mdrepo_id.as_deref(),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        match self {
+            AnyMeta::V1(meta) => meta.to_json(),
+            AnyMeta::V2(meta) => meta.to_json(),
+        }
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        match self {
+            AnyMeta::V1(meta) => meta.to_toml(),
+            AnyMeta::V2(meta) => meta.to_toml(),
+        }
+    }
+
+    pub fn to_writer<W: Write>(&self, writer: W, format: Format) -> Result<()> {
+        match self {
+            AnyMeta::V1(meta) => meta.to_writer(writer, format),
+            AnyMeta::V2(meta) => meta.to_writer(writer, format),
+        }
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn to_yaml(&self) -> Result<String> {
+        match self {
+            AnyMeta::V1(meta) => meta.to_yaml(),
+            AnyMeta::V2(meta) => meta.to_yaml(),
+        }
+    }
+
+    pub fn papers_to_bibtex(&self) -> String {
+        match self {
+            AnyMeta::V1(meta) => meta.papers_to_bibtex(),
+            AnyMeta::V2(meta) => meta.papers_to_bibtex(),
+        }
+    }
+
+    pub fn papers_to_ris(&self) -> String {
+        match self {
+            AnyMeta::V1(meta) => meta.papers_to_ris(),
+            AnyMeta::V2(meta) => meta.papers_to_ris(),
+        }
+    }
+
+    pub fn external_ids(&self) -> ExternalIds {
+        match self {
+            AnyMeta::V1(meta) => meta.external_ids(),
+            AnyMeta::V2(meta) => meta.external_ids(),
+        }
+    }
+
+    pub fn ionic_strength(&self) -> Option<f64> {
+        match self {
+            AnyMeta::V1(meta) => meta.ionic_strength(),
+            AnyMeta::V2(meta) => meta.ionic_strength(),
+        }
+    }
+
+    pub fn all_orcids(&self) -> Vec<&str> {
+        match self {
+            AnyMeta::V1(meta) => meta.all_orcids(),
+            AnyMeta::V2(meta) => meta.all_orcids(),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        match self {
+            AnyMeta::V1(meta) => meta.is_valid(),
+            AnyMeta::V2(meta) => meta.is_valid(),
+        }
+    }
+
+    pub fn find_errors(&self) -> Vec<MetaError> {
+        self.find_errors_with_config(&ValidationConfig::default())
+    }
+
+    pub fn find_errors_with_config(&self, config: &ValidationConfig) -> Vec<MetaError> {
+        match self {
+            AnyMeta::V1(meta) => meta.find_errors_with_config(config),
+            AnyMeta::V2(meta) => meta.find_errors_with_config(config),
+        }
+    }
+
+    pub fn find_warnings(&self) -> Vec<MetaError> {
+        match self {
+            AnyMeta::V1(meta) => meta.find_warnings(),
+            AnyMeta::V2(meta) => meta.find_warnings(),
+        }
+    }
+
+    pub fn auto_fix(&mut self) -> Vec<MetaError> {
+        match self {
+            AnyMeta::V1(meta) => meta.auto_fix(),
+            AnyMeta::V2(meta) => meta.auto_fix(),
+        }
+    }
+
+    pub fn canonicalize(&mut self) {
+        match self {
+            AnyMeta::V1(meta) => meta.canonicalize(),
+            AnyMeta::V2(meta) => meta.canonicalize(),
+        }
+    }
+
+    pub fn numeric_volumes(&mut self) {
+        match self {
+            AnyMeta::V1(meta) => meta.numeric_volumes(),
+            AnyMeta::V2(meta) => meta.numeric_volumes(),
+        }
+    }
+
+    // "other" is first converted to this record's version
+    pub fn diff(&self, other: &AnyMeta) -> Result<Vec<FieldChange>> {
+        match (self, other) {
+            (AnyMeta::V1(meta), AnyMeta::V1(other)) => meta.diff(other),
+            (AnyMeta::V1(meta), AnyMeta::V2(other)) => meta.diff(&other.to_v1()),
+            (AnyMeta::V2(meta), AnyMeta::V1(other)) => meta.diff(&other.to_v2()?),
+            (AnyMeta::V2(meta), AnyMeta::V2(other)) => meta.diff(other),
+        }
+    }
+}
@@ -1,17 +1,76 @@
+#[cfg(feature = "yaml")]
+use crate::common::looks_like_yaml;
 use crate::{
-    common::{Datelike, Numlike, RequiredFile, Software, MAX_TEMP_K, MIN_TEMP_K},
-    metav2::MetaV2,
+    cite::{self, Citation},
+    common::{
+        canon_date, canon_datelike, canon_doi, canon_molecule_id_type, canon_spaces,
+        check_duplicate_orcids, check_duplicate_smiles, check_known_name,
+        check_molecule_id, check_molecule_id_type, check_ph, check_positive,
+        check_replicate, check_single_primary, check_temperature, check_timestep,
+        concentration_divisor_to_mol_l, density_factor_to_kg_m3, ion_charge,
+        is_celsius, molecule_id_type_schema, normalize_input, one_or_many,
+        one_or_many_paths, open_input, parse_pages, primary_or_first, sniff_format,
+        timestep_factor_to_fs, validate_url, Author, CanonChange, Datelike, DoiCache,
+        ExternalIds, Format, Numlike, OneOrMany, RequiredFile, SchemaVersion, Software,
+        Templike, FORCEFIELDS, MAX_PH, MAX_TEMP_K, MAX_WATER_DENSITY_KG_M3, MIN_PH,
+        MIN_TEMP_K, MIN_WATER_DENSITY_KG_M3, PROTONATION_METHODS, WATER_MODELS,
+    },
+    config::ValidationConfig,
+    crossref::{CrossrefWork, PaperFields},
+    diff::{self, FieldChange},
+    error::{ErrorCode, MdrMetaError, MetaError, ParseError, AUTO_FIXABLE},
+    lenient::{self, MetaLenient, UnknownFields},
+    metav2::{self, MetaV2},
+    validate::{
+        validate_date, validate_doi, validate_mdrepo_id, validate_orcid,
+        validate_smiles, DATE_PATTERN, DOI_PATTERN, MDREPO_ID_PATTERN, ORCID_PATTERN,
+    },
+    AnyMeta,
 };
-use anyhow::{anyhow, bail, Result};
-use regex::Regex;
+use anyhow::{bail, Result};
+use chrono::{Datelike as _, NaiveDate};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::{fs, path::Path};
+use std::{
+    collections::HashSet,
+    fmt,
+    io::{Read, Write},
+    iter,
+    str::FromStr,
+};
 use toml::value::Value as TomlValue;
-
-#[derive(Debug, Deserialize, Serialize)]
+use toml_edit::{value, DocumentMut, Item, TableLike, Value as EditValue};
+
+// The optional fields counted by completeness() and their weights; a field's
+// share of the score is its weight over the total (31). The MDRepo ID and
+// permissions are left out as they're assigned rather than described.
+pub const COMPLETENESS_WEIGHTS: &[(&str, f32)] = &[
+    ("proteins", 3.),
+    ("papers", 3.),
+    ("required_files", 3.),
+    ("initial.description", 2.),
+    ("forcefield", 2.),
+    ("temperature", 2.),
+    ("timestep_information", 2.),
+    ("water", 2.),
+    ("contributors", 2.),
+    ("initial.short_description", 1.),
+    ("initial.scientific_goal", 1.),
+    ("initial.commands", 1.),
+    ("initial.external_link", 1.),
+    ("software.version", 1.),
+    ("ligands", 1.),
+    ("solvents", 1.),
+    ("protonation_method", 1.),
+    ("replicates", 1.),
+    ("additional_files", 1.),
+];
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct MetaV1 {
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(regex(pattern = MDREPO_ID_PATTERN))]
     pub mdrepo_id: Option<String>,
 
     pub initial: Initial,
@@ -61,7 +120,7 @@ pub struct MetaV1 {
     pub simulation_permissions: Option<Vec<Permission>>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Initial {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -73,8 +132,10 @@ pub struct Initial {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub external_link: Option<String>,
 
+    #[schemars(regex(pattern = ORCID_PATTERN))]
     pub lead_contributor_orcid: String,
 
+    #[schemars(with = "String", regex(pattern = DATE_PATTERN))]
     pub date: Datelike,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -86,6 +147,13 @@ pub struct Initial {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub scientific_goal: Option<String>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simulation_length_ns: Option<f64>,
+
+    // The x, y, and z edges of the periodic box
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub box_dimensions_nm: Option<[f64; 3]>,
+
     // TODO: Remove?
     // These are only here because people put them in the wrong place
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -95,7 +163,7 @@ pub struct Initial {
     pub solvents: Option<Vec<String>>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct AdditionalFile {
     pub additional_file_type: String,
@@ -106,12 +174,13 @@ pub struct AdditionalFile {
     pub additional_file_description: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Contributor {
     pub name: String,
 
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(regex(pattern = ORCID_PATTERN))]
     pub orcid: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -121,19 +190,22 @@ pub struct Contributor {
     pub institution: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Forcefield {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub forcefield: Option<String>,
+    // E.g., a protein forcefield and a ligand parameterization
+    #[serde(default, with = "one_or_many", skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<OneOrMany>")]
+    pub forcefield: Option<Vec<String>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub forcefield_comments: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Permission {
+    #[schemars(regex(pattern = ORCID_PATTERN))]
     pub user_orcid: String,
 
     pub can_edit: bool,
@@ -141,21 +213,34 @@ pub struct Permission {
     pub can_view: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Protonation {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub protonation_method: Option<String>,
+
+    // The pH the protonation states were assigned for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = MIN_PH, max = MAX_PH))]
+    pub ph: Option<f64>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Timestep {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub integration_time_step: Option<f64>,
+
+    // Assumed to be "fs" when absent; to_canon converts "ps" and "ns" to "fs"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestep_units: Option<String>,
+
+    // Allows timesteps above 4 fs, up to common::MAX_HMR_TIMESTEP_FS
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hydrogen_mass_repartitioning: Option<bool>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Paper {
     #[serde(skip_serializing_if = "Option::is_none", alias = "primary")]
@@ -163,8 +248,12 @@ pub struct Paper {
 
     pub title: String,
 
+    // Kept as given; "author_list" is preferred for citations when present
     pub authors: String,
 
+    #[serde(skip_serializing_if = "Option::is_none", alias = "author")]
+    pub author_list: Option<Vec<Author>>,
+
     pub journal: String,
 
     pub volume: Numlike,
@@ -178,17 +267,33 @@ pub struct Paper {
     pub pages: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(regex(pattern = DOI_PATTERN))]
     pub doi: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+impl Paper {
+    pub fn volume_str(&self) -> String {
+        self.volume.as_str()
+    }
+
+    pub fn number_str(&self) -> Option<String> {
+        self.number.as_ref().map(Numlike::as_str)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Temperature {
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub temperature: Option<u32>,
+    #[schemars(with = "Option<f64>", range(min = MIN_TEMP_K, max = MAX_TEMP_K))]
+    pub temperature: Option<Templike>,
+
+    // Assumed to be "K" when absent; "C" is converted to Kelvin by to_canon
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature_units: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Ligand {
     #[serde(skip_serializing_if = "Option::is_none", alias = "primary")]
@@ -199,7 +304,7 @@ pub struct Ligand {
     pub smiles: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Replicates {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -207,9 +312,14 @@ pub struct Replicates {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub replicate: Option<u32>,
+
+    // The replicates sharing this metadata, set in place of "replicate" when
+    // their files are merged
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merged_replicates: Option<Vec<u32>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 #[serde(untagged)]
 pub enum Protein {
     ProteinOldPDB {
@@ -228,24 +338,66 @@ pub enum Protein {
         #[serde(skip_serializing_if = "Option::is_none")]
         primary: Option<bool>,
 
+        #[schemars(schema_with = "molecule_id_type_schema")]
         molecule_id_type: String,
 
         molecule_id: String,
     },
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl Protein {
+    pub fn is_primary(&self) -> bool {
+        match self {
+            Protein::ProteinOldPDB { primary, .. }
+            | Protein::ProteinOldUniprot { primary, .. }
+            | Protein::ProteinNew { primary, .. } => primary.unwrap_or(false),
+        }
+    }
+
+    pub fn molecule_id(&self) -> &str {
+        match self {
+            Protein::ProteinOldPDB { pdb_id: id, .. }
+            | Protein::ProteinOldUniprot { uniprot_id: id, .. }
+            | Protein::ProteinNew {
+                molecule_id: id, ..
+            } => id,
+        }
+    }
+
+    // E.g., "PDB 5UPE"
+    pub fn label(&self) -> String {
+        match self {
+            Protein::ProteinOldPDB { pdb_id, .. } => format!("PDB {pdb_id}"),
+            Protein::ProteinOldUniprot { uniprot_id, .. } => {
+                format!("Uniprot {uniprot_id}")
+            }
+            Protein::ProteinNew {
+                molecule_id_type,
+                molecule_id,
+                ..
+            } => format!("{molecule_id_type} {molecule_id}"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Solvent {
     pub name: String,
 
-    pub ion_concentration: f64,
+    // None when unknown, e.g., for a solvent moved out of initial.solvents
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ion_concentration: Option<f64>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub solvent_concentration_units: Option<String>,
+
+    // Defaults to the charge in common::ION_CHARGES
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub charge: Option<i32>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Water {
     pub is_present: bool,
@@ -262,59 +414,409 @@ pub struct Water {
 
 impl MetaV1 {
     //[pyfunction]
-    pub fn from_toml(toml: &str) -> Result<Self> {
-        let mut meta: Self = toml::from_str(toml)?;
-        meta.to_canon()?;
-        Ok(meta)
+    pub fn from_toml(toml: &str) -> Result<Self, MdrMetaError> {
+        Self::from_toml_with_log(toml).map(|(meta, _)| meta)
+    }
+
+    // Also returns what to_canon changed
+    pub fn from_toml_with_log(
+        toml: &str,
+    ) -> Result<(Self, Vec<CanonChange>), MdrMetaError> {
+        let mut meta: Self =
+            toml::from_str(toml).map_err(|e| ParseError::from_toml(e, toml))?;
+        let canon_log = meta.to_canon()?;
+        Ok((meta, canon_log))
     }
 
     //[pyfunction]
-    pub fn from_json(json: &str) -> Result<Self> {
-        let mut meta: Self = serde_json::from_str(json)?;
-        meta.to_canon()?;
-        Ok(meta)
+    pub fn from_json(json: &str) -> Result<Self, MdrMetaError> {
+        Self::from_json_with_log(json).map(|(meta, _)| meta)
+    }
+
+    pub fn from_json_with_log(
+        json: &str,
+    ) -> Result<(Self, Vec<CanonChange>), MdrMetaError> {
+        let mut meta: Self =
+            serde_json::from_str(json).map_err(ParseError::from_json)?;
+        let canon_log = meta.to_canon()?;
+        Ok((meta, canon_log))
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(yaml: &str) -> Result<Self, MdrMetaError> {
+        Self::from_yaml_with_log(yaml).map(|(meta, _)| meta)
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml_with_log(
+        yaml: &str,
+    ) -> Result<(Self, Vec<CanonChange>), MdrMetaError> {
+        let mut meta: Self =
+            serde_yaml::from_str(yaml).map_err(ParseError::from_yaml)?;
+        let canon_log = meta.to_canon()?;
+        Ok((meta, canon_log))
     }
 
     //[pyfunction]
-    pub fn from_string(contents: &str) -> Result<Self> {
-        let meta = if contents.starts_with("{") {
-            Self::from_json(contents)?
-        } else {
-            Self::from_toml(contents)?
-        };
-        Ok(meta)
+    pub fn from_string(contents: &str) -> Result<Self, MdrMetaError> {
+        Self::from_string_with_log(contents).map(|(meta, _)| meta)
+    }
+
+    pub fn from_string_with_log(
+        contents: &str,
+    ) -> Result<(Self, Vec<CanonChange>), MdrMetaError> {
+        let contents = &normalize_input(contents);
+        match sniff_format(contents) {
+            Format::Json => Self::from_json_with_log(contents),
+            Format::Toml => Self::from_toml_with_log(contents),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => Self::from_yaml_with_log(contents),
+        }
+    }
+
+    // For input without a filename, e.g., stdin
+    pub fn from_string_any_with_log(
+        contents: &str,
+    ) -> Result<(AnyMeta, Vec<CanonChange>), MdrMetaError> {
+        let contents = &normalize_input(contents);
+        if contents.trim().is_empty() {
+            return Err(MdrMetaError::Empty);
+        }
+        match Self::detect_version(contents) {
+            SchemaVersion::V1 => Self::from_string_with_log(contents)
+                .map(|(meta, canon_log)| (AnyMeta::V1(meta), canon_log)),
+            SchemaVersion::V2 => MetaV2::from_string_with_log(contents)
+                .map(|(meta, canon_log)| (AnyMeta::V2(meta), canon_log)),
+        }
     }
 
     //[pyfunction]
-    pub fn from_file(filename: &str) -> Result<Self> {
-        match Path::new(filename).extension() {
-            Some(ext) => {
-                let contents = fs::read_to_string(filename)?;
-                if contents.is_empty() {
-                    bail!("File is empty")
-                }
-                let meta = match ext.to_str() {
-                    Some("json") => Self::from_json(&contents)?,
-                    Some("toml") => Self::from_toml(&contents)?,
-                    _ => bail!(r#"Unknown file extension "{}""#, ext.display()),
-                };
-                Ok(meta)
+    pub fn from_file(filename: &str) -> Result<Self, MdrMetaError> {
+        Self::from_file_with_log(filename).map(|(meta, _)| meta)
+    }
+
+    pub fn from_file_with_log(
+        filename: &str,
+    ) -> Result<(Self, Vec<CanonChange>), MdrMetaError> {
+        let (reader, format) = open_input(filename)?;
+        Self::from_reader_with_log(reader, format)
+    }
+
+    pub fn from_reader<R: Read>(
+        reader: R,
+        format: Format,
+    ) -> Result<Self, MdrMetaError> {
+        Self::from_reader_with_log(reader, format).map(|(meta, _)| meta)
+    }
+
+    // TOML can't be parsed incrementally, so the input is read in full
+    pub fn from_reader_with_log<R: Read>(
+        mut reader: R,
+        format: Format,
+    ) -> Result<(Self, Vec<CanonChange>), MdrMetaError> {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        let contents = normalize_input(&contents);
+        if contents.is_empty() {
+            return Err(MdrMetaError::Empty);
+        }
+        match format {
+            Format::Json => Self::from_json_with_log(&contents),
+            Format::Toml => Self::from_toml_with_log(&contents),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => Self::from_yaml_with_log(&contents),
+        }
+    }
+
+    // Defaults to V1 when the contents can't be parsed so the real parse
+    // reports the error
+    pub fn detect_version(contents: &str) -> SchemaVersion {
+        let has_key = |key: &str| {
+            #[cfg(feature = "yaml")]
+            if looks_like_yaml(contents) {
+                return serde_yaml::from_str::<serde_yaml::Mapping>(contents)
+                    .is_ok_and(|val| val.contains_key(key));
+            }
+
+            if contents.trim_start().starts_with("{") {
+                serde_json::from_str::<serde_json::Value>(contents)
+                    .is_ok_and(|val| val.get(key).is_some())
+            } else {
+                toml::from_str::<toml::Table>(contents)
+                    .is_ok_and(|val| val.contains_key(key))
+            }
+        };
+
+        if !has_key("initial") && has_key("lead_contributor_orcid") {
+            SchemaVersion::V2
+        } else {
+            SchemaVersion::V1
+        }
+    }
+
+    pub fn from_file_any(filename: &str) -> Result<AnyMeta, MdrMetaError> {
+        Self::from_file_any_with_log(filename).map(|(meta, _)| meta)
+    }
+
+    pub fn from_file_any_with_log(
+        filename: &str,
+    ) -> Result<(AnyMeta, Vec<CanonChange>), MdrMetaError> {
+        let (mut reader, format) = open_input(filename)?;
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        let contents = normalize_input(&contents);
+        match Self::detect_version(&contents) {
+            SchemaVersion::V1 => {
+                Self::from_reader_with_log(contents.as_bytes(), format)
+                    .map(|(meta, canon_log)| (AnyMeta::V1(meta), canon_log))
+            }
+            SchemaVersion::V2 => {
+                MetaV2::from_reader_with_log(contents.as_bytes(), format)
+                    .map(|(meta, canon_log)| (AnyMeta::V2(meta), canon_log))
             }
-            _ => bail!("No file extension"),
         }
     }
 
+    // Like from_string, but keys the schema doesn't know are set aside and
+    // returned instead of failing the parse
+    pub fn from_string_lenient(
+        contents: &str,
+    ) -> Result<(Self, UnknownFields), MdrMetaError> {
+        let format = sniff_format(&normalize_input(contents));
+        Self::from_str_lenient_with_log(contents, format)
+            .map(|(meta, _, unknown)| (meta, unknown))
+    }
+
+    pub fn from_file_lenient(
+        filename: &str,
+    ) -> Result<(Self, UnknownFields), MdrMetaError> {
+        let (mut reader, format) = open_input(filename)?;
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        Self::from_str_lenient_with_log(&contents, format)
+            .map(|(meta, _, unknown)| (meta, unknown))
+    }
+
+    fn from_str_lenient_with_log(
+        contents: &str,
+        format: Format,
+    ) -> Result<(Self, Vec<CanonChange>, UnknownFields), MdrMetaError> {
+        let (mut meta, unknown) = lenient::from_str_lenient::<Self>(contents, format)?;
+        let mut canon_log = meta.to_canon()?;
+        canon_log.extend(meta.canon_molecule_id_types());
+        Ok((meta, canon_log, unknown))
+    }
+
+    // Put misplaced keys where they belong, then parse what the schema knows
+    pub fn from_lenient(
+        mut lenient: MetaLenient,
+    ) -> Result<(Self, UnknownFields), MdrMetaError> {
+        lenient.relocate();
+        let value = serde_json::to_value(&lenient)
+            .map_err(|e| ParseError::new(e.to_string()))?;
+        let (mut meta, unknown) = lenient::from_value_lenient::<Self>(value)?;
+        meta.to_canon()?;
+        meta.canon_molecule_id_types();
+        Ok((meta, unknown))
+    }
+
+    // Lenient parsing fixes the case of protein ID types, e.g., "pdb" to
+    // "PDB", that find_errors would reject
+    fn canon_molecule_id_types(&mut self) -> Vec<CanonChange> {
+        let mut canon_log = vec![];
+        for (index, protein) in self.proteins.iter_mut().flatten().enumerate() {
+            if let Protein::ProteinNew {
+                molecule_id_type, ..
+            } = protein
+                && let Some(canon) = canon_molecule_id_type(molecule_id_type)
+                && canon != molecule_id_type
+            {
+                canon_log.push(CanonChange::new(
+                    &format!("/proteins/{index}/molecule_id_type"),
+                    molecule_id_type.as_str(),
+                    canon,
+                ));
+                *molecule_id_type = canon.to_string();
+            }
+        }
+        canon_log
+    }
+
+    // As from_string_any_with_log and from_file_any_with_log, but lenient
+    pub fn from_string_any_lenient(
+        contents: &str,
+    ) -> Result<(AnyMeta, Vec<CanonChange>, UnknownFields), MdrMetaError> {
+        let contents = &normalize_input(contents);
+        Self::any_lenient(contents, sniff_format(contents))
+    }
+
+    pub fn from_file_any_lenient(
+        filename: &str,
+    ) -> Result<(AnyMeta, Vec<CanonChange>, UnknownFields), MdrMetaError> {
+        let (mut reader, format) = open_input(filename)?;
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        Self::any_lenient(&normalize_input(&contents), format)
+    }
+
+    fn any_lenient(
+        contents: &str,
+        format: Format,
+    ) -> Result<(AnyMeta, Vec<CanonChange>, UnknownFields), MdrMetaError> {
+        match Self::detect_version(contents) {
+            SchemaVersion::V1 => Self::from_str_lenient_with_log(contents, format).map(
+                |(meta, canon_log, unknown)| (AnyMeta::V1(meta), canon_log, unknown),
+            ),
+            SchemaVersion::V2 => MetaV2::from_str_lenient_with_log(contents, format)
+                .map(|(meta, canon_log, unknown)| {
+                    (AnyMeta::V2(meta), canon_log, unknown)
+                }),
+        }
+    }
+
+    // Describes the file format, including the patterns and ranges that
+    // find_errors checks, for clients such as web forms
+    pub fn json_schema() -> serde_json::Value {
+        schemars::schema_for!(Self).to_value()
+    }
+
     //[pyfunction]
     pub fn to_json(&self) -> Result<String> {
         serde_json::to_string_pretty(&self).map_err(Into::into)
     }
 
+    // Writes back the keys a lenient parse set aside, with every key in
+    // alphabetical order
+    pub fn to_json_with_unknown(&self, unknown: &UnknownFields) -> Result<String> {
+        let mut value = serde_json::to_value(self)?;
+        unknown.restore(&mut value);
+        serde_json::to_string_pretty(&value).map_err(Into::into)
+    }
+
+    pub fn to_toml_with_unknown(&self, unknown: &UnknownFields) -> Result<String> {
+        let mut value = serde_json::to_value(self)?;
+        unknown.restore(&mut value);
+        toml::to_string_pretty(&value).map_err(Into::into)
+    }
+
     //[pyfunction]
     pub fn to_toml(&self) -> Result<String> {
         toml::to_string_pretty(&self).map_err(Into::into)
     }
 
-    pub fn to_v2(&self) -> Result<MetaV2> {
+    #[cfg(feature = "yaml")]
+    pub fn to_yaml(&self) -> Result<String> {
+        serde_yaml::to_string(&self).map_err(Into::into)
+    }
+
+    // Apply only the date and protein ID normalizations of to_canon to TOML,
+    // keeping its comments and key order, e.g., to rewrite a hand-edited file.
+    // V2 has only the date, at the top level, to normalize.
+    pub fn normalize_toml_in_place(contents: &str) -> Result<String> {
+        let version = Self::detect_version(contents);
+        let mut doc: DocumentMut = contents.parse()?;
+
+        let (name, date) = match version {
+            SchemaVersion::V1 => (
+                "initial.date",
+                doc.get_mut("initial")
+                    .and_then(|initial| initial.get_mut("date")),
+            ),
+            SchemaVersion::V2 => ("date", doc.get_mut("date")),
+        };
+        if let Some(date) = date.and_then(|date| date.as_value_mut()) {
+            let text = match date {
+                EditValue::String(text) => text.value().clone(),
+                EditValue::Datetime(dt) => dt.value().to_string(),
+                other => {
+                    bail!("{name} must be a date, got {}", other.type_name())
+                }
+            };
+            let canon = canon_date(&text)?;
+            if !matches!(date, EditValue::String(text) if *text.value() == canon) {
+                let decor = date.decor().clone();
+                *date = canon.into();
+                *date.decor_mut() = decor;
+            }
+        }
+
+        if version == SchemaVersion::V2 {
+            return Ok(doc.to_string());
+        }
+
+        // Older versions of the TOML had separate fields for PDB/Uniprot
+        let proteins: Vec<&mut dyn TableLike> = match doc.get_mut("proteins") {
+            Some(Item::ArrayOfTables(tables)) => tables
+                .iter_mut()
+                .map(|table| table as &mut dyn TableLike)
+                .collect(),
+            Some(Item::Value(EditValue::Array(vals))) => vals
+                .iter_mut()
+                .filter_map(EditValue::as_inline_table_mut)
+                .map(|table| table as &mut dyn TableLike)
+                .collect(),
+            _ => vec![],
+        };
+        for protein in proteins {
+            for (old_key, molecule_id_type) in
+                [("pdb_id", "PDB"), ("uniprot_id", "Uniprot")]
+            {
+                if let Some(molecule_id) = protein.remove(old_key) {
+                    protein.insert("molecule_id_type", value(molecule_id_type));
+                    protein.insert("molecule_id", molecule_id);
+                }
+            }
+        }
+
+        Ok(doc.to_string())
+    }
+
+    pub fn to_writer<W: Write>(&self, mut writer: W, format: Format) -> Result<()> {
+        let contents = match format {
+            Format::Json => self.to_json()?,
+            Format::Toml => self.to_toml()?,
+            #[cfg(feature = "yaml")]
+            Format::Yaml => self.to_yaml()?,
+        };
+        writer.write_all(contents.as_bytes()).map_err(Into::into)
+    }
+
+    // Fields with no V2 equivalent (initial.scientific_goal, initial.ligands,
+    // initial.solvents) are dropped; the latter two are already folded into
+    // the top-level lists by to_canon
+    pub fn to_v2(&self) -> Result<MetaV2, MdrMetaError> {
+        let required_file = self
+            .required_files
+            .clone()
+            .ok_or(MdrMetaError::MissingRequiredFiles)?;
+
+        // V2 requires a concentration, which isn't made up when unknown
+        let solvents = self
+            .solvents
+            .as_ref()
+            .map(|solvents| {
+                solvents
+                    .iter()
+                    .map(|solvent| {
+                        Ok(metav2::Solvent {
+                            name: solvent.name.clone(),
+                            ion_concentration_mol_liter: solvent
+                                .ion_concentration
+                                .ok_or_else(|| {
+                                    MdrMetaError::MissingConcentration(
+                                        solvent.name.clone(),
+                                    )
+                                })?,
+                            concentration_units: solvent
+                                .solvent_concentration_units
+                                .clone(),
+                            charge: solvent.charge,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, MdrMetaError>>()
+            })
+            .transpose()?;
+
         let meta_v2 = MetaV2 {
             mdrepo_id: self.mdrepo_id.clone(),
             short_description: self.initial.short_description.clone(),
@@ -324,265 +826,1433 @@ impl MetaV1 {
             date: self.initial.date.clone(),
             run_commands: self.initial.commands.clone(),
             software: self.software.clone(),
-            replicate_id: self.replicates.map_or(None, |rep| rep.replicate.clone()),
+            replicate_id: self.replicates.as_ref().and_then(|rep| rep.replicate),
+            merged_replicate_ids: self
+                .replicates
+                .as_ref()
+                .and_then(|rep| rep.merged_replicates.clone()),
             total_replicates: self
                 .replicates
-                .map_or(None, |rep| rep.total_replicates.clone()),
-            water_is_present: self.water.map_or(None, |water| Some(water.is_present)),
-            water_model: self.water.map_or(None, |water| water.model.clone()),
-            water_density_kg_m3: self.water.map_or(None, |water| water.density.clone()),
-            forcefield: self.forcefield.map_or(None, |f| f.forcefield.clone()),
+                .as_ref()
+                .and_then(|rep| rep.total_replicates),
+            water_is_present: self.water.as_ref().map(|water| water.is_present),
+            water_model: self.water.as_ref().and_then(|water| water.model.clone()),
+            water_density_kg_m3: self.water.as_ref().and_then(|water| water.density),
+            forcefield: self.forcefield.as_ref().and_then(|f| f.forcefield.clone()),
             forcefield_comments: self
                 .forcefield
-                .map_or(None, |f| f.forcefield_comments.clone()),
-            temperature_kelvin: self.temperature.map_or(None, |t| t.temperature),
+                .as_ref()
+                .and_then(|f| f.forcefield_comments.clone()),
+            temperature_kelvin: self.temperature.as_ref().and_then(|t| t.temperature),
             protonation_method: self
                 .protonation_method
-                .map_or(None, |p| p.protonation_method),
+                .as_ref()
+                .and_then(|p| p.protonation_method.clone()),
+            ph: self.protonation_method.as_ref().and_then(|p| p.ph),
+            // Canonical V1 timesteps are in fs
             timestep_ns: self
                 .timestep_information
-                .map_or(None, |ts| ts.integration_time_step),
-            required_file: self.required_files.unwrap().clone(),
-            // TODO: pick up here!
+                .as_ref()
+                .and_then(|ts| ts.integration_time_step)
+                .map(|step| step / 1e6),
+            hydrogen_mass_repartitioning: self
+                .timestep_information
+                .as_ref()
+                .and_then(|ts| ts.hydrogen_mass_repartitioning),
+            simulation_length_ns: self.initial.simulation_length_ns,
+            box_dimensions_nm: self.initial.box_dimensions_nm,
+            required_file,
+            additional_files: self.additional_files.as_ref().map(|files| {
+                files
+                    .iter()
+                    .map(|file| metav2::AdditionalFile {
+                        file_type: file.additional_file_type.clone(),
+                        file_name: file.additional_file_name.clone(),
+                        description: file.additional_file_description.clone(),
+                    })
+                    .collect()
+            }),
+            proteins: self.proteins.as_ref().map(|proteins| {
+                proteins
+                    .iter()
+                    .map(|protein| match protein {
+                        Protein::ProteinOldPDB { primary, pdb_id } => metav2::Protein {
+                            is_primary: *primary,
+                            molecule_id_type: "PDB".to_string(),
+                            molecule_id: pdb_id.clone(),
+                        },
+                        Protein::ProteinOldUniprot {
+                            primary,
+                            uniprot_id,
+                        } => metav2::Protein {
+                            is_primary: *primary,
+                            molecule_id_type: "Uniprot".to_string(),
+                            molecule_id: uniprot_id.clone(),
+                        },
+                        Protein::ProteinNew {
+                            primary,
+                            molecule_id_type,
+                            molecule_id,
+                        } => metav2::Protein {
+                            is_primary: *primary,
+                            molecule_id_type: molecule_id_type.clone(),
+                            molecule_id: molecule_id.clone(),
+                        },
+                    })
+                    .collect()
+            }),
+            ligands: self.ligands.as_ref().map(|ligands| {
+                ligands
+                    .iter()
+                    .map(|ligand| metav2::Ligand {
+                        is_primary: ligand.primary,
+                        name: ligand.name.clone(),
+                        smiles: ligand.smiles.clone(),
+                    })
+                    .collect()
+            }),
+            solvents,
+            papers: self.papers.as_ref().map(|papers| {
+                papers
+                    .iter()
+                    .map(|paper| {
+                        let pages = paper.pages.as_deref().and_then(parse_pages);
+                        metav2::Paper {
+                            is_primary: paper.primary,
+                            title: paper.title.clone(),
+                            authors: paper.authors.clone(),
+                            author_list: paper.author_list.clone(),
+                            journal: paper.journal.clone(),
+                            volume: paper.volume.clone(),
+                            number: paper.number.clone(),
+                            year: paper.year,
+                            pages: paper.pages.clone(),
+                            page_start: pages.map(|(start, _)| start),
+                            page_end: pages.and_then(|(_, end)| end),
+                            doi: paper.doi.clone(),
+                        }
+                    })
+                    .collect()
+            }),
+            contributors: self.contributors.as_ref().map(|contributors| {
+                contributors
+                    .iter()
+                    .map(|contributor| metav2::Contributor {
+                        name: contributor.name.clone(),
+                        orcid: contributor.orcid.clone(),
+                        email: contributor.email.clone(),
+                        institution: contributor.institution.clone(),
+                    })
+                    .collect()
+            }),
+            simulation_is_restricted: self.initial.simulation_is_restricted,
+            simulation_permissions: self.simulation_permissions.as_ref().map(|perms| {
+                perms
+                    .iter()
+                    .map(|perm| metav2::Permission {
+                        user_orcid: perm.user_orcid.clone(),
+                        can_edit: perm.can_edit,
+                        can_view: perm.can_view,
+                    })
+                    .collect()
+            }),
         };
 
         Ok(meta_v2)
     }
 
+    // to_canon stores paper volumes and numbers as strings; this writes the
+    // integers back as numbers, e.g., volume = 17, for consumers wanting them
+    pub fn numeric_volumes(&mut self) {
+        for paper in self.papers.iter_mut().flatten() {
+            paper.volume = paper.volume.to_numeric();
+            paper.number = paper.number.as_ref().map(Numlike::to_numeric);
+        }
+    }
+
+    // None when "section" isn't an optional top-level section
+    pub fn has_section(&self, section: &str) -> Option<bool> {
+        let present = match section {
+            "mdrepo_id" => self.mdrepo_id.is_some(),
+            "required_files" => self.required_files.is_some(),
+            "additional_files" => self
+                .additional_files
+                .as_ref()
+                .is_some_and(|v| !v.is_empty()),
+            "proteins" => self.proteins.as_ref().is_some_and(|v| !v.is_empty()),
+            "replicates" => self.replicates.is_some(),
+            "water" => self.water.is_some(),
+            "ligands" => self.ligands.as_ref().is_some_and(|v| !v.is_empty()),
+            "solvents" => self.solvents.as_ref().is_some_and(|v| !v.is_empty()),
+            "forcefield" => self.forcefield.is_some(),
+            "temperature" => self.temperature.is_some(),
+            "protonation_method" => self.protonation_method.is_some(),
+            "timestep_information" => self.timestep_information.is_some(),
+            "papers" => self.papers.as_ref().is_some_and(|v| !v.is_empty()),
+            "contributors" => self.contributors.as_ref().is_some_and(|v| !v.is_empty()),
+            "simulation_permissions" => self
+                .simulation_permissions
+                .as_ref()
+                .is_some_and(|v| !v.is_empty()),
+            _ => return None,
+        };
+        Some(present)
+    }
+
+    // The weighted fraction of COMPLETENESS_WEIGHTS that is filled in, from
+    // 0 to 1
+    pub fn completeness(&self) -> f32 {
+        let total: f32 = COMPLETENESS_WEIGHTS.iter().map(|(_, weight)| weight).sum();
+        let missing: f32 = self.empty_fields().iter().map(|(_, weight)| weight).sum();
+        (total - missing) / total
+    }
+
+    // The unpopulated fields of COMPLETENESS_WEIGHTS, heaviest first
+    pub fn empty_fields(&self) -> Vec<(&'static str, f32)> {
+        let mut empty: Vec<_> = COMPLETENESS_WEIGHTS
+            .iter()
+            .filter(|(field, _)| !self.is_populated(field))
+            .copied()
+            .collect();
+        empty.sort_by(|a, b| b.1.total_cmp(&a.1));
+        empty
+    }
+
+    fn is_populated(&self, field: &str) -> bool {
+        let filled = |val: &Option<String>| {
+            val.as_ref().is_some_and(|val| !val.trim().is_empty())
+        };
+        match field {
+            "initial.description" => filled(&self.initial.description),
+            "initial.short_description" => filled(&self.initial.short_description),
+            "initial.scientific_goal" => filled(&self.initial.scientific_goal),
+            "initial.commands" => filled(&self.initial.commands),
+            "initial.external_link" => filled(&self.initial.external_link),
+            "software.version" => filled(&self.software.version),
+            section => self.has_section(section).unwrap_or(false),
+        }
+    }
+
+    fn citations(&self) -> Vec<Citation<'_>> {
+        self.papers
+            .iter()
+            .flatten()
+            .map(|paper| Citation {
+                title: &paper.title,
+                authors: &paper.authors,
+                author_list: paper.author_list.as_deref(),
+                journal: &paper.journal,
+                volume: &paper.volume,
+                number: paper.number.as_ref(),
+                year: paper.year,
+                pages: paper.pages.clone(),
+                doi: paper.doi.as_deref(),
+            })
+            .collect()
+    }
+
+    // Fill in empty or missing paper fields from CrossRef by DOI without
+    // overwriting anything; returns the DOIs that failed with their errors
+    #[cfg(feature = "network")]
+    pub fn enrich_papers_from_crossref(&mut self) -> Vec<(String, anyhow::Error)> {
+        self.enrich_papers_with(crate::crossref::fetch_message)
+    }
+
+    // As above with the CrossRef "message" for each DOI from the caller,
+    // e.g., a cache of earlier responses
+    pub fn enrich_papers_with(
+        &mut self,
+        mut fetch: impl FnMut(&str) -> Result<serde_json::Value>,
+    ) -> Vec<(String, anyhow::Error)> {
+        let mut failures = vec![];
+        for paper in self.papers.iter_mut().flatten() {
+            let Some(doi) = paper.doi.clone() else {
+                continue;
+            };
+            match fetch(&doi) {
+                Ok(message) => {
+                    CrossrefWork::from_message(&message).fill(PaperFields {
+                        title: &mut paper.title,
+                        authors: &mut paper.authors,
+                        journal: &mut paper.journal,
+                        volume: &mut paper.volume,
+                        number: &mut paper.number,
+                        pages: &mut paper.pages,
+                        year: &mut paper.year,
+                    });
+                }
+                Err(e) => failures.push((doi, e)),
+            }
+        }
+        failures
+    }
+
+    // Whether each paper's DOI resolves at https://doi.org, or None when
+    // that couldn't be determined (e.g., a timeout). Answers are cached in
+    // DoiCache::default_path() so re-runs skip DOIs already checked.
+    #[cfg(feature = "network")]
+    pub fn verify_dois(&self) -> Result<Vec<(String, Option<bool>)>> {
+        let path = DoiCache::default_path();
+        let mut cache = path.as_deref().map(DoiCache::from_file).unwrap_or_default();
+        let results = self.verify_dois_with(&mut cache, crate::crossref::resolve_doi);
+        if let Some(path) = path {
+            cache.to_file(&path)?;
+        }
+        Ok(results)
+    }
+
+    // As above with the cache and the lookup from the caller
+    pub fn verify_dois_with(
+        &self,
+        cache: &mut DoiCache,
+        resolve: impl FnMut(&str) -> Option<bool>,
+    ) -> Vec<(String, Option<bool>)> {
+        let dois = self
+            .papers
+            .iter()
+            .flatten()
+            .filter_map(|p| p.doi.as_deref());
+        crate::crossref::verify_dois(dois, cache, resolve)
+    }
+
+    // (ID, title) for each PDB protein from RCSB and each UniProt protein
+    // from UniProt, fetching each entry once per process
+    #[cfg(feature = "network")]
+    pub fn resolve_protein_names(&self) -> Result<Vec<(String, String)>> {
+        self.resolve_protein_names_with(crate::protein_names::fetch_cached)
+    }
+
+    // As above with the JSON entry for each URL from the caller
+    pub fn resolve_protein_names_with(
+        &self,
+        fetch: impl FnMut(&str) -> Result<serde_json::Value>,
+    ) -> Result<Vec<(String, String)>> {
+        crate::protein_names::resolve_names(&self.external_ids(), fetch)
+    }
+
+    // One BibTeX @article entry per paper, keyed by DOI where there is one
+    pub fn papers_to_bibtex(&self) -> String {
+        cite::to_bibtex(&self.citations())
+    }
+
+    // One RIS record per paper, with an "AU" line per author
+    pub fn papers_to_ris(&self) -> String {
+        cite::to_ris(&self.citations())
+    }
+
+    // The paper flagged primary, else the first
+    pub fn primary_paper(&self) -> Option<&Paper> {
+        primary_or_first(self.papers.as_ref(), |paper| paper.primary == Some(true))
+    }
+
+    pub fn primary_ligand(&self) -> Option<&Ligand> {
+        primary_or_first(self.ligands.as_ref(), |ligand| ligand.primary == Some(true))
+    }
+
+    pub fn primary_protein(&self) -> Option<&Protein> {
+        primary_or_first(self.proteins.as_ref(), Protein::is_primary)
+    }
+
+    // A one-line overview, e.g.,
+    // "GROMACS 2024, 1 protein (PDB 5UPE), 1 ligand, 300 K, charmm36, replicate 1/4"
+    pub fn summary(&self) -> String {
+        let mut parts = vec![match &self.software.version {
+            Some(version) => format!("{} {version}", self.software.name),
+            _ => self.software.name.clone(),
+        }];
+
+        if let Some(proteins) = &self.proteins
+            && !proteins.is_empty()
+        {
+            let mut part = plural(proteins.len(), "protein");
+            // As for ligands, only name the protein flagged primary
+            if let Some(protein) = proteins.iter().find(|p| p.is_primary()) {
+                part.push_str(&format!(" ({})", protein.label()));
+            }
+            parts.push(part);
+        }
+
+        if let Some(ligands) = &self.ligands
+            && !ligands.is_empty()
+        {
+            let mut part = plural(ligands.len(), "ligand");
+            // Only name the ligand the submitter singled out
+            if let Some(ligand) = ligands.iter().find(|l| l.primary == Some(true)) {
+                part.push_str(&format!(" ({})", ligand.name));
+            }
+            parts.push(part);
+        }
+
+        if let Some(temp) = &self.temperature
+            && let Some(value) = temp.temperature
+        {
+            let units = temp.temperature_units.as_deref().unwrap_or("K");
+            parts.push(format!("{value} {units}"));
+        }
+
+        if let Some(names) =
+            self.forcefield.as_ref().and_then(|f| f.forcefield.as_ref())
+            && !names.is_empty()
+        {
+            parts.push(names.join(" + "));
+        }
+
+        if let Some(reps) = &self.replicates
+            && let Some(rep) = reps.replicate
+        {
+            parts.push(match reps.total_replicates {
+                Some(total) => format!("replicate {rep}/{total}"),
+                _ => format!("replicate {rep}"),
+            });
+        }
+
+        parts.join(", ")
+    }
+
+    // The ionic strength in mol/L, I = ½ Σ cᵢzᵢ², where cᵢ is the molar
+    // concentration of each ion and zᵢ its charge. None when there are no
+    // solvents or any one's charge or concentration is unknown.
+    pub fn ionic_strength(&self) -> Option<f64> {
+        let solvents = self.solvents.as_ref().filter(|s| !s.is_empty())?;
+        solvents.iter().try_fold(0., |total, solvent| {
+            let charge = solvent.charge.or_else(|| ion_charge(&solvent.name))?;
+            let divisor = match &solvent.solvent_concentration_units {
+                Some(units) => concentration_divisor_to_mol_l(units)?,
+                None => 1.,
+            };
+            let conc = solvent.ion_concentration? / divisor;
+            Some(total + 0.5 * conc * f64::from(charge.pow(2)))
+        })
+    }
+
+    pub fn external_ids(&self) -> ExternalIds {
+        let mut ids = ExternalIds::default();
+
+        for protein in self.proteins.iter().flatten() {
+            match protein {
+                Protein::ProteinOldPDB { pdb_id, .. } => ids.add_protein("PDB", pdb_id),
+                Protein::ProteinOldUniprot { uniprot_id, .. } => {
+                    ids.add_protein("Uniprot", uniprot_id)
+                }
+                Protein::ProteinNew {
+                    molecule_id_type,
+                    molecule_id,
+                    ..
+                } => ids.add_protein(molecule_id_type, molecule_id),
+            }
+        }
+
+        for doi in self.papers.iter().flatten().filter_map(|p| p.doi.as_ref()) {
+            ids.add_doi(doi);
+        }
+
+        for orcid in self.all_orcids() {
+            ids.add_orcid(orcid);
+        }
+
+        ids
+    }
+
+    // The lead contributor, contributor, and permission ORCIDs, each once in
+    // the order first mentioned
+    pub fn all_orcids(&self) -> Vec<&str> {
+        let contributors = self
+            .contributors
+            .iter()
+            .flatten()
+            .filter_map(|c| c.orcid.as_deref());
+        let permissions = self
+            .simulation_permissions
+            .iter()
+            .flatten()
+            .map(|perm| perm.user_orcid.as_str());
+
+        let mut orcids: Vec<&str> = vec![];
+        for orcid in iter::once(self.initial.lead_contributor_orcid.as_str())
+            .chain(contributors)
+            .chain(permissions)
+        {
+            if !orcids.contains(&orcid) {
+                orcids.push(orcid);
+            }
+        }
+        orcids
+    }
+
+    // Detects a resubmission of an already-assigned simulation
+    pub fn matches_ignoring_id(&self, other: &Self) -> bool {
+        let Self {
+            mdrepo_id: _,
+            initial,
+            software,
+            required_files,
+            additional_files,
+            proteins,
+            replicates,
+            water,
+            ligands,
+            solvents,
+            forcefield,
+            temperature,
+            protonation_method,
+            timestep_information,
+            papers,
+            contributors,
+            simulation_permissions,
+        } = self;
+
+        *initial == other.initial
+            && *software == other.software
+            && *required_files == other.required_files
+            && *additional_files == other.additional_files
+            && *proteins == other.proteins
+            && *replicates == other.replicates
+            && *water == other.water
+            && *ligands == other.ligands
+            && *solvents == other.solvents
+            && *forcefield == other.forcefield
+            && *temperature == other.temperature
+            && *protonation_method == other.protonation_method
+            && *timestep_information == other.timestep_information
+            && *papers == other.papers
+            && *contributors == other.contributors
+            && *simulation_permissions == other.simulation_permissions
+    }
+
+    // True when there are no errors; warnings don't count
+    //[pyfunction]
+    pub fn is_valid(&self) -> bool {
+        self.find_errors().iter().all(MetaError::is_warning)
+    }
+
     //[pyfunction]
-    pub fn find_errors(&self) -> Vec<(String, String)> {
+    pub fn find_errors(&self) -> Vec<MetaError> {
+        self.find_errors_with_config(&ValidationConfig::default())
+    }
+
+    pub fn find_errors_with_config(&self, config: &ValidationConfig) -> Vec<MetaError> {
         let mut errors = vec![];
-        //if let Some(replicates) = &self.replicates {
-        //    if replicates.replicate.unwra
-        //}
-
-        if let Some(temp) = &self.temperature.clone().and_then(|t| t.temperature) {
-            if !(MIN_TEMP_K..=MAX_TEMP_K).contains(temp) {
-                errors.push((
-                    "temperature.temperature".to_string(),
-                    format!(
-                        r#""{temp}" must be in the range {MIN_TEMP_K}-{MAX_TEMP_K}"#
-                    ),
-                ))
+        if let Some(mdrepo_id) = &self.mdrepo_id
+            && let Err(err) = validate_mdrepo_id(mdrepo_id)
+        {
+            errors.push(err.at("/mdrepo_id"));
+        }
+
+        if let Some(replicates) = &self.replicates {
+            if let Some(err) = check_replicate(
+                "/replicates/replicate",
+                replicates.replicate,
+                replicates.total_replicates,
+            ) {
+                errors.push(err);
+            }
+
+            for (index, replicate) in
+                replicates.merged_replicates.iter().flatten().enumerate()
+            {
+                if let Some(err) = check_replicate(
+                    &format!("/replicates/merged_replicates/{index}"),
+                    Some(*replicate),
+                    replicates.total_replicates,
+                ) {
+                    errors.push(err);
+                }
             }
         }
 
-        let valid_date = Regex::new(r"\d{4}\-\d{2}\-\d{2}").unwrap();
+        if let Some(temp) = self.temperature.as_ref().and_then(|t| t.temperature)
+            && let Some(err) =
+                check_temperature("/temperature/temperature", temp, config)
+        {
+            errors.push(err);
+        }
+
+        if let Some(ph) = self.protonation_method.as_ref().and_then(|p| p.ph)
+            && let Some(err) = check_ph("/protonation_method/ph", ph)
+            && !err.is_warning()
+        {
+            errors.push(err);
+        }
+
         match &self.initial.date {
             Datelike::Stringy(dt) => {
-                if !valid_date.is_match(dt) {
-                    errors.push((
-                        "initial.date".to_string(),
-                        format!(r#"invalid date "{}""#, dt),
-                    ));
+                if let Err(err) = validate_date(dt) {
+                    errors.push(err.at("/initial/date"));
                 }
             }
             _ => {
-                errors.push(("initial.date".to_string(), "invalid date".to_string()));
+                errors.push(MetaError::error(
+                    "/initial/date",
+                    ErrorCode::InvalidDate,
+                    "invalid date",
+                ));
             }
         }
 
-        fn is_valid_orcid(orcid: &str) -> bool {
-            let re = Regex::new(r"\d{4}\-\d{4}\-\d{4}\-\d{3}[A-Z]").unwrap();
-            re.is_match(orcid)
+        if let Ok(date) =
+            NaiveDate::parse_from_str(&self.initial.date.to_string(), "%F")
+            && date > config.today()
+        {
+            errors.push(MetaError::error(
+                "/initial/date",
+                ErrorCode::FutureDate,
+                format!(r#""{date}" is in the future"#),
+            ));
         }
 
-        if !is_valid_orcid(&self.initial.lead_contributor_orcid) {
-            errors.push((
-                "initial.lead_contributor_orcid".to_string(),
-                format!(r#"invalid ORCID "{}""#, self.initial.lead_contributor_orcid),
+        // An empty link is treated as absent
+        if let Some(link) = &self.initial.external_link
+            && !link.is_empty()
+            && !validate_url(link)
+        {
+            errors.push(MetaError::error(
+                "/initial/external_link",
+                ErrorCode::InvalidUrl,
+                format!(r#"invalid URL "{link}""#),
             ));
         }
 
-        if let Some(contributors) = &self.contributors {
-            for contributor in contributors {
-                if let Some(orcid) = &contributor.orcid {
-                    if !is_valid_orcid(orcid) {
-                        errors.push((
-                            "contributor.orcid".to_string(),
-                            format!(r#"invalid ORCID "{}""#, orcid),
-                        ));
-                    }
+        if let Some(max_chars) = config.max_description_chars {
+            for (field, text) in [
+                (
+                    "/initial/short_description",
+                    &self.initial.short_description,
+                ),
+                ("/initial/description", &self.initial.description),
+            ] {
+                let num_chars = text.as_ref().map_or(0, |val| val.chars().count());
+                if num_chars > max_chars {
+                    errors.push(MetaError::error(
+                        field,
+                        ErrorCode::DescriptionTooLong,
+                        format!("length {num_chars} exceeds maximum of {max_chars}"),
+                    ));
                 }
             }
         }
 
-        if let Some(perms) = &self.simulation_permissions {
-            for perm in perms {
-                if !is_valid_orcid(&perm.user_orcid) {
-                    errors.push((
-                        "simulation_permissions.user_orcid".to_string(),
-                        format!(r#"invalid ORCID "{}""#, perm.user_orcid),
-                    ));
-                }
+        for section in &config.required_sections {
+            match self.has_section(section) {
+                Some(true) => (),
+                Some(false) => errors.push(MetaError::error(
+                    &format!("/{section}"),
+                    ErrorCode::MissingSection,
+                    "required section is missing",
+                )),
+                None => errors.push(MetaError::error(
+                    &format!("/{section}"),
+                    ErrorCode::UnknownSection,
+                    "unknown section",
+                )),
             }
         }
 
-        if let Some(water) = &self.water {
-            if let Some(density) = water.density {
-                if !density.is_finite() {
-                    errors.push((
-                        "water.density".to_string(),
-                        format!("{density} is not a finite value"),
-                    ));
-                }
+        if let Err(err) = validate_orcid(&self.initial.lead_contributor_orcid) {
+            errors.push(err.at("/initial/lead_contributor_orcid"));
+        }
+
+        let contributor_orcids: Vec<_> = self
+            .contributors
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(index, contributor)| {
+                Some((index, contributor.orcid.as_deref()?))
+            })
+            .collect();
+        for (index, orcid) in &contributor_orcids {
+            if let Err(err) = validate_orcid(orcid) {
+                errors.push(err.at(&format!("/contributors/{index}/orcid")));
             }
+        }
 
-            if !water.is_present {
-                if water.model.is_some() {
-                    errors.push((
-                        "water.model".to_string(),
-                        "should not be present if water.is_present is false"
-                            .to_string(),
-                    ));
-                }
-                if water.density.is_some() {
-                    errors.push((
-                        "water.density".to_string(),
-                        "should not be present if water.is_present is false"
-                            .to_string(),
-                    ));
+        let permission_orcids: Vec<_> = self
+            .simulation_permissions
+            .iter()
+            .flatten()
+            .map(|perm| perm.user_orcid.as_str())
+            .enumerate()
+            .collect();
+        for (index, orcid) in &permission_orcids {
+            if let Err(err) = validate_orcid(orcid) {
+                errors.push(
+                    err.at(&format!("/simulation_permissions/{index}/user_orcid")),
+                );
+            }
+        }
+        for (index, perm) in self.simulation_permissions.iter().flatten().enumerate() {
+            if perm.can_edit && !perm.can_view {
+                errors.push(MetaError::error(
+                    &format!("/simulation_permissions/{index}/can_view"),
+                    ErrorCode::EditWithoutView,
+                    "must be true when can_edit is true",
+                ));
+            }
+        }
+        errors.extend(check_duplicate_orcids(
+            &contributor_orcids,
+            &permission_orcids,
+        ));
+
+        // The first occurrence is fine, so only the repeats are flagged
+        let mut seen = HashSet::new();
+        for (index, file) in self.additional_files.iter().flatten().enumerate() {
+            if !seen.insert(&file.additional_file_name) {
+                errors.push(MetaError::error(
+                    &format!("/additional_files/{index}/additional_file_name"),
+                    ErrorCode::DuplicateFileName,
+                    format!(r#"duplicate file name "{}""#, file.additional_file_name),
+                ));
+            }
+        }
+
+        for (index, protein) in self.proteins.iter().flatten().enumerate() {
+            let (field, molecule_id_type, molecule_id) = match protein {
+                Protein::ProteinOldPDB { pdb_id, .. } => ("pdb_id", "PDB", pdb_id),
+                Protein::ProteinOldUniprot { uniprot_id, .. } => {
+                    ("uniprot_id", "Uniprot", uniprot_id)
                 }
-                if water.water_density_units.is_some() {
-                    errors.push((
-                        "water.water_density_units".to_string(),
-                        "should not be present if water.is_present is false"
-                            .to_string(),
-                    ));
+                Protein::ProteinNew {
+                    molecule_id_type,
+                    molecule_id,
+                    ..
+                } => ("molecule_id", molecule_id_type.as_str(), molecule_id),
+            };
+            let path = format!("/proteins/{index}/{field}");
+            if let Some(err) = check_molecule_id(&path, molecule_id_type, molecule_id) {
+                errors.push(err);
+            }
+            if let Some(err) = check_molecule_id_type(
+                &format!("/proteins/{index}/molecule_id_type"),
+                molecule_id_type,
+            ) {
+                errors.push(err);
+            }
+        }
+
+        // Empty SMILES are warned about in find_warnings
+        for (index, ligand) in self.ligands.iter().flatten().enumerate() {
+            if !ligand.smiles.is_empty()
+                && let Err(err) = validate_smiles(&ligand.smiles)
+            {
+                errors.push(err.at(&format!("/ligands/{index}/smiles")));
+            }
+        }
+
+        // 1665 saw the first scientific journal
+        let max_year = config.today().year() as u32 + 1;
+        for (index, paper) in self.papers.iter().flatten().enumerate() {
+            if !(1665..=max_year).contains(&paper.year) {
+                errors.push(MetaError::error(
+                    &format!("/papers/{index}/year"),
+                    ErrorCode::InvalidYear,
+                    format!(r#""{}" must be in the range 1665-{max_year}"#, paper.year),
+                ));
+            }
+
+            if let Some(doi) = &paper.doi
+                && let Err(err) = validate_doi(doi)
+            {
+                errors.push(err.at(&format!("/papers/{index}/doi")));
+            }
+
+            for (author_index, author) in paper.author_list.iter().flatten().enumerate()
+            {
+                if let Some(orcid) = &author.orcid
+                    && let Err(err) = validate_orcid(orcid)
+                {
+                    errors.push(err.at(&format!(
+                        "/papers/{index}/author_list/{author_index}/orcid"
+                    )));
                 }
             }
         }
 
+        if let Some(water) = &self.water
+            && let Some(density) = water.density
+        {
+            if !density.is_finite() {
+                errors.push(MetaError::error(
+                    "/water/density",
+                    ErrorCode::NotFinite,
+                    format!("{density} is not a finite value"),
+                ));
+            } else if density <= 0. {
+                errors.push(MetaError::error(
+                    "/water/density",
+                    ErrorCode::NotPositive,
+                    "must be positive",
+                ));
+            }
+        }
+
         if let Some(solvents) = &self.solvents {
-            for solvent in solvents {
-                if !solvent.ion_concentration.is_finite() {
-                    errors.push((
-                        "solvent.ion_concentration".to_string(),
-                        format!(
-                            "{:?} is not a finite value",
-                            solvent.ion_concentration
-                        ),
+            for (index, solvent) in solvents.iter().enumerate() {
+                let Some(conc) = solvent.ion_concentration else {
+                    continue;
+                };
+                let path = format!("/solvents/{index}/ion_concentration");
+                if !conc.is_finite() {
+                    errors.push(MetaError::error(
+                        &path,
+                        ErrorCode::NotFinite,
+                        format!("{conc:?} is not a finite value"),
+                    ));
+                } else if conc < 0. {
+                    errors.push(MetaError::error(
+                        &path,
+                        ErrorCode::Negative,
+                        "must not be negative",
                     ));
                 }
             }
         }
 
-        if let Some(timestep) = &self.timestep_information {
-            if timestep
-                .integration_time_step
-                .map_or(false, |val| !val.is_finite())
+        if config.require_units {
+            if let Some(temp) = &self.temperature
+                && temp.temperature.is_some()
+                && temp.temperature_units.is_none()
             {
-                errors.push((
-                    "timestep.integration_time_step".to_string(),
-                    format!(
-                        "{:?} is not a finite value",
-                        timestep.integration_time_step.unwrap()
-                    ),
+                errors.push(MetaError::error(
+                    "/temperature/temperature_units",
+                    ErrorCode::MissingUnits,
+                    "required when temperature is given",
+                ));
+            }
+
+            if let Some(timestep) = &self.timestep_information
+                && timestep.integration_time_step.is_some()
+                && timestep.timestep_units.is_none()
+            {
+                errors.push(MetaError::error(
+                    "/timestep_information/timestep_units",
+                    ErrorCode::MissingUnits,
+                    "required when integration_time_step is given",
                 ));
             }
         }
 
+        if let Some(timestep) = &self.timestep_information
+            && timestep
+                .integration_time_step
+                .is_some_and(|val| !val.is_finite())
+        {
+            errors.push(MetaError::error(
+                "/timestep_information/integration_time_step",
+                ErrorCode::NotFinite,
+                format!(
+                    "{:?} is not a finite value",
+                    timestep.integration_time_step.unwrap()
+                ),
+            ));
+        }
+
+        if let Some(length) = self.initial.simulation_length_ns {
+            errors.extend(check_positive("/initial/simulation_length_ns", length));
+        }
+
+        for (index, edge) in self.initial.box_dimensions_nm.iter().flatten().enumerate()
+        {
+            errors.extend(check_positive(
+                &format!("/initial/box_dimensions_nm/{index}"),
+                *edge,
+            ));
+        }
+
+        errors.extend(self.find_warnings());
         errors
     }
 
-    fn to_canon(&mut self) -> Result<()> {
-        // Some confusion over dates as quoted strings or unquoted TOML values
-        // But there's no JSON "date" format
-        let date = self.initial.date.to_string();
-        let dt = dateparser::parse_with_timezone(&date, &chrono::offset::Utc)
-            .map_err(|e| anyhow!(r#"initial.date {e}"#))?;
-        self.initial.date = Datelike::Stringy(format!("{}", dt.format("%F")));
-
-        // TODO: This is silly, but I'll have to do the same for the "solvents"?
-        if let Some(initial_ligands) = &self.initial.ligands {
-            if let Some(ligands) = &mut self.ligands {
-                for ligand_name in initial_ligands {
-                    ligands.push(Ligand {
-                        primary: None,
-                        name: ligand_name.clone(),
-                        smiles: "".to_string(),
-                    });
+    // Problems that don't invalidate the metadata but suggest it's incomplete
+    // or inconsistent; these are also included in find_errors
+    //[pyfunction]
+    pub fn find_warnings(&self) -> Vec<MetaError> {
+        let mut warnings = vec![];
+
+        // The lead has no name or email unless they're also a contributor
+        if !self.contributors.iter().flatten().any(|contributor| {
+            contributor.orcid.as_ref() == Some(&self.initial.lead_contributor_orcid)
+        }) {
+            warnings.push(MetaError::warning(
+                "/initial/lead_contributor_orcid",
+                ErrorCode::LeadNotContributor,
+                "not listed among the contributors",
+            ));
+        }
+
+        for (field, text) in [
+            (
+                "/initial/short_description",
+                &self.initial.short_description,
+            ),
+            ("/initial/description", &self.initial.description),
+        ] {
+            if text.as_ref().is_some_and(|val| val.trim().is_empty()) {
+                warnings.push(MetaError::warning(
+                    field,
+                    ErrorCode::EmptyDescription,
+                    "is empty",
+                ));
+            }
+        }
+
+        if let Some(water) = &self.water
+            && !water.is_present
+        {
+            for (field, present) in [
+                ("/water/model", water.model.is_some()),
+                ("/water/density", water.density.is_some()),
+                (
+                    "/water/water_density_units",
+                    water.water_density_units.is_some(),
+                ),
+            ] {
+                if present {
+                    warnings.push(MetaError::warning(
+                        field,
+                        ErrorCode::WaterInconsistent,
+                        "should not be present if water.is_present is false",
+                    ));
                 }
-            } else {
-                self.ligands = Some(
-                    initial_ligands
-                        .iter()
-                        .map(|ligand_name| Ligand {
-                            primary: None,
-                            name: ligand_name.clone(),
-                            smiles: "".to_string(),
-                        })
-                        .collect(),
-                );
             }
         }
-        self.initial.ligands = None;
 
-        if let Some(papers) = &self.papers {
-            let new_papers: Vec<_> = papers
+        // No real simulation runs below 100 K, but 25 °C is common
+        if let Some(temp) = &self.temperature
+            && let Some(degrees) = temp.temperature
+            && degrees.as_f64() < 100.
+            && temp.temperature_units.is_none()
+        {
+            warnings.push(MetaError::warning(
+                "/temperature/temperature",
+                ErrorCode::PossiblyCelsius,
+                format!(
+                    r#""{degrees}" may be Celsius; set temperature_units = "C" to convert"#
+                ),
+            ));
+        }
+
+        if let Some(timestep) = &self.timestep_information
+            && let Some(step) = timestep.integration_time_step
+            && let Some(factor) = timestep_factor_to_fs(
+                timestep.timestep_units.as_deref().unwrap_or("fs"),
+            )
+            && let Some(warning) = check_timestep(
+                "/timestep_information/integration_time_step",
+                step * factor,
+                timestep.hydrogen_mass_repartitioning == Some(true),
+            )
+        {
+            warnings.push(warning);
+        }
+
+        if let Some(ph) = self.protonation_method.as_ref().and_then(|p| p.ph)
+            && let Some(warning) = check_ph("/protonation_method/ph", ph)
+            && warning.is_warning()
+        {
+            warnings.push(warning);
+        }
+
+        if let Some(water) = &self.water
+            && let (Some(density), Some(units)) =
+                (water.density, &water.water_density_units)
+            && density.is_finite()
+            && density > 0.
+            && let Some(factor) = density_factor_to_kg_m3(units)
+            && !(MIN_WATER_DENSITY_KG_M3..=MAX_WATER_DENSITY_KG_M3)
+                .contains(&(density * factor))
+        {
+            warnings.push(MetaError::warning(
+                "/water/density",
+                ErrorCode::ImplausibleDensity,
+                format!(
+                    "{density} {units} is outside the plausible range for liquid \
+                    water ({MIN_WATER_DENSITY_KG_M3}-{MAX_WATER_DENSITY_KG_M3} kg/m^3)"
+                ),
+            ));
+        }
+
+        if let Some(model) = self.water.as_ref().and_then(|water| water.model.as_ref())
+            && let Some(warning) = check_known_name(
+                "/water/model",
+                ErrorCode::UnknownWaterModel,
+                model,
+                WATER_MODELS,
+            )
+        {
+            warnings.push(warning);
+        }
+
+        if let Some(names) = self
+            .forcefield
+            .as_ref()
+            .and_then(|ff| ff.forcefield.as_ref())
+        {
+            for (path, name) in one_or_many_paths("/forcefield/forcefield", names) {
+                warnings.extend(check_known_name(
+                    &path,
+                    ErrorCode::UnknownForcefield,
+                    name,
+                    FORCEFIELDS,
+                ));
+            }
+        }
+
+        if let Some(forcefield) = &self.forcefield
+            && forcefield.forcefield.as_ref().is_none_or(Vec::is_empty)
+            && forcefield.forcefield_comments.is_some()
+        {
+            warnings.push(MetaError::warning(
+                "/forcefield/forcefield_comments",
+                ErrorCode::ForcefieldIncomplete,
+                "present without forcefield.forcefield",
+            ));
+        }
+
+        if let Some(method) = self
+            .protonation_method
+            .as_ref()
+            .and_then(|method| method.protonation_method.as_ref())
+            && let Some(warning) = check_known_name(
+                "/protonation_method/protonation_method",
+                ErrorCode::UnknownProtonationMethod,
+                method,
+                PROTONATION_METHODS,
+            )
+        {
+            warnings.push(warning);
+        }
+
+        warnings.extend(check_single_primary(
+            "papers",
+            "primary",
+            self.papers
                 .iter()
-                .map(|paper| {
-                    let volume = if let Numlike::TomlVal(val) = &paper.volume {
-                        match val {
-                            TomlValue::String(v) => Numlike::Stringy(v.to_string()),
-                            TomlValue::Integer(v) => Numlike::Stringy(v.to_string()),
-                            TomlValue::Float(v) => Numlike::Stringy(v.to_string()),
-                            _ => Numlike::Stringy("".to_string()),
-                        }
-                    } else {
-                        paper.volume.clone()
-                    };
-
-                    let number = paper.number.clone().map(|val| {
-                        if let Numlike::TomlVal(n) = val {
-                            match n {
-                                TomlValue::String(v) => Numlike::Stringy(v.to_string()),
-                                TomlValue::Integer(v) => {
-                                    Numlike::Stringy(v.to_string())
-                                }
-                                TomlValue::Float(v) => Numlike::Stringy(v.to_string()),
-                                _ => Numlike::Stringy("".to_string()),
-                            }
-                        } else {
-                            val.clone()
-                        }
-                    });
+                .flatten()
+                .map(|p| p.primary == Some(true)),
+        ));
+        warnings.extend(check_single_primary(
+            "ligands",
+            "primary",
+            self.ligands
+                .iter()
+                .flatten()
+                .map(|l| l.primary == Some(true)),
+        ));
+        warnings.extend(check_single_primary(
+            "proteins",
+            "primary",
+            self.proteins.iter().flatten().map(Protein::is_primary),
+        ));
+
+        // E.g., solvents that to_canon moved out of initial.solvents
+        for (index, solvent) in self.solvents.iter().flatten().enumerate() {
+            if solvent.ion_concentration.is_none() {
+                warnings.push(MetaError::warning(
+                    &format!("/solvents/{index}/ion_concentration"),
+                    ErrorCode::UnknownConcentration,
+                    format!(r#"is unknown for "{}""#, solvent.name),
+                ));
+            }
+
+            // Those to_canon couldn't convert to mol/L
+            if let Some(units) = &solvent.solvent_concentration_units
+                && concentration_divisor_to_mol_l(units).is_none()
+            {
+                warnings.push(MetaError::warning(
+                    &format!("/solvents/{index}/solvent_concentration_units"),
+                    ErrorCode::UnknownUnits,
+                    format!(r#"unrecognized units "{units}""#),
+                ));
+            }
+        }
+
+        // E.g., ligands that to_canon moved out of initial.ligands
+        for (index, ligand) in self.ligands.iter().flatten().enumerate() {
+            if ligand.smiles.trim().is_empty() {
+                warnings.push(MetaError::warning(
+                    &format!("/ligands/{index}/smiles"),
+                    ErrorCode::MissingSmiles,
+                    format!(r#"is empty for "{}""#, ligand.name),
+                ));
+            }
+        }
+        warnings.extend(check_duplicate_smiles(
+            self.ligands.iter().flatten().map(|l| l.smiles.as_str()),
+        ));
+
+        warnings
+    }
+
+    // Apply the fixes that have an unambiguous correction and return the
+    // findings they resolved
+    pub fn auto_fix(&mut self) -> Vec<MetaError> {
+        let fixed: Vec<_> = self
+            .find_warnings()
+            .into_iter()
+            .filter(|warning| AUTO_FIXABLE.contains(&warning.code))
+            .collect();
+
+        for text in [
+            &mut self.initial.short_description,
+            &mut self.initial.description,
+        ] {
+            if text.as_ref().is_some_and(|val| val.trim().is_empty()) {
+                *text = None;
+            }
+        }
+
+        if let Some(water) = self.water.as_mut()
+            && !water.is_present
+        {
+            water.model = None;
+            water.density = None;
+            water.water_density_units = None;
+        }
+
+        fixed
+    }
+
+    // How "other" differs from this record, field by field
+    pub fn diff(&self, other: &MetaV1) -> Result<Vec<FieldChange>> {
+        diff::diff(self, other).map_err(Into::into)
+    }
+
+    // Sort the lists whose order carries no meaning so that equivalent
+    // records serialize identically. The sorts are stable, so, e.g.,
+    // contributors with the same name keep their order.
+    pub fn canonicalize(&mut self) {
+        if let Some(contributors) = self.contributors.as_mut() {
+            contributors.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        if let Some(permissions) = self.simulation_permissions.as_mut() {
+            permissions.sort_by(|a, b| a.user_orcid.cmp(&b.user_orcid));
+        }
+        if let Some(proteins) = self.proteins.as_mut() {
+            proteins.sort_by(|a, b| a.molecule_id().cmp(b.molecule_id()));
+        }
+        if let Some(papers) = self.papers.as_mut() {
+            papers.sort_by(|a, b| (a.year, &a.title).cmp(&(b.year, &b.title)));
+        }
+    }
+
+    // Returns a log of the values that were rewritten
+    #[allow(clippy::wrong_self_convention)]
+    pub(crate) fn to_canon(&mut self) -> Result<Vec<CanonChange>, MdrMetaError> {
+        let mut canon_log = vec![];
+
+        canon_datelike("/initial/date", &mut self.initial.date, &mut canon_log);
+
+        // People put bare names in the wrong place, so move them where they
+        // belong with the rest left for find_warnings to flag
+        if let Some(names) = self.initial.ligands.take() {
+            let ligands = self.ligands.get_or_insert_with(Vec::new);
+            for (index, name) in names.into_iter().enumerate() {
+                canon_log.push(CanonChange::new(
+                    &format!("/initial/ligands/{index}"),
+                    &name,
+                    "moved to ligands",
+                ));
+                ligands.push(Ligand {
+                    primary: None,
+                    name,
+                    smiles: "".to_string(),
+                });
+            }
+        }
 
+        if let Some(names) = self.initial.solvents.take() {
+            let solvents = self.solvents.get_or_insert_with(Vec::new);
+            for (index, name) in names.into_iter().enumerate() {
+                canon_log.push(CanonChange::new(
+                    &format!("/initial/solvents/{index}"),
+                    &name,
+                    "moved to solvents",
+                ));
+                solvents.push(Solvent {
+                    name,
+                    ion_concentration: None,
+                    solvent_concentration_units: None,
+                    charge: None,
+                });
+            }
+        }
+
+        if let Some(papers) = &self.papers {
+            let new_papers = papers
+                .iter()
+                .enumerate()
+                .map(|(index, paper)| {
                     let mut new_paper = paper.clone();
-                    new_paper.volume = volume;
-                    new_paper.number = number;
-                    new_paper
+                    new_paper.volume = canon_numlike("volume", &paper.volume)?;
+                    new_paper.number = paper
+                        .number
+                        .as_ref()
+                        .map(|val| canon_numlike("number", val))
+                        .transpose()?;
+                    new_paper.doi = paper.doi.as_deref().map(canon_doi);
+                    if new_paper.doi != paper.doi
+                        && let (Some(from), Some(to)) = (&paper.doi, &new_paper.doi)
+                    {
+                        canon_log.push(CanonChange::new(
+                            &format!("/papers/{index}/doi"),
+                            from,
+                            to,
+                        ));
+                    }
+                    Ok(new_paper)
                 })
-                .collect();
+                .collect::<Result<Vec<_>, MdrMetaError>>()?;
 
             self.papers = Some(new_papers);
         }
 
+        // Store temperatures in Kelvin
+        if let Some(temp) = &mut self.temperature
+            && let (Some(degrees), Some(units)) =
+                (temp.temperature, &temp.temperature_units)
+            && is_celsius(units)
+        {
+            let kelvin = degrees.celsius_to_kelvin();
+            canon_log.push(CanonChange::new(
+                "/temperature/temperature",
+                format!("{degrees} {units}"),
+                format!("{kelvin} K"),
+            ));
+            temp.temperature = Some(kelvin);
+            temp.temperature_units = Some("K".to_string());
+        }
+
+        // Store timesteps in fs
+        if let Some(timestep) = &mut self.timestep_information
+            && let (Some(step), Some(units)) =
+                (timestep.integration_time_step, &timestep.timestep_units)
+            && step.is_finite()
+            && units != "fs"
+            && let Some(factor) = timestep_factor_to_fs(units)
+        {
+            let canon = step * factor;
+            canon_log.push(CanonChange::new(
+                "/timestep_information/integration_time_step",
+                format!("{step} {units}"),
+                format!("{canon} fs"),
+            ));
+            timestep.integration_time_step = Some(canon);
+            timestep.timestep_units = Some("fs".to_string());
+        }
+
+        // Store densities in kg/m^3, the unit V2 uses
+        if let Some(water) = &mut self.water
+            && let (Some(density), Some(units)) =
+                (water.density, &water.water_density_units)
+            && density.is_finite()
+            && units != "kg/m^3"
+            && let Some(factor) = density_factor_to_kg_m3(units)
+        {
+            let canon = density * factor;
+            canon_log.push(CanonChange::new(
+                "/water/density",
+                format!("{density} {units}"),
+                format!("{canon} kg/m^3"),
+            ));
+            water.density = Some(canon);
+            water.water_density_units = Some("kg/m^3".to_string());
+        }
+
+        // Store concentrations in mol/L, the unit V2 uses
+        for (index, solvent) in self.solvents.iter_mut().flatten().enumerate() {
+            if let Some(units) = &solvent.solvent_concentration_units
+                && let Some(conc) = solvent.ion_concentration
+                && conc.is_finite()
+                && units != "mol/L"
+                && let Some(divisor) = concentration_divisor_to_mol_l(units)
+            {
+                let canon = conc / divisor;
+                canon_log.push(CanonChange::new(
+                    &format!("/solvents/{index}/ion_concentration"),
+                    format!("{conc} {units}"),
+                    format!("{canon} mol/L"),
+                ));
+                solvent.ion_concentration = Some(canon);
+                solvent.solvent_concentration_units = Some("mol/L".to_string());
+            }
+        }
+
         // Older versions of the TOML had separate fields for PDB/Uniprot
         if let Some(proteins) = &self.proteins {
             let new_proteins: Vec<_> = proteins
                 .iter()
-                .map(|protein| match protein {
-                    Protein::ProteinOldPDB { primary, pdb_id } => Protein::ProteinNew {
-                        primary: primary.clone(),
-                        molecule_id_type: "PDB".to_string(),
-                        molecule_id: pdb_id.clone(),
-                    },
+                .enumerate()
+                .map(|(index, protein)| match protein {
+                    Protein::ProteinOldPDB { primary, pdb_id } => {
+                        canon_log.push(CanonChange::new(
+                            &format!("/proteins/{index}"),
+                            format!(r#"pdb_id = "{pdb_id}""#),
+                            format!(
+                                r#"molecule_id_type = "PDB", molecule_id = "{pdb_id}""#
+                            ),
+                        ));
+                        Protein::ProteinNew {
+                            primary: *primary,
+                            molecule_id_type: "PDB".to_string(),
+                            molecule_id: pdb_id.clone(),
+                        }
+                    }
                     Protein::ProteinOldUniprot {
                         primary,
                         uniprot_id,
-                    } => Protein::ProteinNew {
-                        primary: primary.clone(),
-                        molecule_id_type: "Uniprot".to_string(),
-                        molecule_id: uniprot_id.clone(),
-                    },
+                    } => {
+                        canon_log.push(CanonChange::new(
+                            &format!("/proteins/{index}"),
+                            format!(r#"uniprot_id = "{uniprot_id}""#),
+                            format!(
+                                r#"molecule_id_type = "Uniprot", molecule_id = "{uniprot_id}""#
+                            ),
+                        ));
+                        Protein::ProteinNew {
+                            primary: *primary,
+                            molecule_id_type: "Uniprot".to_string(),
+                            molecule_id: uniprot_id.clone(),
+                        }
+                    }
                     _ => protein.clone(),
                 })
                 .collect();
 
             self.proteins = Some(new_proteins);
         }
-        Ok(())
+
+        self.canon_whitespace(&mut canon_log);
+
+        Ok(canon_log)
+    }
+
+    // Stray spaces and tabs in pasted values fail the ID patterns, so trim
+    // IDs and file names and collapse single-line text; multi-line text such
+    // as the description and commands keeps its formatting
+    fn canon_whitespace(&mut self, canon_log: &mut Vec<CanonChange>) {
+        let mut trim =
+            |path: &str, val: &mut String| canon_spaces(path, val, false, canon_log);
+        let initial = &mut self.initial;
+        trim(
+            "/initial/lead_contributor_orcid",
+            &mut initial.lead_contributor_orcid,
+        );
+        if let Some(link) = initial.external_link.as_mut() {
+            trim("/initial/external_link", link);
+        }
+        if let Some(version) = self.software.version.as_mut() {
+            trim("/software/version", version);
+        }
+        if let Some(files) = self.required_files.as_mut() {
+            trim(
+                "/required_files/trajectory_file_name",
+                &mut files.trajectory_file_name,
+            );
+            trim(
+                "/required_files/structure_file_name",
+                &mut files.structure_file_name,
+            );
+            trim(
+                "/required_files/topology_file_name",
+                &mut files.topology_file_name,
+            );
+        }
+        for (index, file) in self.additional_files.iter_mut().flatten().enumerate() {
+            trim(
+                &format!("/additional_files/{index}/additional_file_type"),
+                &mut file.additional_file_type,
+            );
+            trim(
+                &format!("/additional_files/{index}/additional_file_name"),
+                &mut file.additional_file_name,
+            );
+        }
+        for (index, protein) in self.proteins.iter_mut().flatten().enumerate() {
+            if let Protein::ProteinNew {
+                molecule_id_type,
+                molecule_id,
+                ..
+            } = protein
+            {
+                trim(
+                    &format!("/proteins/{index}/molecule_id_type"),
+                    molecule_id_type,
+                );
+                trim(&format!("/proteins/{index}/molecule_id"), molecule_id);
+            }
+        }
+        for (index, ligand) in self.ligands.iter_mut().flatten().enumerate() {
+            trim(&format!("/ligands/{index}/smiles"), &mut ligand.smiles);
+        }
+        for (index, paper) in self.papers.iter_mut().flatten().enumerate() {
+            if let Some(pages) = paper.pages.as_mut() {
+                trim(&format!("/papers/{index}/pages"), pages);
+            }
+        }
+        for (index, contributor) in self.contributors.iter_mut().flatten().enumerate() {
+            if let Some(orcid) = contributor.orcid.as_mut() {
+                trim(&format!("/contributors/{index}/orcid"), orcid);
+            }
+            if let Some(email) = contributor.email.as_mut() {
+                trim(&format!("/contributors/{index}/email"), email);
+            }
+        }
+        for (index, perm) in
+            self.simulation_permissions.iter_mut().flatten().enumerate()
+        {
+            trim(
+                &format!("/simulation_permissions/{index}/user_orcid"),
+                &mut perm.user_orcid,
+            );
+        }
+
+        let mut collapse =
+            |path: &str, val: &mut String| canon_spaces(path, val, true, canon_log);
+        if let Some(desc) = self.initial.short_description.as_mut() {
+            collapse("/initial/short_description", desc);
+        }
+        collapse("/software/name", &mut self.software.name);
+        for (index, ligand) in self.ligands.iter_mut().flatten().enumerate() {
+            collapse(&format!("/ligands/{index}/name"), &mut ligand.name);
+        }
+        for (index, solvent) in self.solvents.iter_mut().flatten().enumerate() {
+            collapse(&format!("/solvents/{index}/name"), &mut solvent.name);
+        }
+        if let Some(model) = self.water.as_mut().and_then(|w| w.model.as_mut()) {
+            collapse("/water/model", model);
+        }
+        if let Some(names) =
+            self.forcefield.as_mut().and_then(|f| f.forcefield.as_mut())
+        {
+            let paths: Vec<_> = one_or_many_paths("/forcefield/forcefield", names)
+                .map(|(path, _)| path)
+                .collect();
+            for (path, name) in paths.iter().zip(names.iter_mut()) {
+                collapse(path, name);
+            }
+        }
+        if let Some(method) = self
+            .protonation_method
+            .as_mut()
+            .and_then(|p| p.protonation_method.as_mut())
+        {
+            collapse("/protonation_method/protonation_method", method);
+        }
+        for (index, paper) in self.papers.iter_mut().flatten().enumerate() {
+            collapse(&format!("/papers/{index}/title"), &mut paper.title);
+            collapse(&format!("/papers/{index}/authors"), &mut paper.authors);
+            collapse(&format!("/papers/{index}/journal"), &mut paper.journal);
+        }
+        for (index, contributor) in self.contributors.iter_mut().flatten().enumerate() {
+            collapse(
+                &format!("/contributors/{index}/name"),
+                &mut contributor.name,
+            );
+            if let Some(institution) = contributor.institution.as_mut() {
+                collapse(&format!("/contributors/{index}/institution"), institution);
+            }
+        }
     }
 
     // Create an example with every field with valid values
@@ -609,6 +2279,8 @@ impl MetaV1 {
                 ),
                 simulation_is_restricted: Some(false),
                 scientific_goal: None,
+                simulation_length_ns: Some(100.),
+                box_dimensions_nm: Some([7.5, 7.5, 9.]),
                 ligands: None,
                 solvents: None,
             },
@@ -642,13 +2314,13 @@ impl MetaV1 {
                 },
                 Contributor {
                     name: "Contributor2".to_string(),
-                    orcid: Some("0000-0000-0000-000X".to_string()),
+                    orcid: Some("0000-0000-0000-001X".to_string()),
                     email: Some("email@anotherplace.edu".to_string()),
                     institution: Some("Some Other Institution".to_string()),
                 },
             ]),
             forcefield: Some(Forcefield {
-                forcefield: Some("Amber99SB-ILDN".to_string()),
+                forcefield: Some(vec!["Amber99SB-ILDN".to_string()]),
                 forcefield_comments: Some("ligand params: GAFF".to_string()),
             }),
             ligands: Some(vec![
@@ -673,6 +2345,7 @@ impl MetaV1 {
                     title: "GPCRmd uncovers the dynamics of the 3D-GPCRome".to_string(),
                     authors: "Rodríguez, I., Fontanals, M., Tielmann, J.S. et al."
                         .to_string(),
+                    author_list: None,
                     journal: "Nat Methods".to_string(),
                     volume: Numlike::Stringy("17".to_string()),
                     number: Some(Numlike::Stringy("4".to_string())),
@@ -688,6 +2361,7 @@ impl MetaV1 {
                     authors: "Ring, A., Manglik, A., Kruse, A., Enos, M., Weis, \
                         W., Garcia, K., Kobilka, B."
                         .to_string(),
+                    author_list: None,
                     journal: "Nature".to_string(),
                     volume: Numlike::Stringy("502".to_string()),
                     number: Some(Numlike::Stringy("7472".to_string())),
@@ -710,19 +2384,21 @@ impl MetaV1 {
             ]),
             protonation_method: Some(Protonation {
                 protonation_method: Some("PROPKA".to_string()),
+                ph: Some(7.),
             }),
             replicates: Some(Replicates {
                 replicate: Some(1),
                 total_replicates: Some(10),
+                merged_replicates: None,
             }),
             simulation_permissions: Some(vec![
                 Permission {
-                    user_orcid: "0000-0000-0000-000X".to_string(),
+                    user_orcid: "0000-0000-0000-002X".to_string(),
                     can_edit: true,
-                    can_view: false,
+                    can_view: true,
                 },
                 Permission {
-                    user_orcid: "0000-0000-0000-001X".to_string(),
+                    user_orcid: "0000-0000-0000-003X".to_string(),
                     can_edit: false,
                     can_view: true,
                 },
@@ -734,27 +2410,82 @@ impl MetaV1 {
             solvents: Some(vec![
                 Solvent {
                     name: "Sodium".to_string(),
-                    ion_concentration: 0.157,
+                    ion_concentration: Some(0.157),
                     solvent_concentration_units: Some("mol/L".to_string()),
+                    charge: None,
                 },
                 Solvent {
                     name: "Chloride".to_string(),
-                    ion_concentration: 0.225,
+                    ion_concentration: Some(0.225),
                     solvent_concentration_units: Some("mol/L".to_string()),
+                    charge: None,
                 },
             ]),
             temperature: Some(Temperature {
-                temperature: Some(273),
+                temperature: Some(Templike::Integer(273)),
+                temperature_units: Some("K".to_string()),
             }),
             timestep_information: Some(Timestep {
                 integration_time_step: Some(2.),
+                timestep_units: Some("fs".to_string()),
+                hydrogen_mass_repartitioning: None,
             }),
             water: Some(Water {
                 is_present: true,
                 model: Some("TIP3P".to_string()),
-                density: Some(0.986),
-                water_density_units: Some("g/m^3".to_string()),
+                density: Some(986.),
+                water_density_units: Some("kg/m^3".to_string()),
             }),
         }
     }
 }
+
+// E.g., `let meta_v2: MetaV2 = meta.try_into()?`
+impl TryFrom<MetaV1> for MetaV2 {
+    type Error = MdrMetaError;
+
+    fn try_from(meta: MetaV1) -> Result<Self, MdrMetaError> {
+        meta.to_v2()
+    }
+}
+
+// E.g., `let meta: Meta = contents.parse()?`, detecting JSON or TOML
+impl FromStr for MetaV1 {
+    type Err = MdrMetaError;
+
+    fn from_str(contents: &str) -> Result<Self, MdrMetaError> {
+        Self::from_string(contents)
+    }
+}
+
+// The canonical TOML
+impl fmt::Display for MetaV1 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_toml().map_err(|_| fmt::Error)?)
+    }
+}
+
+// --------------------------------------------------
+// E.g., "1 protein" or "2 proteins"
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
+// --------------------------------------------------
+// Volume/number may be given as strings or bare numbers but nothing else
+fn canon_numlike(field: &str, val: &Numlike) -> Result<Numlike, MdrMetaError> {
+    match val {
+        Numlike::TomlVal(
+            TomlValue::String(_) | TomlValue::Integer(_) | TomlValue::Float(_),
+        ) => Ok(Numlike::Stringy(val.as_str())),
+        Numlike::TomlVal(other) => Err(MdrMetaError::NotNumlike {
+            field: field.to_string(),
+            found: other.type_str().to_string(),
+        }),
+        Numlike::Stringy(_) => Ok(val.clone()),
+    }
+}
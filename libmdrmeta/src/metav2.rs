@@ -1,13 +1,43 @@
-use crate::common::{Datelike, Numlike, RequiredFile, Software};
-use anyhow::{bail, Result};
-//use regex::Regex;
+use crate::{
+    cite::{self, Citation},
+    common::{
+        canon_datelike, canon_molecule_id_type, canon_spaces, check_duplicate_orcids,
+        check_duplicate_smiles, check_known_name, check_molecule_id,
+        check_molecule_id_type, check_ph, check_positive, check_replicate,
+        check_single_primary, check_temperature, check_timestep,
+        concentration_divisor_to_mol_l, ion_charge, molecule_id_type_schema,
+        normalize_input, one_or_many, one_or_many_paths, open_input, primary_or_first,
+        sniff_format, validate_url, Author, CanonChange, Datelike, DoiCache,
+        ExternalIds, Format, Numlike, OneOrMany, RequiredFile, Software, Templike,
+        FORCEFIELDS, MAX_PH, MAX_TEMP_K, MAX_WATER_DENSITY_KG_M3, MIN_PH, MIN_TEMP_K,
+        MIN_WATER_DENSITY_KG_M3, PROTONATION_METHODS, WATER_MODELS,
+    },
+    config::ValidationConfig,
+    crossref::{self, CrossrefWork, PaperFields},
+    diff::{self, FieldChange},
+    error::{ErrorCode, MdrMetaError, MetaError, ParseError, AUTO_FIXABLE},
+    lenient::{self, UnknownFields},
+    metav1::{self, MetaV1},
+    validate::{
+        validate_date, validate_doi, validate_mdrepo_id, validate_orcid,
+        validate_smiles, DATE_PATTERN, DOI_PATTERN, MDREPO_ID_PATTERN, ORCID_PATTERN,
+    },
+};
+use anyhow::Result;
+use chrono::{Datelike as _, NaiveDate};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::{fs, path::Path};
+use std::{
+    collections::HashSet,
+    io::{Read, Write},
+    iter,
+};
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct MetaV2 {
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(regex(pattern = MDREPO_ID_PATTERN))]
     pub mdrepo_id: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -19,9 +49,11 @@ pub struct MetaV2 {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub external_link: Option<String>,
 
+    #[schemars(regex(pattern = ORCID_PATTERN))]
     pub lead_contributor_orcid: String,
 
     // TODO: What is this date? Of creation? Of the experiment? Of submission?
+    #[schemars(with = "String", regex(pattern = DATE_PATTERN))]
     pub date: Datelike,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -35,35 +67,58 @@ pub struct MetaV2 {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub total_replicates: Option<u32>,
 
+    // The replicates sharing this metadata, set in place of replicate_id when
+    // their files are merged
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merged_replicate_ids: Option<Vec<u32>>,
+
     // TODO: Remove -- this should come from data?
     #[serde(skip_serializing_if = "Option::is_none")]
     pub water_is_present: Option<bool>,
 
-    // TODO: Limit to "TIP3P/TIP4P"?
+    // Checked against common::WATER_MODELS
     #[serde(skip_serializing_if = "Option::is_none")]
     pub water_model: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub water_density_kg_m3: Option<f32>,
 
-    // TODO: Limit to "Amber99SB-ILDN", "CHARMM36m", "AMBER"?
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub forcefield: Option<String>,
+    // Each checked against common::FORCEFIELDS
+    #[serde(default, with = "one_or_many", skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<OneOrMany>")]
+    pub forcefield: Option<Vec<String>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub forcefield_comments: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub temperature_kelvin: Option<u32>,
+    #[schemars(with = "Option<f64>", range(min = MIN_TEMP_K, max = MAX_TEMP_K))]
+    pub temperature_kelvin: Option<Templike>,
 
-    // TODO: Limit to "PROPKA", "H++"?
+    // Checked against common::PROTONATION_METHODS
     #[serde(skip_serializing_if = "Option::is_none")]
     pub protonation_method: Option<String>,
 
-    // TODO: Is "ns" the correct unit?
+    // The pH the protonation states were assigned for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = MIN_PH, max = MAX_PH))]
+    pub ph: Option<f64>,
+
+    // Typically 1-4 fs, i.e., 0.000001-0.000004 ns
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timestep_ns: Option<f64>,
 
+    // Allows timesteps above 4 fs, up to common::MAX_HMR_TIMESTEP_FS
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hydrogen_mass_repartitioning: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simulation_length_ns: Option<f64>,
+
+    // The x, y, and z edges of the periodic box
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub box_dimensions_nm: Option<[f64; 3]>,
+
     pub required_file: RequiredFile,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -93,7 +148,7 @@ pub struct MetaV2 {
     pub simulation_permissions: Option<Vec<Permission>>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct AdditionalFile {
     pub file_type: String,
@@ -104,12 +159,13 @@ pub struct AdditionalFile {
     pub description: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Contributor {
     pub name: String,
 
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(regex(pattern = ORCID_PATTERN))]
     pub orcid: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -119,9 +175,10 @@ pub struct Contributor {
     pub institution: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Permission {
+    #[schemars(regex(pattern = ORCID_PATTERN))]
     pub user_orcid: String,
 
     pub can_edit: bool,
@@ -129,7 +186,7 @@ pub struct Permission {
     pub can_view: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Paper {
     #[serde(skip_serializing_if = "Option::is_none", alias = "primary")]
@@ -137,8 +194,12 @@ pub struct Paper {
 
     pub title: String,
 
+    // Kept as given; "author_list" is preferred for citations when present
     pub authors: String,
 
+    #[serde(skip_serializing_if = "Option::is_none", alias = "author")]
+    pub author_list: Option<Vec<Author>>,
+
     pub journal: String,
 
     pub volume: Numlike,
@@ -151,11 +212,29 @@ pub struct Paper {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pages: Option<String>,
 
+    // Parsed from "pages" when converting from V1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_start: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_end: Option<u32>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(regex(pattern = DOI_PATTERN))]
     pub doi: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+impl Paper {
+    pub fn volume_str(&self) -> String {
+        self.volume.as_str()
+    }
+
+    pub fn number_str(&self) -> Option<String> {
+        self.number.as_ref().map(Numlike::as_str)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Ligand {
     #[serde(skip_serializing_if = "Option::is_none", alias = "primary")]
@@ -166,7 +245,7 @@ pub struct Ligand {
     pub smiles: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct RequiredFiles {
     pub trajectory_file_name: String,
@@ -176,18 +255,18 @@ pub struct RequiredFiles {
     pub topology_file_name: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 pub struct Protein {
     #[serde(skip_serializing_if = "Option::is_none")]
-    is_primary: Option<bool>,
+    pub is_primary: Option<bool>,
 
-    // TODO: Limit to "PDB," "Uniprot," and "Unknown"?
-    molecule_id_type: String,
+    #[schemars(schema_with = "molecule_id_type_schema")]
+    pub molecule_id_type: String,
 
-    molecule_id: String,
+    pub molecule_id: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Solvent {
     pub name: String,
@@ -197,48 +276,278 @@ pub struct Solvent {
     // TODO: Remove and put unit into "ion_concentration"?
     #[serde(skip_serializing_if = "Option::is_none")]
     pub concentration_units: Option<String>,
+
+    // Defaults to the charge in common::ION_CHARGES
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub charge: Option<i32>,
 }
 
 impl MetaV2 {
     //[pyfunction]
-    pub fn from_toml(toml: &str) -> Result<Self> {
-        let meta: Self = toml::from_str(toml)?;
-        Ok(meta)
+    pub fn from_toml(toml: &str) -> Result<Self, MdrMetaError> {
+        Self::from_toml_with_log(toml).map(|(meta, _)| meta)
+    }
+
+    // V2 values need no unit conversion, so the log only has the date and
+    // whitespace canonicalized as for V1
+    pub fn from_toml_with_log(
+        toml: &str,
+    ) -> Result<(Self, Vec<CanonChange>), MdrMetaError> {
+        let mut meta: Self =
+            toml::from_str(toml).map_err(|e| ParseError::from_toml(e, toml))?;
+        let canon_log = meta.to_canon();
+        Ok((meta, canon_log))
     }
 
     //[pyfunction]
-    pub fn from_json(json: &str) -> Result<Self> {
-        let meta: Self = serde_json::from_str(json)?;
-        Ok(meta)
+    pub fn from_json(json: &str) -> Result<Self, MdrMetaError> {
+        Self::from_json_with_log(json).map(|(meta, _)| meta)
+    }
+
+    pub fn from_json_with_log(
+        json: &str,
+    ) -> Result<(Self, Vec<CanonChange>), MdrMetaError> {
+        let mut meta: Self =
+            serde_json::from_str(json).map_err(ParseError::from_json)?;
+        let canon_log = meta.to_canon();
+        Ok((meta, canon_log))
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(yaml: &str) -> Result<Self, MdrMetaError> {
+        Self::from_yaml_with_log(yaml).map(|(meta, _)| meta)
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml_with_log(
+        yaml: &str,
+    ) -> Result<(Self, Vec<CanonChange>), MdrMetaError> {
+        let mut meta: Self =
+            serde_yaml::from_str(yaml).map_err(ParseError::from_yaml)?;
+        let canon_log = meta.to_canon();
+        Ok((meta, canon_log))
     }
 
     //[pyfunction]
-    pub fn from_string(contents: &str) -> Result<Self> {
-        let meta = if contents.starts_with("{") {
-            Self::from_json(contents)?
-        } else {
-            Self::from_toml(contents)?
-        };
-        Ok(meta)
+    pub fn from_string(contents: &str) -> Result<Self, MdrMetaError> {
+        Self::from_string_with_log(contents).map(|(meta, _)| meta)
+    }
+
+    pub fn from_string_with_log(
+        contents: &str,
+    ) -> Result<(Self, Vec<CanonChange>), MdrMetaError> {
+        let contents = &normalize_input(contents);
+        match sniff_format(contents) {
+            Format::Json => Self::from_json_with_log(contents),
+            Format::Toml => Self::from_toml_with_log(contents),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => Self::from_yaml_with_log(contents),
+        }
     }
 
     //[pyfunction]
-    pub fn from_file(filename: &str) -> Result<Self> {
-        match Path::new(filename).extension() {
-            Some(ext) => {
-                let contents = fs::read_to_string(filename)?;
-                if contents.is_empty() {
-                    bail!("File is empty")
-                }
-                let meta = match ext.to_str() {
-                    Some("json") => Self::from_json(&contents)?,
-                    Some("toml") => Self::from_toml(&contents)?,
-                    _ => bail!(r#"Unknown file extension "{}""#, ext.display()),
-                };
-                Ok(meta)
+    pub fn from_file(filename: &str) -> Result<Self, MdrMetaError> {
+        let (reader, format) = open_input(filename)?;
+        Self::from_reader(reader, format)
+    }
+
+    pub fn from_reader<R: Read>(
+        reader: R,
+        format: Format,
+    ) -> Result<Self, MdrMetaError> {
+        Self::from_reader_with_log(reader, format).map(|(meta, _)| meta)
+    }
+
+    // TOML can't be parsed incrementally, so the input is read in full
+    pub fn from_reader_with_log<R: Read>(
+        mut reader: R,
+        format: Format,
+    ) -> Result<(Self, Vec<CanonChange>), MdrMetaError> {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        let contents = normalize_input(&contents);
+        if contents.is_empty() {
+            return Err(MdrMetaError::Empty);
+        }
+        match format {
+            Format::Json => Self::from_json_with_log(&contents),
+            Format::Toml => Self::from_toml_with_log(&contents),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => Self::from_yaml_with_log(&contents),
+        }
+    }
+
+    // Like from_string, but keys the schema doesn't know are set aside and
+    // returned instead of failing the parse
+    pub fn from_string_lenient(
+        contents: &str,
+    ) -> Result<(Self, UnknownFields), MdrMetaError> {
+        let format = sniff_format(&normalize_input(contents));
+        Self::from_str_lenient_with_log(contents, format)
+            .map(|(meta, _, unknown)| (meta, unknown))
+    }
+
+    pub fn from_file_lenient(
+        filename: &str,
+    ) -> Result<(Self, UnknownFields), MdrMetaError> {
+        let (mut reader, format) = open_input(filename)?;
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        Self::from_str_lenient_with_log(&contents, format)
+            .map(|(meta, _, unknown)| (meta, unknown))
+    }
+
+    pub(crate) fn from_str_lenient_with_log(
+        contents: &str,
+        format: Format,
+    ) -> Result<(Self, Vec<CanonChange>, UnknownFields), MdrMetaError> {
+        let (mut meta, unknown) = lenient::from_str_lenient::<Self>(contents, format)?;
+        let mut canon_log = meta.to_canon();
+        canon_log.extend(meta.canon_molecule_id_types());
+        Ok((meta, canon_log, unknown))
+    }
+
+    // Returns a log of the values that were rewritten
+    #[allow(clippy::wrong_self_convention)]
+    fn to_canon(&mut self) -> Vec<CanonChange> {
+        let mut canon_log = vec![];
+        canon_datelike("/date", &mut self.date, &mut canon_log);
+        canon_log.extend(self.canon_whitespace());
+        canon_log
+    }
+
+    // As for V1, trim IDs and file names and collapse single-line text
+    fn canon_whitespace(&mut self) -> Vec<CanonChange> {
+        let mut canon_log = vec![];
+        let mut trim = |path: &str, val: &mut String| {
+            canon_spaces(path, val, false, &mut canon_log)
+        };
+        trim("/lead_contributor_orcid", &mut self.lead_contributor_orcid);
+        if let Some(link) = self.external_link.as_mut() {
+            trim("/external_link", link);
+        }
+        if let Some(version) = self.software.version.as_mut() {
+            trim("/software/version", version);
+        }
+        let files = &mut self.required_file;
+        trim(
+            "/required_file/trajectory_file_name",
+            &mut files.trajectory_file_name,
+        );
+        trim(
+            "/required_file/structure_file_name",
+            &mut files.structure_file_name,
+        );
+        trim(
+            "/required_file/topology_file_name",
+            &mut files.topology_file_name,
+        );
+        for (index, file) in self.additional_files.iter_mut().flatten().enumerate() {
+            trim(
+                &format!("/additional_files/{index}/file_type"),
+                &mut file.file_type,
+            );
+            trim(
+                &format!("/additional_files/{index}/file_name"),
+                &mut file.file_name,
+            );
+        }
+        for (index, protein) in self.proteins.iter_mut().flatten().enumerate() {
+            trim(
+                &format!("/proteins/{index}/molecule_id_type"),
+                &mut protein.molecule_id_type,
+            );
+            trim(
+                &format!("/proteins/{index}/molecule_id"),
+                &mut protein.molecule_id,
+            );
+        }
+        for (index, ligand) in self.ligands.iter_mut().flatten().enumerate() {
+            trim(&format!("/ligands/{index}/smiles"), &mut ligand.smiles);
+        }
+        for (index, paper) in self.papers.iter_mut().flatten().enumerate() {
+            if let Some(pages) = paper.pages.as_mut() {
+                trim(&format!("/papers/{index}/pages"), pages);
             }
-            _ => bail!("No file extension"),
         }
+        for (index, contributor) in self.contributors.iter_mut().flatten().enumerate() {
+            if let Some(orcid) = contributor.orcid.as_mut() {
+                trim(&format!("/contributors/{index}/orcid"), orcid);
+            }
+            if let Some(email) = contributor.email.as_mut() {
+                trim(&format!("/contributors/{index}/email"), email);
+            }
+        }
+        for (index, perm) in
+            self.simulation_permissions.iter_mut().flatten().enumerate()
+        {
+            trim(
+                &format!("/simulation_permissions/{index}/user_orcid"),
+                &mut perm.user_orcid,
+            );
+        }
+
+        let mut collapse = |path: &str, val: &mut String| {
+            canon_spaces(path, val, true, &mut canon_log)
+        };
+        if let Some(desc) = self.short_description.as_mut() {
+            collapse("/short_description", desc);
+        }
+        collapse("/software/name", &mut self.software.name);
+        for (index, ligand) in self.ligands.iter_mut().flatten().enumerate() {
+            collapse(&format!("/ligands/{index}/name"), &mut ligand.name);
+        }
+        for (index, solvent) in self.solvents.iter_mut().flatten().enumerate() {
+            collapse(&format!("/solvents/{index}/name"), &mut solvent.name);
+        }
+        if let Some(model) = self.water_model.as_mut() {
+            collapse("/water_model", model);
+        }
+        for (index, name) in self.forcefield.iter_mut().flatten().enumerate() {
+            collapse(&format!("/forcefield/{index}"), name);
+        }
+        if let Some(method) = self.protonation_method.as_mut() {
+            collapse("/protonation_method", method);
+        }
+        for (index, paper) in self.papers.iter_mut().flatten().enumerate() {
+            collapse(&format!("/papers/{index}/title"), &mut paper.title);
+            collapse(&format!("/papers/{index}/authors"), &mut paper.authors);
+            collapse(&format!("/papers/{index}/journal"), &mut paper.journal);
+        }
+        for (index, contributor) in self.contributors.iter_mut().flatten().enumerate() {
+            collapse(
+                &format!("/contributors/{index}/name"),
+                &mut contributor.name,
+            );
+            if let Some(institution) = contributor.institution.as_mut() {
+                collapse(&format!("/contributors/{index}/institution"), institution);
+            }
+        }
+        canon_log
+    }
+
+    // As for V1
+    pub(crate) fn canon_molecule_id_types(&mut self) -> Vec<CanonChange> {
+        let mut canon_log = vec![];
+        for (index, protein) in self.proteins.iter_mut().flatten().enumerate() {
+            if let Some(canon) = canon_molecule_id_type(&protein.molecule_id_type)
+                && canon != protein.molecule_id_type
+            {
+                canon_log.push(CanonChange::new(
+                    &format!("/proteins/{index}/molecule_id_type"),
+                    protein.molecule_id_type.as_str(),
+                    canon,
+                ));
+                protein.molecule_id_type = canon.to_string();
+            }
+        }
+        canon_log
+    }
+
+    // Describes the file format, including the patterns and ranges that
+    // find_errors checks, for clients such as web forms
+    pub fn json_schema() -> serde_json::Value {
+        schemars::schema_for!(Self).to_value()
     }
 
     //[pyfunction]
@@ -251,312 +560,1119 @@ impl MetaV2 {
         toml::to_string_pretty(&self).map_err(Into::into)
     }
 
+    // Writes back the keys a lenient parse set aside, as for V1
+    pub fn to_json_with_unknown(&self, unknown: &UnknownFields) -> Result<String> {
+        let mut value = serde_json::to_value(self)?;
+        unknown.restore(&mut value);
+        serde_json::to_string_pretty(&value).map_err(Into::into)
+    }
+
+    pub fn to_toml_with_unknown(&self, unknown: &UnknownFields) -> Result<String> {
+        let mut value = serde_json::to_value(self)?;
+        unknown.restore(&mut value);
+        toml::to_string_pretty(&value).map_err(Into::into)
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn to_yaml(&self) -> Result<String> {
+        serde_yaml::to_string(&self).map_err(Into::into)
+    }
+
+    pub fn to_writer<W: Write>(&self, mut writer: W, format: Format) -> Result<()> {
+        let contents = match format {
+            Format::Json => self.to_json()?,
+            Format::Toml => self.to_toml()?,
+            #[cfg(feature = "yaml")]
+            Format::Yaml => self.to_yaml()?,
+        };
+        writer.write_all(contents.as_bytes()).map_err(Into::into)
+    }
+
+    // Re-nests the flat fields into the V1 tables. A table is only created
+    // when at least one of its fields is present, and "water.is_present"
+    // is assumed true when only the model or density is given. V1 has no
+    // place for anything V2-only, so those fields are dropped; V1-only
+    // fields (e.g., "water.water_density_units") are left empty.
+    pub fn to_v1(&self) -> MetaV1 {
+        MetaV1 {
+            mdrepo_id: self.mdrepo_id.clone(),
+            initial: metav1::Initial {
+                short_description: self.short_description.clone(),
+                description: self.description.clone(),
+                external_link: self.external_link.clone(),
+                lead_contributor_orcid: self.lead_contributor_orcid.clone(),
+                date: self.date.clone(),
+                commands: self.run_commands.clone(),
+                simulation_is_restricted: self.simulation_is_restricted,
+                scientific_goal: None,
+                simulation_length_ns: self.simulation_length_ns,
+                box_dimensions_nm: self.box_dimensions_nm,
+                ligands: None,
+                solvents: None,
+            },
+            software: self.software.clone(),
+            required_files: Some(self.required_file.clone()),
+            additional_files: self.additional_files.as_ref().map(|files| {
+                files
+                    .iter()
+                    .map(|file| metav1::AdditionalFile {
+                        additional_file_type: file.file_type.clone(),
+                        additional_file_name: file.file_name.clone(),
+                        additional_file_description: file.description.clone(),
+                    })
+                    .collect()
+            }),
+            proteins: self.proteins.as_ref().map(|proteins| {
+                proteins
+                    .iter()
+                    .map(|protein| metav1::Protein::ProteinNew {
+                        primary: protein.is_primary,
+                        molecule_id_type: protein.molecule_id_type.clone(),
+                        molecule_id: protein.molecule_id.clone(),
+                    })
+                    .collect()
+            }),
+            replicates: (self.replicate_id.is_some()
+                || self.total_replicates.is_some()
+                || self.merged_replicate_ids.is_some())
+            .then(|| metav1::Replicates {
+                total_replicates: self.total_replicates,
+                replicate: self.replicate_id,
+                merged_replicates: self.merged_replicate_ids.clone(),
+            }),
+            water: (self.water_is_present.is_some()
+                || self.water_model.is_some()
+                || self.water_density_kg_m3.is_some())
+            .then(|| metav1::Water {
+                is_present: self.water_is_present.unwrap_or(true),
+                model: self.water_model.clone(),
+                density: self.water_density_kg_m3,
+                water_density_units: None,
+            }),
+            ligands: self.ligands.as_ref().map(|ligands| {
+                ligands
+                    .iter()
+                    .map(|ligand| metav1::Ligand {
+                        primary: ligand.is_primary,
+                        name: ligand.name.clone(),
+                        smiles: ligand.smiles.clone(),
+                    })
+                    .collect()
+            }),
+            solvents: self.solvents.as_ref().map(|solvents| {
+                solvents
+                    .iter()
+                    .map(|solvent| metav1::Solvent {
+                        name: solvent.name.clone(),
+                        ion_concentration: Some(solvent.ion_concentration_mol_liter),
+                        solvent_concentration_units: solvent
+                            .concentration_units
+                            .clone(),
+                        charge: solvent.charge,
+                    })
+                    .collect()
+            }),
+            forcefield: (self.forcefield.is_some()
+                || self.forcefield_comments.is_some())
+            .then(|| metav1::Forcefield {
+                forcefield: self.forcefield.clone(),
+                forcefield_comments: self.forcefield_comments.clone(),
+            }),
+            temperature: self.temperature_kelvin.map(|temp| metav1::Temperature {
+                temperature: Some(temp),
+                temperature_units: Some("K".to_string()),
+            }),
+            protonation_method: (self.protonation_method.is_some()
+                || self.ph.is_some())
+            .then(|| metav1::Protonation {
+                protonation_method: self.protonation_method.clone(),
+                ph: self.ph,
+            }),
+            timestep_information: self.timestep_ns.map(|step| metav1::Timestep {
+                integration_time_step: Some(step * 1e6),
+                timestep_units: Some("fs".to_string()),
+                hydrogen_mass_repartitioning: self.hydrogen_mass_repartitioning,
+            }),
+            papers: self.papers.as_ref().map(|papers| {
+                papers
+                    .iter()
+                    .map(|paper| metav1::Paper {
+                        primary: paper.is_primary,
+                        title: paper.title.clone(),
+                        authors: paper.authors.clone(),
+                        author_list: paper.author_list.clone(),
+                        journal: paper.journal.clone(),
+                        volume: paper.volume.clone(),
+                        number: paper.number.clone(),
+                        year: paper.year,
+                        pages: paper.pages.clone(),
+                        doi: paper.doi.clone(),
+                    })
+                    .collect()
+            }),
+            contributors: self.contributors.as_ref().map(|contributors| {
+                contributors
+                    .iter()
+                    .map(|contributor| metav1::Contributor {
+                        name: contributor.name.clone(),
+                        orcid: contributor.orcid.clone(),
+                        email: contributor.email.clone(),
+                        institution: contributor.institution.clone(),
+                    })
+                    .collect()
+            }),
+            simulation_permissions: self.simulation_permissions.as_ref().map(|perms| {
+                perms
+                    .iter()
+                    .map(|perm| metav1::Permission {
+                        user_orcid: perm.user_orcid.clone(),
+                        can_edit: perm.can_edit,
+                        can_view: perm.can_view,
+                    })
+                    .collect()
+            }),
+        }
+    }
+
+    fn citations(&self) -> Vec<Citation<'_>> {
+        self.papers
+            .iter()
+            .flatten()
+            .map(|paper| Citation {
+                title: &paper.title,
+                authors: &paper.authors,
+                author_list: paper.author_list.as_deref(),
+                journal: &paper.journal,
+                volume: &paper.volume,
+                number: paper.number.as_ref(),
+                year: paper.year,
+                pages: paper.pages.clone().or_else(|| {
+                    paper.page_start.map(|start| match paper.page_end {
+                        Some(end) => format!("{start}-{end}"),
+                        _ => start.to_string(),
+                    })
+                }),
+                doi: paper.doi.as_deref(),
+            })
+            .collect()
+    }
+
+    // Fill in empty or missing paper fields from CrossRef by DOI without
+    // overwriting anything; returns the DOIs that failed with their errors
+    #[cfg(feature = "network")]
+    pub fn enrich_papers_from_crossref(&mut self) -> Vec<(String, anyhow::Error)> {
+        self.enrich_papers_with(crossref::fetch_message)
+    }
+
+    // As above with the CrossRef "message" for each DOI from the caller,
+    // e.g., a cache of earlier responses
+    pub fn enrich_papers_with(
+        &mut self,
+        mut fetch: impl FnMut(&str) -> Result<serde_json::Value>,
+    ) -> Vec<(String, anyhow::Error)> {
+        let mut failures = vec![];
+        for paper in self.papers.iter_mut().flatten() {
+            let Some(doi) = paper.doi.clone() else {
+                continue;
+            };
+            match fetch(&doi) {
+                Ok(message) => {
+                    CrossrefWork::from_message(&message).fill(PaperFields {
+                        title: &mut paper.title,
+                        authors: &mut paper.authors,
+                        journal: &mut paper.journal,
+                        volume: &mut paper.volume,
+                        number: &mut paper.number,
+                        pages: &mut paper.pages,
+                        year: &mut paper.year,
+                    });
+                    crossref::fill_page_range(
+                        &paper.pages,
+                        &mut paper.page_start,
+                        &mut paper.page_end,
+                    );
+                }
+                Err(e) => failures.push((doi, e)),
+            }
+        }
+        failures
+    }
+
+    // Whether each paper's DOI resolves at https://doi.org, or None when
+    // that couldn't be determined (e.g., a timeout). Answers are cached in
+    // DoiCache::default_path() so re-runs skip DOIs already checked.
+    #[cfg(feature = "network")]
+    pub fn verify_dois(&self) -> Result<Vec<(String, Option<bool>)>> {
+        let path = DoiCache::default_path();
+        let mut cache = path.as_deref().map(DoiCache::from_file).unwrap_or_default();
+        let results = self.verify_dois_with(&mut cache, crossref::resolve_doi);
+        if let Some(path) = path {
+            cache.to_file(&path)?;
+        }
+        Ok(results)
+    }
+
+    // As above with the cache and the lookup from the caller
+    pub fn verify_dois_with(
+        &self,
+        cache: &mut DoiCache,
+        resolve: impl FnMut(&str) -> Option<bool>,
+    ) -> Vec<(String, Option<bool>)> {
+        let dois = self
+            .papers
+            .iter()
+            .flatten()
+            .filter_map(|p| p.doi.as_deref());
+        crossref::verify_dois(dois, cache, resolve)
+    }
+
+    // (ID, title) for each PDB protein from RCSB and each UniProt protein
+    // from UniProt, fetching each entry once per process
+    #[cfg(feature = "network")]
+    pub fn resolve_protein_names(&self) -> Result<Vec<(String, String)>> {
+        self.resolve_protein_names_with(crate::protein_names::fetch_cached)
+    }
+
+    // As above with the JSON entry for each URL from the caller
+    pub fn resolve_protein_names_with(
+        &self,
+        fetch: impl FnMut(&str) -> Result<serde_json::Value>,
+    ) -> Result<Vec<(String, String)>> {
+        crate::protein_names::resolve_names(&self.external_ids(), fetch)
+    }
+
+    // One BibTeX @article entry per paper, keyed by DOI where there is one
+    pub fn papers_to_bibtex(&self) -> String {
+        cite::to_bibtex(&self.citations())
+    }
+
+    // One RIS record per paper, with an "AU" line per author
+    pub fn papers_to_ris(&self) -> String {
+        cite::to_ris(&self.citations())
+    }
+
+    // The paper flagged primary, else the first
+    pub fn primary_paper(&self) -> Option<&Paper> {
+        primary_or_first(self.papers.as_ref(), |paper| paper.is_primary == Some(true))
+    }
+
+    pub fn primary_ligand(&self) -> Option<&Ligand> {
+        primary_or_first(self.ligands.as_ref(), |ligand| {
+            ligand.is_primary == Some(true)
+        })
+    }
+
+    pub fn primary_protein(&self) -> Option<&Protein> {
+        primary_or_first(self.proteins.as_ref(), |protein| {
+            protein.is_primary == Some(true)
+        })
+    }
+
+    // The ionic strength in mol/L, I = ½ Σ cᵢzᵢ², where cᵢ is the molar
+    // concentration of each ion and zᵢ its charge. None when there are no
+    // solvents or any one's charge or concentration units are unknown.
+    pub fn ionic_strength(&self) -> Option<f64> {
+        let solvents = self.solvents.as_ref().filter(|s| !s.is_empty())?;
+        solvents.iter().try_fold(0., |total, solvent| {
+            let charge = solvent.charge.or_else(|| ion_charge(&solvent.name))?;
+            let divisor = match &solvent.concentration_units {
+                Some(units) => concentration_divisor_to_mol_l(units)?,
+                None => 1.,
+            };
+            let conc = solvent.ion_concentration_mol_liter / divisor;
+            Some(total + 0.5 * conc * f64::from(charge.pow(2)))
+        })
+    }
+
+    pub fn external_ids(&self) -> ExternalIds {
+        let mut ids = ExternalIds::default();
+
+        for protein in self.proteins.iter().flatten() {
+            ids.add_protein(&protein.molecule_id_type, &protein.molecule_id);
+        }
+
+        for doi in self.papers.iter().flatten().filter_map(|p| p.doi.as_ref()) {
+            ids.add_doi(doi);
+        }
+
+        for orcid in self.all_orcids() {
+            ids.add_orcid(orcid);
+        }
+
+        ids
+    }
+
+    // The lead contributor, contributor, and permission ORCIDs, each once in
+    // the order first mentioned
+    pub fn all_orcids(&self) -> Vec<&str> {
+        let contributors = self
+            .contributors
+            .iter()
+            .flatten()
+            .filter_map(|c| c.orcid.as_deref());
+        let permissions = self
+            .simulation_permissions
+            .iter()
+            .flatten()
+            .map(|perm| perm.user_orcid.as_str());
+
+        let mut orcids: Vec<&str> = vec![];
+        for orcid in iter::once(self.lead_contributor_orcid.as_str())
+            .chain(contributors)
+            .chain(permissions)
+        {
+            if !orcids.contains(&orcid) {
+                orcids.push(orcid);
+            }
+        }
+        orcids
+    }
+
+    // None when "section" isn't an optional top-level field
+    pub fn has_section(&self, section: &str) -> Option<bool> {
+        let present = match section {
+            "mdrepo_id" => self.mdrepo_id.is_some(),
+            "short_description" => self.short_description.is_some(),
+            "description" => self.description.is_some(),
+            "external_link" => self.external_link.is_some(),
+            "run_commands" => self.run_commands.is_some(),
+            "replicate_id" => self.replicate_id.is_some(),
+            "total_replicates" => self.total_replicates.is_some(),
+            "merged_replicate_ids" => self.merged_replicate_ids.is_some(),
+            "water_is_present" => self.water_is_present.is_some(),
+            "water_model" => self.water_model.is_some(),
+            "water_density_kg_m3" => self.water_density_kg_m3.is_some(),
+            "forcefield" => self.forcefield.is_some(),
+            "forcefield_comments" => self.forcefield_comments.is_some(),
+            "temperature_kelvin" => self.temperature_kelvin.is_some(),
+            "protonation_method" => self.protonation_method.is_some(),
+            "ph" => self.ph.is_some(),
+            "timestep_ns" => self.timestep_ns.is_some(),
+            "hydrogen_mass_repartitioning" => {
+                self.hydrogen_mass_repartitioning.is_some()
+            }
+            "simulation_length_ns" => self.simulation_length_ns.is_some(),
+            "box_dimensions_nm" => self.box_dimensions_nm.is_some(),
+            "additional_files" => self
+                .additional_files
+                .as_ref()
+                .is_some_and(|v| !v.is_empty()),
+            "proteins" => self.proteins.as_ref().is_some_and(|v| !v.is_empty()),
+            "ligands" => self.ligands.as_ref().is_some_and(|v| !v.is_empty()),
+            "solvents" => self.solvents.as_ref().is_some_and(|v| !v.is_empty()),
+            "papers" => self.papers.as_ref().is_some_and(|v| !v.is_empty()),
+            "contributors" => self.contributors.as_ref().is_some_and(|v| !v.is_empty()),
+            "simulation_is_restricted" => self.simulation_is_restricted.is_some(),
+            "simulation_permissions" => self
+                .simulation_permissions
+                .as_ref()
+                .is_some_and(|v| !v.is_empty()),
+            _ => return None,
+        };
+        Some(present)
+    }
+
+    // True when there are no errors; warnings don't count
+    //[pyfunction]
+    pub fn is_valid(&self) -> bool {
+        self.find_errors().iter().all(MetaError::is_warning)
+    }
+
+    //[pyfunction]
+    pub fn find_errors(&self) -> Vec<MetaError> {
+        self.find_errors_with_config(&ValidationConfig::default())
+    }
+
+    pub fn find_errors_with_config(&self, config: &ValidationConfig) -> Vec<MetaError> {
+        let mut errors = vec![];
+        if let Some(mdrepo_id) = &self.mdrepo_id
+            && let Err(err) = validate_mdrepo_id(mdrepo_id)
+        {
+            errors.push(err.at("/mdrepo_id"));
+        }
+
+        if let Some(err) =
+            check_replicate("/replicate_id", self.replicate_id, self.total_replicates)
+        {
+            errors.push(err);
+        }
+
+        for (index, replicate) in self.merged_replicate_ids.iter().flatten().enumerate()
+        {
+            if let Some(err) = check_replicate(
+                &format!("/merged_replicate_ids/{index}"),
+                Some(*replicate),
+                self.total_replicates,
+            ) {
+                errors.push(err);
+            }
+        }
+
+        if let Some(temp) = self.temperature_kelvin
+            && let Some(err) = check_temperature("/temperature_kelvin", temp, config)
+        {
+            errors.push(err);
+        }
+
+        if let Some(ph) = self.ph
+            && let Some(err) = check_ph("/ph", ph)
+            && !err.is_warning()
+        {
+            errors.push(err);
+        }
+
+        match &self.date {
+            Datelike::Stringy(dt) => {
+                if let Err(err) = validate_date(dt) {
+                    errors.push(err.at("/date"));
+                }
+            }
+            _ => {
+                errors.push(MetaError::error(
+                    "/date",
+                    ErrorCode::InvalidDate,
+                    "invalid date",
+                ));
+            }
+        }
+
+        if let Ok(date) = NaiveDate::parse_from_str(&self.date.to_string(), "%F")
+            && date > config.today()
+        {
+            errors.push(MetaError::error(
+                "/date",
+                ErrorCode::FutureDate,
+                format!(r#""{date}" is in the future"#),
+            ));
+        }
+
+        // An empty link is treated as absent
+        if let Some(link) = &self.external_link
+            && !link.is_empty()
+            && !validate_url(link)
+        {
+            errors.push(MetaError::error(
+                "/external_link",
+                ErrorCode::InvalidUrl,
+                format!(r#"invalid URL "{link}""#),
+            ));
+        }
+
+        if let Some(max_chars) = config.max_description_chars {
+            for (field, text) in [
+                ("/short_description", &self.short_description),
+                ("/description", &self.description),
+            ] {
+                let num_chars = text.as_ref().map_or(0, |val| val.chars().count());
+                if num_chars > max_chars {
+                    errors.push(MetaError::error(
+                        field,
+                        ErrorCode::DescriptionTooLong,
+                        format!("length {num_chars} exceeds maximum of {max_chars}"),
+                    ));
+                }
+            }
+        }
+
+        for section in &config.required_sections {
+            match self.has_section(section) {
+                Some(true) => (),
+                Some(false) => errors.push(MetaError::error(
+                    &format!("/{section}"),
+                    ErrorCode::MissingSection,
+                    "required section is missing",
+                )),
+                None => errors.push(MetaError::error(
+                    &format!("/{section}"),
+                    ErrorCode::UnknownSection,
+                    "unknown section",
+                )),
+            }
+        }
+
+        if let Err(err) = validate_orcid(&self.lead_contributor_orcid) {
+            errors.push(err.at("/lead_contributor_orcid"));
+        }
+
+        let contributor_orcids: Vec<_> = self
+            .contributors
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(index, contributor)| {
+                Some((index, contributor.orcid.as_deref()?))
+            })
+            .collect();
+        for (index, orcid) in &contributor_orcids {
+            if let Err(err) = validate_orcid(orcid) {
+                errors.push(err.at(&format!("/contributors/{index}/orcid")));
+            }
+        }
+
+        let permission_orcids: Vec<_> = self
+            .simulation_permissions
+            .iter()
+            .flatten()
+            .map(|perm| perm.user_orcid.as_str())
+            .enumerate()
+            .collect();
+        for (index, orcid) in &permission_orcids {
+            if let Err(err) = validate_orcid(orcid) {
+                errors.push(
+                    err.at(&format!("/simulation_permissions/{index}/user_orcid")),
+                );
+            }
+        }
+        for (index, perm) in self.simulation_permissions.iter().flatten().enumerate() {
+            if perm.can_edit && !perm.can_view {
+                errors.push(MetaError::error(
+                    &format!("/simulation_permissions/{index}/can_view"),
+                    ErrorCode::EditWithoutView,
+                    "must be true when can_edit is true",
+                ));
+            }
+        }
+        errors.extend(check_duplicate_orcids(
+            &contributor_orcids,
+            &permission_orcids,
+        ));
+
+        // The first occurrence is fine, so only the repeats are flagged
+        let mut seen = HashSet::new();
+        for (index, file) in self.additional_files.iter().flatten().enumerate() {
+            if !seen.insert(&file.file_name) {
+                errors.push(MetaError::error(
+                    &format!("/additional_files/{index}/file_name"),
+                    ErrorCode::DuplicateFileName,
+                    format!(r#"duplicate file name "{}""#, file.file_name),
+                ));
+            }
+        }
+
+        for (index, protein) in self.proteins.iter().flatten().enumerate() {
+            if let Some(err) = check_molecule_id(
+                &format!("/proteins/{index}/molecule_id"),
+                &protein.molecule_id_type,
+                &protein.molecule_id,
+            ) {
+                errors.push(err);
+            }
+            if let Some(err) = check_molecule_id_type(
+                &format!("/proteins/{index}/molecule_id_type"),
+                &protein.molecule_id_type,
+            ) {
+                errors.push(err);
+            }
+        }
+
+        // Empty SMILES are warned about in find_warnings
+        for (index, ligand) in self.ligands.iter().flatten().enumerate() {
+            if !ligand.smiles.is_empty()
+                && let Err(err) = validate_smiles(&ligand.smiles)
+            {
+                errors.push(err.at(&format!("/ligands/{index}/smiles")));
+            }
+        }
+
+        // 1665 saw the first scientific journal
+        let max_year = config.today().year() as u32 + 1;
+        for (index, paper) in self.papers.iter().flatten().enumerate() {
+            if !(1665..=max_year).contains(&paper.year) {
+                errors.push(MetaError::error(
+                    &format!("/papers/{index}/year"),
+                    ErrorCode::InvalidYear,
+                    format!(r#""{}" must be in the range 1665-{max_year}"#, paper.year),
+                ));
+            }
+
+            if let Some(doi) = &paper.doi
+                && let Err(err) = validate_doi(doi)
+            {
+                errors.push(err.at(&format!("/papers/{index}/doi")));
+            }
+
+            for (author_index, author) in paper.author_list.iter().flatten().enumerate()
+            {
+                if let Some(orcid) = &author.orcid
+                    && let Err(err) = validate_orcid(orcid)
+                {
+                    errors.push(err.at(&format!(
+                        "/papers/{index}/author_list/{author_index}/orcid"
+                    )));
+                }
+            }
+
+            if let (Some(start), Some(end)) = (paper.page_start, paper.page_end)
+                && start > end
+            {
+                errors.push(MetaError::error(
+                    &format!("/papers/{index}/page_end"),
+                    ErrorCode::PagesOutOfOrder,
+                    format!("{end} is before page_start {start}"),
+                ));
+            }
+        }
+
+        if let Some(density) = self.water_density_kg_m3 {
+            if !density.is_finite() {
+                errors.push(MetaError::error(
+                    "/water_density_kg_m3",
+                    ErrorCode::NotFinite,
+                    format!("{density} is not a finite value"),
+                ));
+            } else if density <= 0. {
+                errors.push(MetaError::error(
+                    "/water_density_kg_m3",
+                    ErrorCode::NotPositive,
+                    "must be positive",
+                ));
+            }
+        }
+
+        if let Some(solvents) = &self.solvents {
+            for (index, solvent) in solvents.iter().enumerate() {
+                let path = format!("/solvents/{index}/ion_concentration_mol_liter");
+                if !solvent.ion_concentration_mol_liter.is_finite() {
+                    errors.push(MetaError::error(
+                        &path,
+                        ErrorCode::NotFinite,
+                        format!(
+                            "{:?} is not a finite value",
+                            solvent.ion_concentration_mol_liter
+                        ),
+                    ));
+                } else if solvent.ion_concentration_mol_liter < 0. {
+                    errors.push(MetaError::error(
+                        &path,
+                        ErrorCode::Negative,
+                        "must not be negative",
+                    ));
+                }
+            }
+        }
+
+        if let Some(timestep) = self.timestep_ns
+            && !timestep.is_finite()
+        {
+            errors.push(MetaError::error(
+                "/timestep_ns",
+                ErrorCode::NotFinite,
+                format!("{timestep:?} is not a finite value"),
+            ));
+        }
+
+        if let Some(length) = self.simulation_length_ns {
+            errors.extend(check_positive("/simulation_length_ns", length));
+        }
+
+        for (index, edge) in self.box_dimensions_nm.iter().flatten().enumerate() {
+            errors.extend(check_positive(
+                &format!("/box_dimensions_nm/{index}"),
+                *edge,
+            ));
+        }
+
+        errors.extend(self.find_warnings());
+        errors
+    }
+
+    // Problems that don't invalidate the metadata but suggest it's incomplete
+    // or inconsistent; these are also included in find_errors
     //[pyfunction]
-    //pub fn find_errors(&self) -> Vec<(String, String)> {
-    //    let mut errors = vec![];
-    //    //if let Some(replicates) = &self.replicates {
-    //    //    if replicates.replicate.unwra
-    //    //}
-
-    //    if let Some(temp) = &self.temperature.clone().and_then(|t| t.temperature) {
-    //        if !(MIN_TEMP_K..=MAX_TEMP_K).contains(temp) {
-    //            errors.push((
-    //                "temperature.temperature".to_string(),
-    //                format!(
-    //                    r#""{temp}" must be in the range {MIN_TEMP_K}-{MAX_TEMP_K}"#
-    //                ),
-    //            ))
-    //        }
-    //    }
-
-    //    let valid_date = Regex::new(r"\d{4}\-\d{2}\-\d{2}").unwrap();
-    //    match &self.initial.date {
-    //        Datelike::Stringy(dt) => {
-    //            if !valid_date.is_match(dt) {
-    //                errors.push((
-    //                    "initial.date".to_string(),
-    //                    format!(r#"invalid date "{}""#, dt),
-    //                ));
-    //            }
-    //        }
-    //        _ => {
-    //            errors.push(("initial.date".to_string(), "invalid date".to_string()));
-    //        }
-    //    }
-
-    //    fn is_valid_orcid(orcid: &str) -> bool {
-    //        let re = Regex::new(r"\d{4}\-\d{4}\-\d{4}\-\d{3}[A-Z]").unwrap();
-    //        re.is_match(orcid)
-    //    }
-
-    //    if !is_valid_orcid(&self.initial.lead_contributor_orcid) {
-    //        errors.push((
-    //            "initial.lead_contributor_orcid".to_string(),
-    //            format!(r#"invalid ORCID "{}""#, self.initial.lead_contributor_orcid),
-    //        ));
-    //    }
-
-    //    if let Some(contributors) = &self.contributors {
-    //        for contributor in contributors {
-    //            if let Some(orcid) = &contributor.orcid {
-    //                if !is_valid_orcid(orcid) {
-    //                    errors.push((
-    //                        "contributor.orcid".to_string(),
-    //                        format!(r#"invalid ORCID "{}""#, orcid),
-    //                    ));
-    //                }
-    //            }
-    //        }
-    //    }
-
-    //    if let Some(perms) = &self.simulation_permissions {
-    //        for perm in perms {
-    //            if !is_valid_orcid(&perm.user_orcid) {
-    //                errors.push((
-    //                    "simulation_permissions.user_orcid".to_string(),
-    //                    format!(r#"invalid ORCID "{}""#, perm.user_orcid),
-    //                ));
-    //            }
-    //        }
-    //    }
-
-    //    if let Some(water) = &self.water {
-    //        if let Some(density) = water.density {
-    //            if !density.is_finite() {
-    //                errors.push((
-    //                    "water.density".to_string(),
-    //                    format!("{density} is not a finite value"),
-    //                ));
-    //            }
-    //        }
-
-    //        if !water.is_present {
-    //            if water.model.is_some() {
-    //                errors.push((
-    //                    "water.model".to_string(),
-    //                    "should not be present if water.is_present is false"
-    //                        .to_string(),
-    //                ));
-    //            }
-    //            if water.density.is_some() {
-    //                errors.push((
-    //                    "water.density".to_string(),
-    //                    "should not be present if water.is_present is false"
-    //                        .to_string(),
-    //                ));
-    //            }
-    //            if water.water_density_units.is_some() {
-    //                errors.push((
-    //                    "water.water_density_units".to_string(),
-    //                    "should not be present if water.is_present is false"
-    //                        .to_string(),
-    //                ));
-    //            }
-    //        }
-    //    }
-
-    //    if let Some(solvents) = &self.solvents {
-    //        for solvent in solvents {
-    //            if !solvent.ion_concentration.is_finite() {
-    //                errors.push((
-    //                    "solvent.ion_concentration".to_string(),
-    //                    format!(
-    //                        "{:?} is not a finite value",
-    //                        solvent.ion_concentration
-    //                    ),
-    //                ));
-    //            }
-    //        }
-    //    }
-
-    //    if let Some(timestep) = &self.timestep_information {
-    //        if timestep
-    //            .integration_time_step
-    //            .map_or(false, |val| !val.is_finite())
-    //        {
-    //            errors.push((
-    //                "timestep.integration_time_step".to_string(),
-    //                format!(
-    //                    "{:?} is not a finite value",
-    //                    timestep.integration_time_step.unwrap()
-    //                ),
-    //            ));
-    //        }
-    //    }
-
-    //    errors
-    //}
+    pub fn find_warnings(&self) -> Vec<MetaError> {
+        let mut warnings = vec![];
+
+        // The lead has no name or email unless they're also a contributor
+        if !self.contributors.iter().flatten().any(|contributor| {
+            contributor.orcid.as_ref() == Some(&self.lead_contributor_orcid)
+        }) {
+            warnings.push(MetaError::warning(
+                "/lead_contributor_orcid",
+                ErrorCode::LeadNotContributor,
+                "not listed among the contributors",
+            ));
+        }
+
+        for (field, text) in [
+            ("/short_description", &self.short_description),
+            ("/description", &self.description),
+        ] {
+            if text.as_ref().is_some_and(|val| val.trim().is_empty()) {
+                warnings.push(MetaError::warning(
+                    field,
+                    ErrorCode::EmptyDescription,
+                    "is empty",
+                ));
+            }
+        }
+
+        if self.water_is_present == Some(false) {
+            for (field, present) in [
+                ("/water_model", self.water_model.is_some()),
+                ("/water_density_kg_m3", self.water_density_kg_m3.is_some()),
+            ] {
+                if present {
+                    warnings.push(MetaError::warning(
+                        field,
+                        ErrorCode::WaterInconsistent,
+                        "should not be present if water_is_present is false",
+                    ));
+                }
+            }
+        }
+
+        if let Some(ph) = self.ph
+            && let Some(warning) = check_ph("/ph", ph)
+            && warning.is_warning()
+        {
+            warnings.push(warning);
+        }
+
+        for (index, solvent) in self.solvents.iter().flatten().enumerate() {
+            if let Some(units) = &solvent.concentration_units
+                && concentration_divisor_to_mol_l(units).is_none()
+            {
+                warnings.push(MetaError::warning(
+                    &format!("/solvents/{index}/concentration_units"),
+                    ErrorCode::UnknownUnits,
+                    format!(r#"unrecognized units "{units}""#),
+                ));
+            }
+        }
+
+        if let Some(density) = self.water_density_kg_m3
+            && density.is_finite()
+            && density > 0.
+            && !(MIN_WATER_DENSITY_KG_M3..=MAX_WATER_DENSITY_KG_M3).contains(&density)
+        {
+            warnings.push(MetaError::warning(
+                "/water_density_kg_m3",
+                ErrorCode::ImplausibleDensity,
+                format!(
+                    "{density} is outside the plausible range for liquid water \
+                    ({MIN_WATER_DENSITY_KG_M3}-{MAX_WATER_DENSITY_KG_M3} kg/m^3)"
+                ),
+            ));
+        }
+
+        if let Some(step) = self.timestep_ns
+            && let Some(warning) = check_timestep(
+                "/timestep_ns",
+                step * 1e6,
+                self.hydrogen_mass_repartitioning == Some(true),
+            )
+        {
+            warnings.push(warning);
+        }
+
+        if let Some(model) = &self.water_model
+            && let Some(warning) = check_known_name(
+                "/water_model",
+                ErrorCode::UnknownWaterModel,
+                model,
+                WATER_MODELS,
+            )
+        {
+            warnings.push(warning);
+        }
+
+        for (path, name) in one_or_many_paths(
+            "/forcefield",
+            self.forcefield.as_deref().unwrap_or_default(),
+        ) {
+            warnings.extend(check_known_name(
+                &path,
+                ErrorCode::UnknownForcefield,
+                name,
+                FORCEFIELDS,
+            ));
+        }
+
+        if self.forcefield.as_ref().is_none_or(Vec::is_empty)
+            && self.forcefield_comments.is_some()
+        {
+            warnings.push(MetaError::warning(
+                "/forcefield_comments",
+                ErrorCode::ForcefieldIncomplete,
+                "present without forcefield",
+            ));
+        }
+
+        if let Some(method) = &self.protonation_method
+            && let Some(warning) = check_known_name(
+                "/protonation_method",
+                ErrorCode::UnknownProtonationMethod,
+                method,
+                PROTONATION_METHODS,
+            )
+        {
+            warnings.push(warning);
+        }
+
+        warnings.extend(check_single_primary(
+            "papers",
+            "is_primary",
+            self.papers
+                .iter()
+                .flatten()
+                .map(|p| p.is_primary == Some(true)),
+        ));
+        warnings.extend(check_single_primary(
+            "ligands",
+            "is_primary",
+            self.ligands
+                .iter()
+                .flatten()
+                .map(|l| l.is_primary == Some(true)),
+        ));
+        warnings.extend(check_single_primary(
+            "proteins",
+            "is_primary",
+            self.proteins
+                .iter()
+                .flatten()
+                .map(|p| p.is_primary == Some(true)),
+        ));
+
+        // E.g., ligands that to_canon moved out of initial.ligands
+        for (index, ligand) in self.ligands.iter().flatten().enumerate() {
+            if ligand.smiles.trim().is_empty() {
+                warnings.push(MetaError::warning(
+                    &format!("/ligands/{index}/smiles"),
+                    ErrorCode::MissingSmiles,
+                    format!(r#"is empty for "{}""#, ligand.name),
+                ));
+            }
+        }
+        warnings.extend(check_duplicate_smiles(
+            self.ligands.iter().flatten().map(|l| l.smiles.as_str()),
+        ));
+
+        warnings
+    }
+
+    // Apply the fixes that have an unambiguous correction and return the
+    // findings they resolved
+    pub fn auto_fix(&mut self) -> Vec<MetaError> {
+        let fixed: Vec<_> = self
+            .find_warnings()
+            .into_iter()
+            .filter(|warning| AUTO_FIXABLE.contains(&warning.code))
+            .collect();
+
+        for text in [&mut self.short_description, &mut self.description] {
+            if text.as_ref().is_some_and(|val| val.trim().is_empty()) {
+                *text = None;
+            }
+        }
+
+        if self.water_is_present == Some(false) {
+            self.water_model = None;
+            self.water_density_kg_m3 = None;
+        }
+
+        fixed
+    }
+
+    // How "other" differs from this record, as for V1
+    pub fn diff(&self, other: &MetaV2) -> Result<Vec<FieldChange>> {
+        diff::diff(self, other).map_err(Into::into)
+    }
+
+    // Write integer paper volumes and numbers back as numbers, as for V1
+    pub fn numeric_volumes(&mut self) {
+        for paper in self.papers.iter_mut().flatten() {
+            paper.volume = paper.volume.to_numeric();
+            paper.number = paper.number.as_ref().map(Numlike::to_numeric);
+        }
+    }
+
+    // Sort the lists whose order carries no meaning, as for V1
+    pub fn canonicalize(&mut self) {
+        if let Some(contributors) = self.contributors.as_mut() {
+            contributors.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        if let Some(permissions) = self.simulation_permissions.as_mut() {
+            permissions.sort_by(|a, b| a.user_orcid.cmp(&b.user_orcid));
+        }
+        if let Some(proteins) = self.proteins.as_mut() {
+            proteins.sort_by(|a, b| a.molecule_id.cmp(&b.molecule_id));
+        }
+        if let Some(papers) = self.papers.as_mut() {
+            papers.sort_by(|a, b| (a.year, &a.title).cmp(&(b.year, &b.title)));
+        }
+    }
 
     // Create an example with every field with valid values
-    //pub fn example() -> Self {
-    //    Self {
-    //        initial: Initial {
-    //            short_description: Some(
-    //                "Adaptive sampling of AncFT luciferase".to_string(),
-    //            ),
-    //            description: Some(
-    //                "Adaptive sampling of AncFT luciferase performed in \
-    //                HTMD, using a C-alpha RMSD metric. 5 microseconds in total. 10 \
-    //                epochs of 10 parallel simulations each."
-    //                    .to_string(),
-    //            ),
-    //            external_link: Some("http://external.link".to_string()),
-    //            lead_contributor_orcid: "0000-0000-0000-000X".to_string(),
-    //            date: Datelike::Stringy("2000-01-01".to_string()),
-    //            commands: Some(
-    //                "gmx_mpi mdrun -s fname.tpr -deffnm fname -v -c fname.pdb \
-    //                -cpi fname.cpt -maxh clock_time -noappend -update gpu -bonded gpu \
-    //                -pme gpu -pmefft gpu -nb gpu"
-    //                    .to_string(),
-    //            ),
-    //            simulation_is_restricted: Some(false),
-    //            scientific_goal: None,
-    //            ligands: None,
-    //            solvents: None,
-    //        },
-    //        required_files: Some(RequiredFile {
-    //            trajectory_file_name: "trajectory.xtc".to_string(),
-    //            structure_file_name: "structure.pdb".to_string(),
-    //            topology_file_name: "topology.psf".to_string(),
-    //        }),
-    //        additional_files: Some(vec![
-    //            AdditionalFile {
-    //                additional_file_type: "Checkpoint".to_string(),
-    //                additional_file_name: "abc.cpt".to_string(),
-    //                additional_file_description: Some(
-    //                    "Last GROMACS checkpoint of the \
-    //                    simulation"
-    //                        .to_string(),
-    //                ),
-    //            },
-    //            AdditionalFile {
-    //                additional_file_type: "Miscellaneous".to_string(),
-    //                additional_file_name: "xyz.tpr".to_string(),
-    //                additional_file_description: None,
-    //            },
-    //        ]),
-    //        contributors: Some(vec![
-    //            Contributor {
-    //                name: "Contributor1".to_string(),
-    //                orcid: Some("0000-0000-0000-000X".to_string()),
-    //                email: Some("email@place.edu".to_string()),
-    //                institution: Some("Institution".to_string()),
-    //            },
-    //            Contributor {
-    //                name: "Contributor2".to_string(),
-    //                orcid: Some("0000-0000-0000-000X".to_string()),
-    //                email: Some("email@anotherplace.edu".to_string()),
-    //                institution: Some("Some Other Institution".to_string()),
-    //            },
-    //        ]),
-    //        forcefield: Some(Forcefield {
-    //            forcefield: Some("Amber99SB-ILDN".to_string()),
-    //            forcefield_comments: Some("ligand params: GAFF".to_string()),
-    //        }),
-    //        ligands: Some(vec![
-    //            Ligand {
-    //                primary: None,
-    //                name: "Foropafant".to_string(),
-    //                smiles: "CC(C)C1=CC(=C(C(=C1)C(C)C)C2=CSC(=N2)N(CCN(C)C)\
-    //                    CC3=CN=CC=C3)C(C)C"
-    //                    .to_string(),
-    //            },
-    //            Ligand {
-    //                primary: None,
-    //                name: "Vipadenant".to_string(),
-    //                smiles: "CC1=C(C=CC(=C1)CN2C3=NC(=NC(=C3N=N2)C4=CC=CO4)N)N"
-    //                    .to_string(),
-    //            },
-    //        ]),
-    //        mdrepo_id: None,
-    //        papers: Some(vec![
-    //            Paper {
-    //                primary: Some(true),
-    //                title: "GPCRmd uncovers the dynamics of the 3D-GPCRome".to_string(),
-    //                authors: "Rodríguez, I., Fontanals, M., Tielmann, J.S. et al."
-    //                    .to_string(),
-    //                journal: "Nat Methods".to_string(),
-    //                volume: Numlike::Stringy("17".to_string()),
-    //                number: Some(Numlike::Stringy("4".to_string())),
-    //                year: 2000,
-    //                pages: Some("777–787".to_string()),
-    //                doi: Some("10.1038/x41594-020-0884-y".to_string()),
-    //            },
-    //            Paper {
-    //                primary: None,
-    //                title: "Adrenaline-activated structure of β2-adrenoceptor \
-    //                    stabilized by an engineered nanobody"
-    //                    .to_string(),
-    //                authors: "Ring, A., Manglik, A., Kruse, A., Enos, M., Weis, \
-    //                    W., Garcia, K., Kobilka, B."
-    //                    .to_string(),
-    //                journal: "Nature".to_string(),
-    //                volume: Numlike::Stringy("502".to_string()),
-    //                number: Some(Numlike::Stringy("7472".to_string())),
-    //                year: 2013,
-    //                pages: Some("575-579".to_string()),
-    //                doi: Some("10.1038/nature12572".to_string()),
-    //            },
-    //        ]),
-    //        proteins: Some(vec![
-    //            Protein::ProteinNew {
-    //                primary: None,
-    //                molecule_id_type: "PDB".to_string(),
-    //                molecule_id: "7QXR".to_string(),
-    //            },
-    //            Protein::ProteinNew {
-    //                primary: None,
-    //                molecule_id_type: "Uniprot".to_string(),
-    //                molecule_id: "A7M120".to_string(),
-    //            },
-    //        ]),
-    //        protonation_method: Some(Protonation {
-    //            protonation_method: Some("PROPKA".to_string()),
-    //        }),
-    //        replicates: Some(Replicates {
-    //            replicate: Some(1),
-    //            total_replicates: Some(10),
-    //        }),
-    //        simulation_permissions: Some(vec![
-    //            Permission {
-    //                user_orcid: "0000-0000-0000-000X".to_string(),
-    //                can_edit: true,
-    //                can_view: false,
-    //            },
-    //            Permission {
-    //                user_orcid: "0000-0000-0000-001X".to_string(),
-    //                can_edit: false,
-    //                can_view: true,
-    //            },
-    //        ]),
-    //        software: Software {
-    //            name: "GROMACS".to_string(),
-    //            version: Some("2016.5".to_string()),
-    //        },
-    //        solvents: Some(vec![
-    //            Solvent {
-    //                name: "Sodium".to_string(),
-    //                ion_concentration: 0.157,
-    //                solvent_concentration_units: Some("mol/L".to_string()),
-    //            },
-    //            Solvent {
-    //                name: "Chloride".to_string(),
-    //                ion_concentration: 0.225,
-    //                solvent_concentration_units: Some("mol/L".to_string()),
-    //            },
-    //        ]),
-    //        temperature: Some(Temperature {
-    //            temperature: Some(273),
-    //        }),
-    //        timestep_information: Some(Timestep {
-    //            integration_time_step: Some(2.),
-    //        }),
-    //        water: Some(Water {
-    //            is_present: true,
-    //            model: Some("TIP3P".to_string()),
-    //            density: Some(0.986),
-    //            water_density_units: Some("g/m^3".to_string()),
-    //        }),
-    //    }
-    //}
+    pub fn example() -> Self {
+        Self {
+            mdrepo_id: None,
+            short_description: Some(
+                "Adaptive sampling of AncFT luciferase".to_string(),
+            ),
+            description: Some(
+                "Adaptive sampling of AncFT luciferase performed in \
+                HTMD, using a C-alpha RMSD metric. 5 microseconds in total. 10 \
+                epochs of 10 parallel simulations each."
+                    .to_string(),
+            ),
+            external_link: Some("http://external.link".to_string()),
+            lead_contributor_orcid: "0000-0000-0000-000X".to_string(),
+            date: Datelike::Stringy("2000-01-01".to_string()),
+            run_commands: Some(
+                "gmx_mpi mdrun -s fname.tpr -deffnm fname -v -c fname.pdb \
+                -cpi fname.cpt -maxh clock_time -noappend -update gpu -bonded gpu \
+                -pme gpu -pmefft gpu -nb gpu"
+                    .to_string(),
+            ),
+            software: Software {
+                name: "GROMACS".to_string(),
+                version: Some("2016.5".to_string()),
+            },
+            replicate_id: Some(1),
+            total_replicates: Some(10),
+            merged_replicate_ids: None,
+            water_is_present: Some(true),
+            water_model: Some("TIP3P".to_string()),
+            water_density_kg_m3: Some(986.),
+            forcefield: Some(vec!["Amber99SB-ILDN".to_string()]),
+            forcefield_comments: Some("ligand params: GAFF".to_string()),
+            temperature_kelvin: Some(Templike::Integer(273)),
+            protonation_method: Some("PROPKA".to_string()),
+            ph: Some(7.),
+            timestep_ns: Some(2e-6),
+            hydrogen_mass_repartitioning: None,
+            simulation_length_ns: Some(100.),
+            box_dimensions_nm: Some([7.5, 7.5, 9.]),
+            required_file: RequiredFile {
+                trajectory_file_name: "trajectory.xtc".to_string(),
+                structure_file_name: "structure.pdb".to_string(),
+                topology_file_name: "topology.psf".to_string(),
+            },
+            additional_files: Some(vec![
+                AdditionalFile {
+                    file_type: "Checkpoint".to_string(),
+                    file_name: "abc.cpt".to_string(),
+                    description: Some(
+                        "Last GROMACS checkpoint of the simulation".to_string(),
+                    ),
+                },
+                AdditionalFile {
+                    file_type: "Miscellaneous".to_string(),
+                    file_name: "xyz.tpr".to_string(),
+                    description: None,
+                },
+            ]),
+            proteins: Some(vec![
+                Protein {
+                    is_primary: None,
+                    molecule_id_type: "PDB".to_string(),
+                    molecule_id: "7QXR".to_string(),
+                },
+                Protein {
+                    is_primary: None,
+                    molecule_id_type: "Uniprot".to_string(),
+                    molecule_id: "A7M120".to_string(),
+                },
+            ]),
+            ligands: Some(vec![
+                Ligand {
+                    is_primary: None,
+                    name: "Foropafant".to_string(),
+                    smiles: "CC(C)C1=CC(=C(C(=C1)C(C)C)C2=CSC(=N2)N(CCN(C)C)\
+                        CC3=CN=CC=C3)C(C)C"
+                        .to_string(),
+                },
+                Ligand {
+                    is_primary: None,
+                    name: "Vipadenant".to_string(),
+                    smiles: "CC1=C(C=CC(=C1)CN2C3=NC(=NC(=C3N=N2)C4=CC=CO4)N)N"
+                        .to_string(),
+                },
+            ]),
+            solvents: Some(vec![
+                Solvent {
+                    name: "Sodium".to_string(),
+                    ion_concentration_mol_liter: 0.157,
+                    concentration_units: Some("mol/L".to_string()),
+                    charge: None,
+                },
+                Solvent {
+                    name: "Chloride".to_string(),
+                    ion_concentration_mol_liter: 0.225,
+                    concentration_units: Some("mol/L".to_string()),
+                    charge: None,
+                },
+            ]),
+            papers: Some(vec![
+                Paper {
+                    is_primary: Some(true),
+                    title: "GPCRmd uncovers the dynamics of the 3D-GPCRome".to_string(),
+                    authors: "Rodríguez, I., Fontanals, M., Tielmann, J.S. et al."
+                        .to_string(),
+                    author_list: None,
+                    journal: "Nat Methods".to_string(),
+                    volume: Numlike::Stringy("17".to_string()),
+                    number: Some(Numlike::Stringy("4".to_string())),
+                    year: 2000,
+                    pages: Some("777–787".to_string()),
+                    page_start: Some(777),
+                    page_end: Some(787),
+                    doi: Some("10.1038/x41594-020-0884-y".to_string()),
+                },
+                Paper {
+                    is_primary: None,
+                    title: "Adrenaline-activated structure of β2-adrenoceptor \
+                        stabilized by an engineered nanobody"
+                        .to_string(),
+                    authors: "Ring, A., Manglik, A., Kruse, A., Enos, M., Weis, \
+                        W., Garcia, K., Kobilka, B."
+                        .to_string(),
+                    author_list: None,
+                    journal: "Nature".to_string(),
+                    volume: Numlike::Stringy("502".to_string()),
+                    number: Some(Numlike::Stringy("7472".to_string())),
+                    year: 2013,
+                    pages: Some("575-579".to_string()),
+                    page_start: Some(575),
+                    page_end: Some(579),
+                    doi: Some("10.1038/nature12572".to_string()),
+                },
+            ]),
+            contributors: Some(vec![
+                Contributor {
+                    name: "Contributor1".to_string(),
+                    orcid: Some("0000-0000-0000-000X".to_string()),
+                    email: Some("email@place.edu".to_string()),
+                    institution: Some("Institution".to_string()),
+                },
+                Contributor {
+                    name: "Contributor2".to_string(),
+                    orcid: Some("0000-0000-0000-001X".to_string()),
+                    email: Some("email@anotherplace.edu".to_string()),
+                    institution: Some("Some Other Institution".to_string()),
+                },
+            ]),
+            simulation_is_restricted: Some(false),
+            simulation_permissions: Some(vec![
+                Permission {
+                    user_orcid: "0000-0000-0000-002X".to_string(),
+                    can_edit: true,
+                    can_view: true,
+                },
+                Permission {
+                    user_orcid: "0000-0000-0000-003X".to_string(),
+                    can_edit: false,
+                    can_view: true,
+                },
+            ]),
+        }
+    }
+}
+
+// E.g., `let meta: MetaV1 = meta_v2.into()`, as every V2 record fits V1
+impl From<MetaV2> for MetaV1 {
+    fn from(meta: MetaV2) -> Self {
+        meta.to_v1()
+    }
 }
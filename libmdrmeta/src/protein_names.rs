@@ -0,0 +1,77 @@
+use crate::common::ExternalIds;
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+// The entry for a PDB ID, e.g., https://data.rcsb.org/rest/v1/core/entry/5UPE
+fn pdb_url(pdb_id: &str) -> String {
+    // The title belongs to the entry, not the chain, e.g., "1U19.A"
+    let entry = pdb_id.split('.').next().unwrap_or(pdb_id);
+    format!("https://data.rcsb.org/rest/v1/core/entry/{entry}")
+}
+
+// E.g., https://rest.uniprot.org/uniprotkb/A7M120.json
+fn uniprot_url(uniprot_id: &str) -> String {
+    format!("https://rest.uniprot.org/uniprotkb/{uniprot_id}.json")
+}
+
+// The recommended name, else the name given on submission
+fn uniprot_name(entry: &Value) -> Option<&str> {
+    let names = &entry["proteinDescription"];
+    names["recommendedName"]["fullName"]["value"]
+        .as_str()
+        .or_else(|| names["submissionNames"][0]["fullName"]["value"].as_str())
+}
+
+// (ID, title) for each PDB then UniProt ID, with the JSON for each URL from
+// the caller
+pub(crate) fn resolve_names(
+    ids: &ExternalIds,
+    mut fetch: impl FnMut(&str) -> Result<Value>,
+) -> Result<Vec<(String, String)>> {
+    let mut names = vec![];
+    for pdb_id in &ids.pdb {
+        let entry =
+            fetch(&pdb_url(pdb_id)).map_err(|e| anyhow!("PDB {pdb_id}: {e}"))?;
+        let title = entry["struct"]["title"]
+            .as_str()
+            .ok_or_else(|| anyhow!("PDB {pdb_id}: no title in entry"))?;
+        names.push((pdb_id.clone(), title.trim().to_string()));
+    }
+    for uniprot_id in &ids.uniprot {
+        let entry = fetch(&uniprot_url(uniprot_id))
+            .map_err(|e| anyhow!("Uniprot {uniprot_id}: {e}"))?;
+        let name = uniprot_name(&entry)
+            .ok_or_else(|| anyhow!("Uniprot {uniprot_id}: no protein name in entry"))?;
+        names.push((uniprot_id.clone(), name.trim().to_string()));
+    }
+    Ok(names)
+}
+
+// Entries rarely change, so each URL is fetched once per process
+#[cfg(feature = "network")]
+pub(crate) fn fetch_cached(url: &str) -> Result<Value> {
+    use std::{
+        collections::HashMap,
+        sync::{LazyLock, Mutex},
+    };
+
+    static CACHE: LazyLock<Mutex<HashMap<String, Value>>> =
+        LazyLock::new(|| Mutex::new(HashMap::new()));
+
+    if let Some(entry) = CACHE.lock().map_err(|e| anyhow!("{e}"))?.get(url) {
+        return Ok(entry.clone());
+    }
+
+    let entry: Value = reqwest::blocking::Client::builder()
+        .user_agent(concat!("libmdrmeta/", env!("CARGO_PKG_VERSION")))
+        .build()?
+        .get(url)
+        .send()?
+        .error_for_status()?
+        .json()?;
+    CACHE
+        .lock()
+        .map_err(|e| anyhow!("{e}"))?
+        .insert(url.to_string(), entry.clone());
+    Ok(entry)
+}
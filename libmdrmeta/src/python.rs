@@ -0,0 +1,53 @@
+use crate::{AnyMeta, Meta};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+// Metadata of either schema version, e.g.:
+//   from libmdrmeta import PyMeta
+//   meta = PyMeta.from_file("MDR_00004423.toml")
+//   for path, message in meta.find_errors(): ...
+#[pyclass(name = "PyMeta")]
+pub struct PyMeta {
+    meta: AnyMeta,
+}
+
+#[pymethods]
+impl PyMeta {
+    #[staticmethod]
+    fn from_file(filename: &str) -> PyResult<Self> {
+        let meta = Meta::from_file_any(filename).map_err(to_py_err)?;
+        Ok(Self { meta })
+    }
+
+    // JSON or TOML, detected from the contents
+    #[staticmethod]
+    fn from_string(contents: &str) -> PyResult<Self> {
+        let (meta, _) = Meta::from_string_any_with_log(contents).map_err(to_py_err)?;
+        Ok(Self { meta })
+    }
+
+    fn to_json(&self) -> PyResult<String> {
+        self.meta.to_json().map_err(to_py_err)
+    }
+
+    fn to_toml(&self) -> PyResult<String> {
+        self.meta.to_toml().map_err(to_py_err)
+    }
+
+    // (path, message) pairs, warnings included
+    fn find_errors(&self) -> Vec<(String, String)> {
+        self.meta
+            .find_errors()
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+}
+
+fn to_py_err(err: impl std::fmt::Display) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+#[pymodule]
+fn libmdrmeta(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyMeta>()
+}
@@ -0,0 +1,443 @@
+// proptest strategies for metadata that parses, though it need not
+// validate, e.g., for format round-trip tests
+use crate::{
+    common::{Author, Datelike, Numlike, RequiredFile, Software, Templike},
+    error::MdrMetaError,
+    metav1::{
+        AdditionalFile, Contributor, Forcefield, Initial, Ligand, MetaV1, Paper,
+        Permission, Protein, Protonation, Replicates, Solvent, Temperature, Timestep,
+        Water,
+    },
+};
+use proptest::{arbitrary::Arbitrary, collection::vec, option, prelude::*};
+use std::fmt;
+use toml::{
+    value::{Date as TomlDate, Datetime as TomlDatetime},
+    Value as TomlValue,
+};
+
+// Printable text with the odd quote, backslash, and non-ASCII letter that
+// TOML and JSON must escape or encode
+const TEXT: &str = r#"[A-Za-z0-9 .,:;'"\\/()é–-]{0,20}"#;
+const NAME: &str = "[A-Za-z][A-Za-z0-9 -]{0,15}";
+const ORCID: &str = "[0-9]{4}-[0-9]{4}-[0-9]{4}-[0-9]{3}[0-9X]";
+
+fn text() -> BoxedStrategy<String> {
+    TEXT.boxed()
+}
+
+fn name() -> BoxedStrategy<String> {
+    NAME.boxed()
+}
+
+fn orcid() -> BoxedStrategy<String> {
+    ORCID.boxed()
+}
+
+// Whole numbers and fractions, but nothing JSON can't write, e.g., NaN
+fn finite(max: f64) -> BoxedStrategy<f64> {
+    prop_oneof![(0..1000i64).prop_map(|val| val as f64), 0.0..max].boxed()
+}
+
+fn units(known: &'static [&'static str]) -> BoxedStrategy<Option<String>> {
+    option::of(proptest::sample::select(known).prop_map(str::to_string)).boxed()
+}
+
+fn list<T: fmt::Debug + 'static>(
+    item: BoxedStrategy<T>,
+) -> BoxedStrategy<Option<Vec<T>>> {
+    option::of(vec(item, 0..3)).boxed()
+}
+
+// Dates as written in the wild: ISO strings, bare TOML dates, and the
+// other spellings to_canon rewrites
+fn date() -> BoxedStrategy<Datelike> {
+    const MONTHS: [&str; 12] = [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ];
+    (1990..2030u16, 1..=12u8, 1..=28u8, 0..5)
+        .prop_map(|(year, month, day, style)| match style {
+            0 => Datelike::Stringy(format!("{year}-{month:02}-{day:02}")),
+            1 => Datelike::TomlDate(TomlDatetime {
+                date: Some(TomlDate { year, month, day }),
+                time: None,
+                offset: None,
+            }),
+            2 => Datelike::Stringy(format!("{month}/{day}/{year}")),
+            3 => Datelike::Stringy(format!("{year}/{month}/{day}")),
+            _ => Datelike::Stringy(format!(
+                "{} {day}, {year}",
+                MONTHS[usize::from(month) - 1]
+            )),
+        })
+        .boxed()
+}
+
+// Volumes and issues as given in TOML: quoted, bare integers, or floats
+fn numlike() -> BoxedStrategy<Numlike> {
+    prop_oneof![
+        "[0-9]{1,4}[A-Z]?".prop_map(Numlike::Stringy),
+        (0..10_000i64).prop_map(|num| Numlike::TomlVal(TomlValue::Integer(num))),
+        (0.0..1000.).prop_map(|num| Numlike::TomlVal(TomlValue::Float(num))),
+    ]
+    .boxed()
+}
+
+fn templike() -> BoxedStrategy<Templike> {
+    prop_oneof![
+        (0..400i64).prop_map(Templike::Integer),
+        (0.0..400.).prop_map(Templike::Float),
+    ]
+    .boxed()
+}
+
+fn initial() -> BoxedStrategy<Initial> {
+    (
+        (option::of(text()), option::of(text()), option::of(text())),
+        (orcid(), date(), option::of(text())),
+        (option::of(any::<bool>()), option::of(text())),
+        (
+            option::of(finite(1000.)),
+            option::of((finite(20.), finite(20.), finite(20.))),
+        ),
+        (option::of(vec(name(), 0..3)), option::of(vec(name(), 0..3))),
+    )
+        .prop_map(
+            |(
+                (short_description, description, external_link),
+                (lead_contributor_orcid, date, commands),
+                (simulation_is_restricted, scientific_goal),
+                (simulation_length_ns, box_dimensions_nm),
+                (ligands, solvents),
+            )| Initial {
+                short_description,
+                description,
+                external_link,
+                lead_contributor_orcid,
+                date,
+                commands,
+                simulation_is_restricted,
+                scientific_goal,
+                simulation_length_ns,
+                box_dimensions_nm: box_dimensions_nm.map(|(x, y, z)| [x, y, z]),
+                ligands,
+                solvents,
+            },
+        )
+        .boxed()
+}
+
+fn software() -> BoxedStrategy<Software> {
+    (name(), option::of("[0-9]{1,4}(\\.[0-9]{1,2})?"))
+        .prop_map(|(name, version)| Software { name, version })
+        .boxed()
+}
+
+fn required_file() -> BoxedStrategy<RequiredFile> {
+    (name(), name(), name())
+        .prop_map(
+            |(trajectory_file_name, structure_file_name, topology_file_name)| {
+                RequiredFile {
+                    trajectory_file_name,
+                    structure_file_name,
+                    topology_file_name,
+                }
+            },
+        )
+        .boxed()
+}
+
+fn additional_file() -> BoxedStrategy<AdditionalFile> {
+    (name(), name(), option::of(text()))
+        .prop_map(
+            |(
+                additional_file_type,
+                additional_file_name,
+                additional_file_description,
+            )| {
+                AdditionalFile {
+                    additional_file_type,
+                    additional_file_name,
+                    additional_file_description,
+                }
+            },
+        )
+        .boxed()
+}
+
+// Including the older PDB/Uniprot forms that to_canon rewrites
+fn protein() -> BoxedStrategy<Protein> {
+    let primary = || option::of(any::<bool>());
+    prop_oneof![
+        (primary(), "[0-9][A-Z0-9]{3}")
+            .prop_map(|(primary, pdb_id)| Protein::ProteinOldPDB { primary, pdb_id }),
+        (primary(), "[A-Z][0-9][A-Z0-9]{3}[0-9]").prop_map(|(primary, uniprot_id)| {
+            Protein::ProteinOldUniprot {
+                primary,
+                uniprot_id,
+            }
+        }),
+        (primary(), name(), "[A-Z0-9]{4,10}").prop_map(
+            |(primary, molecule_id_type, molecule_id)| Protein::ProteinNew {
+                primary,
+                molecule_id_type,
+                molecule_id,
+            }
+        ),
+    ]
+    .boxed()
+}
+
+fn replicates() -> BoxedStrategy<Replicates> {
+    (
+        option::of(1..100u32),
+        option::of(1..100u32),
+        option::of(vec(1..100u32, 1..4)),
+    )
+        .prop_map(
+            |(total_replicates, replicate, merged_replicates)| Replicates {
+                total_replicates,
+                replicate,
+                merged_replicates,
+            },
+        )
+        .boxed()
+}
+
+fn water() -> BoxedStrategy<Water> {
+    (
+        any::<bool>(),
+        option::of(name()),
+        option::of(finite(2000.).prop_map(|val| val as f32)),
+        units(&["kg/m^3", "g/cm^3", "g/L"]),
+    )
+        .prop_map(|(is_present, model, density, water_density_units)| Water {
+            is_present,
+            model,
+            density,
+            water_density_units,
+        })
+        .boxed()
+}
+
+fn ligand() -> BoxedStrategy<Ligand> {
+    (option::of(any::<bool>()), name(), "[A-Za-z0-9()=#]{0,20}")
+        .prop_map(|(primary, name, smiles)| Ligand {
+            primary,
+            name,
+            smiles,
+        })
+        .boxed()
+}
+
+fn solvent() -> BoxedStrategy<Solvent> {
+    (
+        name(),
+        option::of(finite(10.)),
+        units(&["mol/L", "M", "mM", "µM"]),
+        option::of(-3..=3i32),
+    )
+        .prop_map(
+            |(name, ion_concentration, solvent_concentration_units, charge)| Solvent {
+                name,
+                ion_concentration,
+                solvent_concentration_units,
+                charge,
+            },
+        )
+        .boxed()
+}
+
+fn forcefield() -> BoxedStrategy<Forcefield> {
+    (option::of(vec(name(), 1..3)), option::of(text()))
+        .prop_map(|(forcefield, forcefield_comments)| Forcefield {
+            forcefield,
+            forcefield_comments,
+        })
+        .boxed()
+}
+
+fn temperature() -> BoxedStrategy<Temperature> {
+    (option::of(templike()), units(&["K", "C"]))
+        .prop_map(|(temperature, temperature_units)| Temperature {
+            temperature,
+            temperature_units,
+        })
+        .boxed()
+}
+
+fn protonation() -> BoxedStrategy<Protonation> {
+    (option::of(name()), option::of(finite(14.)))
+        .prop_map(|(protonation_method, ph)| Protonation {
+            protonation_method,
+            ph,
+        })
+        .boxed()
+}
+
+fn timestep() -> BoxedStrategy<Timestep> {
+    (
+        option::of(finite(10.)),
+        units(&["fs", "ps", "ns"]),
+        option::of(any::<bool>()),
+    )
+        .prop_map(
+            |(integration_time_step, timestep_units, hydrogen_mass_repartitioning)| {
+                Timestep {
+                    integration_time_step,
+                    timestep_units,
+                    hydrogen_mass_repartitioning,
+                }
+            },
+        )
+        .boxed()
+}
+
+fn author() -> BoxedStrategy<Author> {
+    (name(), option::of(name()), option::of(orcid()))
+        .prop_map(|(family, given, orcid)| Author {
+            family,
+            given,
+            orcid,
+        })
+        .boxed()
+}
+
+fn paper() -> BoxedStrategy<Paper> {
+    (
+        (option::of(any::<bool>()), text(), text()),
+        (option::of(vec(author(), 1..3)), text()),
+        (numlike(), option::of(numlike()), 1900..2030u32),
+        (
+            option::of("[0-9]{1,4}(-[0-9]{1,4})?"),
+            option::of("10\\.[0-9]{4,5}/[a-z0-9.]{1,10}"),
+        ),
+    )
+        .prop_map(
+            |(
+                (primary, title, authors),
+                (author_list, journal),
+                (volume, number, year),
+                (pages, doi),
+            )| Paper {
+                primary,
+                title,
+                authors,
+                author_list,
+                journal,
+                volume,
+                number,
+                year,
+                pages,
+                doi,
+            },
+        )
+        .boxed()
+}
+
+fn contributor() -> BoxedStrategy<Contributor> {
+    (
+        name(),
+        option::of(orcid()),
+        option::of("[a-z]{1,8}@[a-z]{1,8}\\.edu"),
+        option::of(text()),
+    )
+        .prop_map(|(name, orcid, email, institution)| Contributor {
+            name,
+            orcid,
+            email,
+            institution,
+        })
+        .boxed()
+}
+
+fn permission() -> BoxedStrategy<Permission> {
+    (orcid(), any::<bool>(), any::<bool>())
+        .prop_map(|(user_orcid, can_edit, can_view)| Permission {
+            user_orcid,
+            can_edit,
+            can_view,
+        })
+        .boxed()
+}
+
+impl Arbitrary for MetaV1 {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            (
+                option::of("MDR_[0-9]{8}"),
+                initial(),
+                software(),
+                option::of(required_file()),
+            ),
+            (
+                list(additional_file()),
+                list(protein()),
+                option::of(replicates()),
+                option::of(water()),
+            ),
+            (
+                list(ligand()),
+                list(solvent()),
+                option::of(forcefield()),
+                option::of(temperature()),
+            ),
+            (
+                option::of(protonation()),
+                option::of(timestep()),
+                list(paper()),
+            ),
+            (list(contributor()), list(permission())),
+        )
+            .prop_map(
+                |(
+                    (mdrepo_id, initial, software, required_files),
+                    (additional_files, proteins, replicates, water),
+                    (ligands, solvents, forcefield, temperature),
+                    (protonation_method, timestep_information, papers),
+                    (contributors, simulation_permissions),
+                )| MetaV1 {
+                    mdrepo_id,
+                    initial,
+                    software,
+                    required_files,
+                    additional_files,
+                    proteins,
+                    replicates,
+                    water,
+                    ligands,
+                    solvents,
+                    forcefield,
+                    temperature,
+                    protonation_method,
+                    timestep_information,
+                    papers,
+                    contributors,
+                    simulation_permissions,
+                },
+            )
+            .boxed()
+    }
+}
+
+// The record as parsing either format should leave it, normalized once in
+// memory so that neither format is the reference for the other
+pub fn fix(meta: &MetaV1) -> Result<MetaV1, MdrMetaError> {
+    let mut fixed = meta.clone();
+    fixed.to_canon()?;
+    Ok(fixed)
+}
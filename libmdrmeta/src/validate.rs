@@ -0,0 +1,177 @@
+use crate::{
+    common::is_calendar_date,
+    error::{ErrorCode, ValidateError},
+};
+use regex::Regex;
+use std::{collections::HashSet, sync::LazyLock};
+
+// Shared with the JSON Schema so client-side validation matches ours
+// An ORCID's last character is a checksum, a digit or "X"
+pub const ORCID_PATTERN: &str = r"^\d{4}-\d{4}-\d{4}-\d{3}[\dX]$";
+pub const DATE_PATTERN: &str = r"^\d{4}-\d{2}-\d{2}$";
+pub const DOI_PATTERN: &str = r"^10\.\d{4,9}/\S+$";
+pub const MDREPO_ID_PATTERN: &str = r"^MDR_\d{8}$";
+
+// Patterns are compiled once and shared, as validating a large batch of files
+// would otherwise rebuild each one for every record
+
+static ORCID_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(ORCID_PATTERN).unwrap());
+
+// Date-shaped, e.g., "2020-02-30" passes and is_calendar_date decides
+static DATE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(DATE_PATTERN).unwrap());
+
+// Exactly "YYYY-MM-DD" with the parts captured
+pub(crate) static YMD_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{4})-(\d{2})-(\d{2})$").unwrap());
+
+static DOI_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(DOI_PATTERN).unwrap());
+
+static MDREPO_ID_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(MDREPO_ID_PATTERN).unwrap());
+
+static PDB_ID_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[0-9][A-Za-z0-9]{3}(\.[A-Za-z0-9]+)?$").unwrap());
+
+static UNIPROT_ID_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^([OPQ][0-9][A-Z0-9]{3}[0-9]|[A-NR-Z][0-9]([A-Z][A-Z0-9]{2}[0-9]){1,2})$",
+    )
+    .unwrap()
+});
+
+pub fn validate_orcid(orcid: &str) -> Result<(), ValidateError> {
+    if ORCID_RE.is_match(orcid) {
+        Ok(())
+    } else {
+        Err(ValidateError::new(
+            ErrorCode::InvalidOrcid,
+            format!(r#"invalid ORCID "{orcid}""#),
+        ))
+    }
+}
+
+pub fn validate_doi(doi: &str) -> Result<(), ValidateError> {
+    if DOI_RE.is_match(doi) {
+        Ok(())
+    } else {
+        Err(ValidateError::new(
+            ErrorCode::InvalidDoi,
+            format!(r#"invalid DOI "{doi}""#),
+        ))
+    }
+}
+
+// A "YYYY-MM-DD" date that names a real day
+pub fn validate_date(date: &str) -> Result<(), ValidateError> {
+    if !DATE_RE.is_match(date) {
+        Err(ValidateError::new(
+            ErrorCode::InvalidDate,
+            format!(r#"invalid date "{date}""#),
+        ))
+    } else if !is_calendar_date(date) {
+        Err(ValidateError::new(
+            ErrorCode::InvalidDate,
+            format!(r#""{date}" is not a calendar date"#),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+// E.g., "MDR_00004423"
+pub fn validate_mdrepo_id(mdrepo_id: &str) -> Result<(), ValidateError> {
+    if MDREPO_ID_RE.is_match(mdrepo_id) {
+        Ok(())
+    } else {
+        Err(ValidateError::new(
+            ErrorCode::InvalidMdrepoId,
+            format!(r#"invalid MDRepo ID "{mdrepo_id}""#),
+        ))
+    }
+}
+
+// A PDB accession with an optional chain suffix, e.g., "1U19" or "1U19.A"
+pub fn validate_pdb_id(pdb_id: &str) -> Result<(), ValidateError> {
+    if PDB_ID_RE.is_match(pdb_id) {
+        Ok(())
+    } else {
+        Err(ValidateError::new(
+            ErrorCode::InvalidPdbId,
+            format!(r#"invalid PDB ID "{pdb_id}""#),
+        ))
+    }
+}
+
+// The official UniProt accession format, e.g., "A7M120" or "P02699"
+pub fn validate_uniprot_id(uniprot_id: &str) -> Result<(), ValidateError> {
+    if UNIPROT_ID_RE.is_match(uniprot_id) {
+        Ok(())
+    } else {
+        Err(ValidateError::new(
+            ErrorCode::InvalidUniprotId,
+            format!(r#"invalid UniProt accession "{uniprot_id}""#),
+        ))
+    }
+}
+
+// Not a full parser, but catches the truncations and stray characters of a
+// bad copy-paste: balanced parentheses and brackets, paired ring-closure
+// digits, and only characters that can appear in SMILES
+pub fn validate_smiles(smiles: &str) -> Result<(), ValidateError> {
+    let invalid = |reason: String| {
+        Err(ValidateError::new(
+            ErrorCode::InvalidSmiles,
+            format!("invalid SMILES: {reason}"),
+        ))
+    };
+
+    let mut depth = 0;
+    let mut in_bracket = false;
+    let mut open_rings = HashSet::new();
+    let mut chars = smiles.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '[' if in_bracket => return invalid(r#"nested "[""#.to_string()),
+            '[' => in_bracket = true,
+            ']' if !in_bracket => return invalid(r#"unmatched "]""#.to_string()),
+            ']' => in_bracket = false,
+            '(' if !in_bracket => depth += 1,
+            ')' if !in_bracket => {
+                if depth == 0 {
+                    return invalid(r#"unmatched ")""#.to_string());
+                }
+                depth -= 1;
+            }
+            // Inside brackets, digits are isotopes, charges, or H counts
+            '0'..='9' if !in_bracket => {
+                if !open_rings.insert(c.to_string()) {
+                    open_rings.remove(&c.to_string());
+                }
+            }
+            '%' if !in_bracket => {
+                let ring: String = chars.by_ref().take(2).collect();
+                if ring.len() != 2 || !ring.chars().all(|c| c.is_ascii_digit()) {
+                    return invalid(
+                        r#""%" must be followed by two digits"#.to_string(),
+                    );
+                }
+                let ring = format!("%{ring}");
+                if !open_rings.insert(ring.clone()) {
+                    open_rings.remove(&ring);
+                }
+            }
+            c if c.is_ascii_alphanumeric() || "=#$:/\\.+-@*".contains(c) => (),
+            c => return invalid(format!(r#"illegal character "{c}""#)),
+        }
+    }
+
+    if in_bracket {
+        invalid(r#"unclosed "[""#.to_string())
+    } else if depth > 0 {
+        invalid(r#"unclosed "(""#.to_string())
+    } else if let Some(ring) = open_rings.iter().min() {
+        invalid(format!("unclosed ring bond {ring}"))
+    } else {
+        Ok(())
+    }
+}
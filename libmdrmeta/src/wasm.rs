@@ -0,0 +1,23 @@
+use crate::Meta;
+use wasm_bindgen::prelude::*;
+
+// Validate JSON or TOML metadata in the browser, returning the findings as
+// an array, e.g., [{"path": "/initial/date", "message": "...", ...}]
+#[wasm_bindgen]
+pub fn validate_string(contents: &str) -> Result<JsValue, JsError> {
+    let (meta, _) = Meta::from_string_any_with_log(contents).map_err(to_js_err)?;
+    let findings = serde_json::to_string(&meta.find_errors())?;
+    js_sys::JSON::parse(&findings)
+        .map_err(|_| JsError::new("Failed to convert the findings"))
+}
+
+// JSON or TOML metadata as JSON
+#[wasm_bindgen]
+pub fn to_json(contents: &str) -> Result<String, JsError> {
+    let (meta, _) = Meta::from_string_any_with_log(contents).map_err(to_js_err)?;
+    meta.to_json().map_err(to_js_err)
+}
+
+fn to_js_err(err: impl std::fmt::Display) -> JsError {
+    JsError::new(&err.to_string())
+}
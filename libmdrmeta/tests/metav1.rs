@@ -1,6 +1,22 @@
 use anyhow::Result;
-use libmdrmeta::metav1::{Datelike, Ligand, MetaV1, Protein};
+use chrono::NaiveDate;
+#[cfg(feature = "test-util")]
+use libmdrmeta::test_util;
+use libmdrmeta::{
+    common::{
+        Author, CanonChange, Datelike, DoiCache, Numlike, RequiredFile, SchemaVersion,
+        Templike, MAX_TEMP_K, MIN_TEMP_K,
+    },
+    config::ValidationConfig,
+    diff::FieldChange,
+    error::{ErrorCode, MdrMetaError, MetaError, Severity},
+    lenient::{MetaLenient, UnknownFields},
+    metav1::{Forcefield, Ligand, MetaV1, Protein, Replicates, Temperature},
+    Meta,
+};
 use pretty_assertions::assert_eq;
+#[cfg(feature = "test-util")]
+use proptest::prelude::*;
 use std::fs;
 
 const BAD_JSON: &str = "../tests/inputs/bad.json";
@@ -9,6 +25,7 @@ const EMPTY: &str = "../tests/inputs/empty";
 const EMPTY_JSON: &str = "../tests/inputs/empty.json";
 const EMPTY_TOML: &str = "../tests/inputs/empty.toml";
 const FULL_EXAMPLE: &str = "../tests/inputs/example.toml";
+const INITIAL_SOLVENTS: &str = "../tests/inputs/example_initial_solvents.toml";
 const MDR0002_JSON: &str = "../tests/inputs/MDR_00000002.json";
 const MDR0002_TOML: &str = "../tests/inputs/MDR_00000002.toml";
 const MDR4423_TOML: &str = "../tests/inputs/MDR_00004423.toml";
@@ -19,7 +36,7 @@ const OUTPUT_MDR0002_TOML: &str = "../tests/outputs/MDR_00000002.toml";
 #[test]
 fn dies_from_file_no_ext() -> Result<()> {
     let res = MetaV1::from_file(EMPTY);
-    assert!(!res.is_ok());
+    assert!(res.is_err());
 
     let err = res.unwrap_err();
     assert_eq!(err.to_string(), "No file extension");
@@ -31,16 +48,60 @@ fn dies_from_file_no_ext() -> Result<()> {
 #[test]
 fn dies_from_file_bad() -> Result<()> {
     let res = MetaV1::from_file(EMPTY_TOML);
-    assert!(!res.is_ok());
+    assert!(res.is_err());
 
     let res = MetaV1::from_file(EMPTY_JSON);
-    assert!(!res.is_ok());
+    assert!(res.is_err());
 
     let res = MetaV1::from_file(BAD_TOML);
-    assert!(!res.is_ok());
+    assert!(res.is_err());
 
     let res = MetaV1::from_file(BAD_JSON);
-    assert!(!res.is_ok());
+    assert!(res.is_err());
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn error_kinds() -> Result<()> {
+    assert!(matches!(
+        MetaV1::from_file(EMPTY),
+        Err(MdrMetaError::UnknownExtension(None))
+    ));
+    assert!(matches!(
+        MetaV1::from_file("../Cargo.lock"),
+        Err(MdrMetaError::UnknownExtension(Some(ext))) if ext == "lock"
+    ));
+    assert!(matches!(
+        MetaV1::from_file("../tests/inputs/missing.toml"),
+        Err(MdrMetaError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound
+    ));
+    assert!(matches!(
+        MetaV1::from_file(EMPTY_TOML),
+        Err(MdrMetaError::Empty)
+    ));
+    assert!(matches!(
+        Meta::from_string_any_with_log(" \n"),
+        Err(MdrMetaError::Empty)
+    ));
+    assert!(matches!(
+        MetaV1::from_file("../tests/inputs/truncated.toml"),
+        Err(MdrMetaError::Parse(_))
+    ));
+
+    assert!(matches!(
+        MetaV1::normalize_toml_in_place("[initial]\ndate = \"someday\"\n")
+            .map_err(|e| e.downcast::<MdrMetaError>()),
+        Err(Ok(MdrMetaError::DateParse(_)))
+    ));
+
+    let mut meta = MetaV1::example();
+    meta.required_files = None;
+    assert!(matches!(
+        meta.to_v2(),
+        Err(MdrMetaError::MissingRequiredFiles)
+    ));
 
     Ok(())
 }
@@ -151,6 +212,17 @@ fn json_to_toml() -> Result<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn json_keeps_floats_exact() -> Result<()> {
+    let mut meta = MetaV1::example();
+    if let Some(solvents) = meta.solvents.as_mut() {
+        solvents[0].ion_concentration = Some(12.490460884724385);
+    }
+    assert_eq!(MetaV1::from_json(&meta.to_json()?)?, meta);
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn parses_0002() -> Result<()> {
@@ -258,7 +330,7 @@ fn parses_4423() -> Result<()> {
 
     assert!(meta.forcefield.is_some());
     let forcefield = meta.forcefield.unwrap();
-    assert_eq!(forcefield.forcefield, Some("charmm36".to_string()));
+    assert_eq!(forcefield.forcefield, Some(vec!["charmm36".to_string()]));
     assert_eq!(
         forcefield.forcefield_comments,
         Some("ligand parameters from swissparam".to_string())
@@ -266,7 +338,7 @@ fn parses_4423() -> Result<()> {
 
     assert!(meta.temperature.is_some());
     let temperature = meta.temperature.unwrap();
-    assert_eq!(temperature.temperature, Some(300));
+    assert_eq!(temperature.temperature, Some(Templike::Integer(300)));
 
     assert_eq!(meta.software.name, "GROMACS".to_string());
     assert_eq!(meta.software.version, Some("2024".to_string()));
@@ -331,3 +403,2265 @@ fn parses_full_example() -> Result<()> {
 
     Ok(())
 }
+
+// --------------------------------------------------
+#[test]
+fn warns_forcefield_comments_without_name() -> Result<()> {
+    let mut meta = MetaV1::example();
+    meta.forcefield = Some(Forcefield {
+        forcefield: None,
+        forcefield_comments: Some("ligand params: GAFF".to_string()),
+    });
+
+    assert_eq!(
+        meta.find_warnings(),
+        vec![MetaError::warning(
+            "/forcefield/forcefield_comments",
+            ErrorCode::ForcefieldIncomplete,
+            "present without forcefield.forcefield",
+        )]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn forcefield_list() -> Result<()> {
+    // A single name is still written as a string
+    let toml = MetaV1::example().to_toml()?;
+    assert!(toml.contains("forcefield = \"Amber99SB-ILDN\"\n"));
+
+    let toml = toml.replace(
+        "forcefield = \"Amber99SB-ILDN\"",
+        "forcefield = [\"Amber99SB-ILDN\", \" GAFF2 \"]",
+    );
+    let meta = MetaV1::from_toml(&toml)?;
+    let names = vec!["Amber99SB-ILDN".to_string(), "GAFF2".to_string()];
+    let forcefield = meta.forcefield.as_ref().expect("forcefield");
+    assert_eq!(forcefield.forcefield, Some(names.clone()));
+    assert!(meta.find_warnings().is_empty());
+    assert!(meta.summary().contains(", Amber99SB-ILDN + GAFF2,"));
+    assert!(meta
+        .to_toml()?
+        .contains("forcefield = [\n    \"Amber99SB-ILDN\",\n    \"GAFF2\",\n]"));
+    assert_eq!(meta.to_v2()?.forcefield, Some(names));
+
+    let meta = MetaV1::from_toml(&toml.replace("GAFF2", "MyCustomFF"))?;
+    assert_eq!(
+        meta.find_warnings(),
+        vec![MetaError::warning(
+            "/forcefield/forcefield/1",
+            ErrorCode::UnknownForcefield,
+            r#"unrecognized value "MyCustomFF""#,
+        )]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn no_warning_forcefield_with_name_and_comments() -> Result<()> {
+    let meta = MetaV1::example();
+    let forcefield = meta.forcefield.as_ref().expect("forcefield");
+    assert!(forcefield.forcefield.is_some());
+    assert!(forcefield.forcefield_comments.is_some());
+    assert!(meta.find_warnings().is_empty());
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn temperature_range_agrees_with_meta() -> Result<()> {
+    let too_hot = || {
+        Some(Temperature {
+            temperature: Some(Templike::Integer(500)),
+            temperature_units: None,
+        })
+    };
+
+    let mut meta = Meta::example();
+    meta.temperature = too_hot();
+    let meta_errors = meta.find_errors();
+
+    let mut meta_v1 = MetaV1::example();
+    meta_v1.temperature = too_hot();
+    let meta_v1_errors = meta_v1.find_errors();
+
+    let expected = vec![MetaError::error(
+        "/temperature/temperature",
+        ErrorCode::TemperatureOutOfRange,
+        format!(r#""500" must be in the range {MIN_TEMP_K}-{MAX_TEMP_K}"#),
+    )];
+    assert_eq!(meta_errors, expected);
+    assert_eq!(meta_v1_errors, expected);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn configurable_temperature_range() -> Result<()> {
+    let mut meta = MetaV1::example();
+    if let Some(temp) = meta.temperature.as_mut() {
+        temp.temperature = Some(Templike::Integer(77));
+        temp.temperature_units = Some("K".to_string());
+    }
+    assert_eq!(meta.find_errors().len(), 1);
+
+    let config = ValidationConfig {
+        temp_min: Some(4.),
+        ..Default::default()
+    };
+    assert!(meta.find_errors_with_config(&config).is_empty());
+
+    let config = ValidationConfig {
+        temp_min: Some(4.),
+        temp_max: Some(50.),
+        ..Default::default()
+    };
+    assert_eq!(
+        meta.find_errors_with_config(&config),
+        vec![MetaError::error(
+            "/temperature/temperature",
+            ErrorCode::TemperatureOutOfRange,
+            r#""77" must be in the range 4-50"#,
+        )]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn description_length_unlimited_by_default() -> Result<()> {
+    let mut meta = MetaV1::example();
+    meta.initial.description = Some("x".repeat(10_000));
+    assert!(meta.find_errors().is_empty());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn description_length_boundary() -> Result<()> {
+    let config = ValidationConfig {
+        max_description_chars: Some(5),
+        ..Default::default()
+    };
+
+    // "µ" is two bytes but one character
+    let mut meta = MetaV1::example();
+    meta.initial.short_description = Some("µµµµµ".to_string());
+    meta.initial.description = Some("abcde".to_string());
+    assert!(meta.find_errors_with_config(&config).is_empty());
+
+    meta.initial.short_description = Some("µµµµµµ".to_string());
+    meta.initial.description = Some("abcdef".to_string());
+    assert_eq!(
+        meta.find_errors_with_config(&config),
+        vec![
+            MetaError::error(
+                "/initial/short_description",
+                ErrorCode::DescriptionTooLong,
+                "length 6 exceeds maximum of 5",
+            ),
+            MetaError::error(
+                "/initial/description",
+                ErrorCode::DescriptionTooLong,
+                "length 6 exceeds maximum of 5",
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn to_v2_keeps_collections() -> Result<()> {
+    let meta = MetaV1::from_file(FULL_EXAMPLE)?;
+    let meta_v2 = meta.to_v2()?;
+
+    assert_eq!(
+        meta_v2.lead_contributor_orcid,
+        meta.initial.lead_contributor_orcid
+    );
+    assert_eq!(meta_v2.date, meta.initial.date);
+    assert_eq!(
+        meta_v2.simulation_is_restricted,
+        meta.initial.simulation_is_restricted
+    );
+    assert_eq!(meta_v2.proteins.map(|v| v.len()), Some(2));
+    assert_eq!(meta_v2.ligands.map(|v| v.len()), Some(2));
+    assert_eq!(meta_v2.solvents.map(|v| v.len()), Some(2));
+    assert_eq!(meta_v2.papers.map(|v| v.len()), Some(2));
+    assert_eq!(meta_v2.contributors.map(|v| v.len()), Some(2));
+    assert_eq!(meta_v2.simulation_permissions.map(|v| v.len()), Some(2));
+
+    let additional_files = meta_v2.additional_files.expect("additional_files");
+    assert_eq!(additional_files.len(), 2);
+    assert_eq!(additional_files[0].file_type, "Checkpoint");
+    assert_eq!(additional_files[0].file_name, "abc.cpt");
+    assert_eq!(
+        additional_files[0].description,
+        Some("Last GROMACS checkpoint of the simulation".to_string())
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn to_v2_promotes_legacy_proteins() -> Result<()> {
+    let mut meta = MetaV1::from_file(MDR0002_TOML)?;
+    assert!(meta.required_files.is_none());
+
+    let res = meta.to_v2();
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().to_string(), "Missing required_files");
+
+    meta.required_files = Some(RequiredFile {
+        trajectory_file_name: "trajectory.xtc".to_string(),
+        structure_file_name: "structure.pdb".to_string(),
+        topology_file_name: "topology.psf".to_string(),
+    });
+    let meta_v2 = meta.to_v2()?;
+
+    let proteins = meta_v2.proteins.expect("proteins");
+    assert_eq!(proteins.len(), 1);
+    assert_eq!(proteins[0].molecule_id_type, "PDB");
+    assert_eq!(proteins[0].molecule_id, "1U19.A");
+    assert_eq!(meta_v2.solvents.map(|v| v.len()), Some(2));
+    assert_eq!(meta_v2.papers.map(|v| v.len()), Some(2));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn matches_ignoring_id() -> Result<()> {
+    let mut assigned = MetaV1::from_file(MDR4423_TOML)?;
+    assigned.mdrepo_id = Some("MDR_00004423".to_string());
+    let mut resubmitted = MetaV1::from_file(MDR4423_TOML)?;
+    resubmitted.mdrepo_id = None;
+
+    assert!(assigned.matches_ignoring_id(&resubmitted));
+    assert!(resubmitted.matches_ignoring_id(&assigned));
+
+    resubmitted.initial.short_description = Some("Something else".to_string());
+    assert!(!assigned.matches_ignoring_id(&resubmitted));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn water_density_must_be_positive() -> Result<()> {
+    let mut meta = MetaV1::example();
+    for density in [0., -1.] {
+        if let Some(water) = meta.water.as_mut() {
+            water.density = Some(density);
+        }
+        assert_eq!(
+            meta.find_errors(),
+            vec![MetaError::error(
+                "/water/density",
+                ErrorCode::NotPositive,
+                "must be positive"
+            )]
+        );
+    }
+
+    if let Some(water) = meta.water.as_mut() {
+        water.density = Some(998.2);
+    }
+    assert!(meta.find_errors().is_empty());
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn required_sections() -> Result<()> {
+    let meta = MetaV1::from_file(MDR4423_TOML)?;
+    let config = ValidationConfig {
+        required_sections: vec![
+            "proteins".to_string(),
+            "papers".to_string(),
+            "bogus".to_string(),
+        ],
+        ..Default::default()
+    };
+    let errors: Vec<_> = meta
+        .find_errors_with_config(&config)
+        .into_iter()
+        .filter(|err| !err.path.contains("orcid") && !err.is_warning())
+        .collect();
+
+    assert_eq!(
+        errors,
+        vec![
+            MetaError::error(
+                "/papers",
+                ErrorCode::MissingSection,
+                "required section is missing"
+            ),
+            MetaError::error("/bogus", ErrorCode::UnknownSection, "unknown section"),
+        ]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_paper_volume_not_stringlike() -> Result<()> {
+    let toml = MetaV1::example().to_toml()?;
+    assert!(toml.contains(r#"volume = "17""#));
+
+    let res = MetaV1::from_toml(&toml.replace(r#"volume = "17""#, "volume = true"));
+    assert!(matches!(res, Err(MdrMetaError::NotNumlike { .. })));
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "volume must be a string or number, got boolean"
+    );
+
+    let res = MetaV1::from_toml(&toml.replace(r#"volume = "17""#, "volume = [1, 2]"));
+    assert!(res.is_err());
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "volume must be a string or number, got array"
+    );
+
+    let json = MetaV1::example().to_json()?;
+    let res =
+        MetaV1::from_json(&json.replace(r#""number": "4""#, r#""number": false"#));
+    assert!(res.is_err());
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "number must be a string or number, got boolean"
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn external_ids() -> Result<()> {
+    let ids = MetaV1::example().external_ids();
+    assert_eq!(ids.pdb, vec!["7QXR".to_string()]);
+    assert_eq!(ids.uniprot, vec!["A7M120".to_string()]);
+    assert_eq!(
+        ids.doi,
+        vec![
+            "10.1038/x41594-020-0884-y".to_string(),
+            "10.1038/nature12572".to_string()
+        ]
+    );
+    assert_eq!(
+        ids.orcid,
+        vec![
+            "0000-0000-0000-000X".to_string(),
+            "0000-0000-0000-001X".to_string(),
+            "0000-0000-0000-002X".to_string(),
+            "0000-0000-0000-003X".to_string()
+        ]
+    );
+
+    let meta = MetaV1::from_file(MDR0002_TOML)?;
+    assert_eq!(meta.external_ids().pdb, vec!["1U19.A".to_string()]);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn primary_accessors() -> Result<()> {
+    let mut meta = MetaV1::example();
+    assert_eq!(meta.primary_paper().map(|paper| paper.year), Some(2000));
+    assert!(meta.find_errors().is_empty());
+
+    // Falls back to the first when none is flagged
+    let ligands = meta.ligands.as_mut().unwrap();
+    let first = ligands[0].name.clone();
+    assert_eq!(meta.primary_ligand().map(|l| l.name.clone()), Some(first));
+
+    let ligands = meta.ligands.as_mut().unwrap();
+    ligands[1].primary = Some(true);
+    let second = ligands[1].name.clone();
+    assert_eq!(meta.primary_ligand().map(|l| l.name.clone()), Some(second));
+
+    // More than one is a warning
+    meta.papers.as_mut().unwrap()[1].primary = Some(true);
+    let errors = meta.find_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].path, "/papers/1/primary");
+    assert_eq!(errors[0].code, ErrorCode::MultiplePrimary);
+    assert!(errors[0].is_warning());
+    assert_eq!(meta.find_warnings(), errors);
+
+    meta.proteins = None;
+    assert!(meta.primary_protein().is_none());
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn edit_requires_view() -> Result<()> {
+    let mut meta = MetaV1::example();
+    assert!(meta.find_errors().is_empty());
+
+    if let Some(perms) = meta.simulation_permissions.as_mut() {
+        perms[1].can_edit = true;
+        perms[1].can_view = false;
+    }
+    assert_eq!(
+        meta.find_errors(),
+        vec![MetaError::error(
+            "/simulation_permissions/1/can_view",
+            ErrorCode::EditWithoutView,
+            "must be true when can_edit is true",
+        )]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn completeness() -> Result<()> {
+    let mut meta = MetaV1::example();
+    assert_eq!(meta.empty_fields(), vec![("initial.scientific_goal", 1.)]);
+    assert_eq!(meta.completeness(), 30. / 31.);
+
+    meta.papers = Some(vec![]);
+    meta.initial.description = Some(" ".to_string());
+    assert_eq!(
+        meta.empty_fields(),
+        vec![
+            ("papers", 3.),
+            ("initial.description", 2.),
+            ("initial.scientific_goal", 1.)
+        ]
+    );
+    assert_eq!(meta.completeness(), 25. / 31.);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn summary() -> Result<()> {
+    let meta = Meta::from_file(MDR4423_TOML)?;
+    assert_eq!(
+        meta.summary(),
+        "GROMACS 2024, 1 protein, 1 ligand, 300 K, charmm36, replicate 1/4"
+    );
+
+    // Names only the flagged protein and ligand
+    let mut meta = MetaV1::example();
+    meta.replicates = None;
+    assert!(meta.summary().contains("2 proteins, 2 ligands,"));
+
+    meta.ligands.as_mut().unwrap()[1].primary = Some(true);
+    meta.proteins.as_mut().unwrap()[1] = Protein::ProteinNew {
+        primary: Some(true),
+        molecule_id_type: "Uniprot".to_string(),
+        molecule_id: "A7M120".to_string(),
+    };
+    let summary = meta.summary();
+    assert!(summary.contains("2 proteins (Uniprot A7M120)"));
+    assert!(summary.contains(&format!(
+        "2 ligands ({})",
+        meta.ligands.as_ref().unwrap()[1].name
+    )));
+    assert!(!summary.contains("replicate"));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn duplicate_smiles() -> Result<()> {
+    let mut meta = MetaV1::example();
+    let ligands = meta.ligands.as_mut().unwrap();
+    ligands[1].smiles = format!(" {} ", ligands[0].smiles);
+    assert_eq!(
+        meta.find_warnings(),
+        vec![MetaError::warning(
+            "/ligands/1/smiles",
+            ErrorCode::DuplicateSmiles,
+            "same SMILES as /ligands/0/smiles",
+        )]
+    );
+
+    // Ligands without SMILES aren't duplicates of each other
+    let ligands = meta.ligands.as_mut().unwrap();
+    ligands[0].smiles = "".to_string();
+    ligands[1].smiles = "".to_string();
+    assert!(meta
+        .find_warnings()
+        .iter()
+        .all(|warning| warning.code != ErrorCode::DuplicateSmiles));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn invalid_smiles() -> Result<()> {
+    let mut meta = MetaV1::example();
+    meta.ligands.as_mut().unwrap()[1].smiles =
+        "CC1=C(C=CC(=C1)CN2C3=NC(=NC(=C3".to_string();
+    assert_eq!(
+        meta.find_errors(),
+        vec![MetaError::error(
+            "/ligands/1/smiles",
+            ErrorCode::InvalidSmiles,
+            r#"invalid SMILES: unclosed "(""#,
+        )]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn promoted_ligands_missing_smiles() -> Result<()> {
+    let toml = fs::read_to_string(FULL_EXAMPLE)?
+        .replace("[initial]\n", "[initial]\nligands = [\"ABC\"]\n");
+    let meta = MetaV1::from_toml(&toml)?;
+    let ligands = meta.ligands.as_ref().unwrap();
+    assert_eq!(ligands.len(), 3);
+    assert_eq!(ligands[2].name, "ABC");
+    assert_eq!(
+        meta.find_warnings(),
+        vec![MetaError::warning(
+            "/ligands/2/smiles",
+            ErrorCode::MissingSmiles,
+            r#"is empty for "ABC""#,
+        )]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn promotes_initial_solvents() -> Result<()> {
+    let (meta, canon_log) = MetaV1::from_file_with_log(INITIAL_SOLVENTS)?;
+    assert!(meta.initial.solvents.is_none());
+
+    let solvents = meta.solvents.as_ref().unwrap();
+    let names: Vec<_> = solvents.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["Sodium", "Chloride"]);
+    assert!(canon_log.contains(&CanonChange {
+        path: "/initial/solvents/0".to_string(),
+        from: "Sodium".to_string(),
+        to: "moved to solvents".to_string(),
+    }));
+
+    assert_eq!(
+        meta.find_errors(),
+        vec![
+            MetaError::warning(
+                "/solvents/0/ion_concentration",
+                ErrorCode::UnknownConcentration,
+                r#"is unknown for "Sodium""#,
+            ),
+            MetaError::warning(
+                "/solvents/1/ion_concentration",
+                ErrorCode::UnknownConcentration,
+                r#"is unknown for "Chloride""#,
+            ),
+        ]
+    );
+    assert_eq!(meta.ionic_strength(), None);
+
+    // Written without a concentration rather than a placeholder
+    let toml = meta.to_toml()?;
+    assert!(!toml.contains("ion_concentration"));
+
+    // Nor is one made up for V2, which requires it
+    assert!(matches!(
+        meta.to_v2(),
+        Err(MdrMetaError::MissingConcentration(name)) if name == "Sodium"
+    ));
+
+    // A real concentration of 0 without units is known
+    let mut meta = MetaV1::from_toml(&toml)?;
+    for solvent in meta.solvents.iter_mut().flatten() {
+        solvent.ion_concentration = Some(0.);
+    }
+    assert!(meta.find_errors().is_empty());
+    assert_eq!(meta.ionic_strength(), Some(0.));
+    assert_eq!(meta.to_v2()?.ionic_strength(), Some(0.));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn all_orcids() -> Result<()> {
+    let mut meta = MetaV1::example();
+    assert_eq!(
+        meta.all_orcids(),
+        vec![
+            "0000-0000-0000-000X",
+            "0000-0000-0000-001X",
+            "0000-0000-0000-002X",
+            "0000-0000-0000-003X"
+        ]
+    );
+
+    // Repeats are dropped and contributors without one skipped
+    if let Some(contributors) = meta.contributors.as_mut() {
+        contributors[1].orcid = None;
+    }
+    if let Some(perms) = meta.simulation_permissions.as_mut() {
+        perms[1].user_orcid = "0000-0000-0000-000X".to_string();
+    }
+    assert_eq!(
+        meta.all_orcids(),
+        vec!["0000-0000-0000-000X", "0000-0000-0000-002X"]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn promotes_legacy_protein_ids_from_json_and_toml() -> Result<()> {
+    let toml = r#"
+        [initial]
+        lead_contributor_orcid = "0000-0002-1825-009X"
+        date = "2020-07-13"
+        [software]
+        name = "ACEMD"
+        version = "GPUGRID"
+        [[proteins]]
+        pdb_id = "1U19"
+        [[proteins]]
+        primary = true
+        uniprot_id = "P02699"
+    "#;
+    let json = r#"{
+        "initial": {
+            "lead_contributor_orcid": "0000-0002-1825-009X",
+            "date": "2020-07-13"
+        },
+        "software": { "name": "ACEMD", "version": "GPUGRID" },
+        "proteins": [
+            { "pdb_id": "1U19" },
+            { "primary": true, "uniprot_id": "P02699" }
+        ]
+    }"#;
+
+    let from_toml = MetaV1::from_toml(toml)?;
+    let from_json = MetaV1::from_json(json)?;
+    assert_eq!(from_json, from_toml);
+    assert_eq!(
+        from_json.proteins,
+        Some(vec![
+            Protein::ProteinNew {
+                primary: None,
+                molecule_id_type: "PDB".to_string(),
+                molecule_id: "1U19".to_string(),
+            },
+            Protein::ProteinNew {
+                primary: Some(true),
+                molecule_id_type: "Uniprot".to_string(),
+                molecule_id: "P02699".to_string(),
+            },
+        ])
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn water_inconsistency_is_a_warning() -> Result<()> {
+    let mut meta = MetaV1::example();
+    if let Some(water) = meta.water.as_mut() {
+        water.is_present = false;
+    }
+    meta.temperature = Some(Temperature {
+        temperature: Some(Templike::Integer(500)),
+        temperature_units: None,
+    });
+
+    let errors = meta.find_errors();
+    let warnings = meta.find_warnings();
+    assert_eq!(
+        warnings,
+        vec![
+            MetaError::warning(
+                "/water/model",
+                ErrorCode::WaterInconsistent,
+                "should not be present if water.is_present is false",
+            ),
+            MetaError::warning(
+                "/water/density",
+                ErrorCode::WaterInconsistent,
+                "should not be present if water.is_present is false",
+            ),
+            MetaError::warning(
+                "/water/water_density_units",
+                ErrorCode::WaterInconsistent,
+                "should not be present if water.is_present is false",
+            ),
+        ]
+    );
+
+    // find_errors reports the hard errors first, then the warnings
+    assert_eq!(errors.len(), 4);
+    assert_eq!(errors[0].code, ErrorCode::TemperatureOutOfRange);
+    assert_eq!(errors[0].severity, Severity::Error);
+    assert_eq!(errors[1..], warnings[..]);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn empty_description_is_a_warning() -> Result<()> {
+    let mut meta = MetaV1::example();
+    meta.initial.description = Some("  ".to_string());
+    assert_eq!(
+        meta.find_errors(),
+        vec![MetaError::warning(
+            "/initial/description",
+            ErrorCode::EmptyDescription,
+            "is empty"
+        )]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn auto_fix_resolves_warnings() -> Result<()> {
+    let mut meta = MetaV1::example();
+    if let Some(water) = meta.water.as_mut() {
+        water.is_present = false;
+    }
+    meta.initial.short_description = Some("".to_string());
+    let warnings = meta.find_warnings();
+
+    assert_eq!(meta.auto_fix(), warnings);
+    assert!(meta.find_errors().is_empty());
+    assert_eq!(meta.initial.short_description, None);
+    let water = meta.water.as_ref().expect("water");
+    assert!(!water.is_present);
+    assert_eq!(water.model, None);
+
+    // Nothing left to fix
+    assert!(meta.auto_fix().is_empty());
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn lenient_sets_aside_unknown_fields() -> Result<()> {
+    let toml = fs::read_to_string(FULL_EXAMPLE)?
+        .replacen("[initial]\n", "[initial]\nexperimental_flag = true\n", 1)
+        .replacen(
+            "name = \"Contributor1\"\n",
+            "name = \"Contributor1\"\nnickname = \"C1\"\n",
+            1,
+        );
+    assert!(MetaV1::from_toml(&toml).is_err());
+
+    let (meta, unknown) = MetaV1::from_string_lenient(&toml)?;
+    assert_eq!(meta, MetaV1::from_file(FULL_EXAMPLE)?);
+    assert_eq!(
+        unknown,
+        UnknownFields(
+            [
+                ("/contributors/0/nickname".to_string(), "C1".into()),
+                ("/initial/experimental_flag".to_string(), true.into()),
+            ]
+            .into()
+        )
+    );
+    let warnings: Vec<(String, String)> =
+        unknown.warnings().into_iter().map(Into::into).collect();
+    assert_eq!(
+        warnings,
+        vec![
+            (
+                "/contributors/0/nickname".to_string(),
+                "unrecognized field".to_string()
+            ),
+            (
+                "/initial/experimental_flag".to_string(),
+                "unrecognized field".to_string()
+            ),
+        ]
+    );
+
+    // The extras can be written back out and read again
+    let (again, unknown_again) =
+        MetaV1::from_string_lenient(&meta.to_toml_with_unknown(&unknown)?)?;
+    assert_eq!(again, meta);
+    assert_eq!(unknown_again, unknown);
+
+    // Known fields, including serde aliases, are left alone
+    let (_, unknown) = MetaV1::from_file_lenient(FULL_EXAMPLE)?;
+    assert!(unknown.is_empty());
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn lenient_relocates_misplaced_keys() -> Result<()> {
+    let toml = fs::read_to_string(FULL_EXAMPLE)?
+        .replacen(
+            "[software]\n",
+            "[software]\nscientific_goal = \"Folding\"\n",
+            1,
+        )
+        .replacen("[initial]\n", "experimental = 1\n\n[initial]\n", 1);
+    let lenient = MetaLenient::from_string(&toml)?;
+    assert_eq!(lenient.software["scientific_goal"], "Folding");
+    assert_eq!(lenient.extra["experimental"], 1);
+
+    let mut relocated = lenient.clone();
+    assert_eq!(
+        relocated.relocate(),
+        vec![(
+            "software.scientific_goal".to_string(),
+            "initial.scientific_goal".to_string()
+        )]
+    );
+    assert!(!relocated.software.contains_key("scientific_goal"));
+
+    let (meta, unknown) = MetaV1::from_lenient(lenient)?;
+    assert_eq!(meta.initial.scientific_goal.as_deref(), Some("Folding"));
+    assert_eq!(
+        unknown,
+        UnknownFields([("/experimental".to_string(), 1.into())].into())
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ionic_strength_from_solvents() -> Result<()> {
+    // ½ (0.157 · 1² + 0.225 · (-1)²)
+    let mut meta = MetaV1::example();
+    let strength = meta.ionic_strength().expect("ionic strength");
+    assert!((strength - 0.191).abs() < 1e-9);
+
+    let solvents = meta.solvents.as_mut().expect("solvents");
+    solvents[0].ion_concentration = Some(150.);
+    solvents[0].solvent_concentration_units = Some("mM".to_string());
+    solvents[1].name = "Sulfate".to_string();
+    let strength = meta.ionic_strength().expect("ionic strength");
+    assert!((strength - 0.525).abs() < 1e-9);
+
+    // An unknown ion needs its charge given
+    let solvents = meta.solvents.as_mut().expect("solvents");
+    solvents[1].name = "Citrate".to_string();
+    assert_eq!(meta.ionic_strength(), None);
+    meta.solvents.as_mut().expect("solvents")[1].charge = Some(-3);
+    let strength = meta.ionic_strength().expect("ionic strength");
+    assert!((strength - 1.0875).abs() < 1e-9);
+
+    // As are concentrations that are placeholders
+    let meta = MetaV1::from_file(INITIAL_SOLVENTS)?;
+    assert_eq!(meta.ionic_strength(), None);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn diff_matches_lists_by_key() -> Result<()> {
+    let meta = MetaV1::example();
+    assert!(meta.diff(&meta)?.is_empty());
+
+    // Reordered proteins are no change, but a protein or paper that is
+    // gone is listed field by field
+    let mut other = meta.clone();
+    let proteins = other.proteins.as_mut().unwrap();
+    proteins.reverse();
+    proteins.pop();
+    other.papers.as_mut().unwrap().remove(0);
+    other.temperature = Some(Temperature {
+        temperature: Some(Templike::Integer(310)),
+        temperature_units: Some("K".to_string()),
+    });
+    let changes = meta.diff(&other)?;
+    assert!(changes.contains(&FieldChange {
+        path: "/temperature/temperature".to_string(),
+        before: Some("273".to_string()),
+        after: Some("310".to_string()),
+    }));
+    assert!(changes.contains(&FieldChange {
+        path: "/proteins/7QXR/molecule_id".to_string(),
+        before: Some("7QXR".to_string()),
+        after: None,
+    }));
+    assert!(!changes
+        .iter()
+        .any(|c| c.path.starts_with("/proteins/A7M120")));
+    assert!(changes.contains(&FieldChange {
+        path: "/papers/10.1038~1x41594-020-0884-y/journal".to_string(),
+        before: Some("Nat Methods".to_string()),
+        after: None,
+    }));
+    assert!(!changes
+        .iter()
+        .any(|c| c.path.starts_with("/papers/10.1038~1nature")));
+    assert_eq!(
+        changes[0].to_string(),
+        "- /papers/10.1038~1x41594-020-0884-y/authors: \
+        Rodríguez, I., Fontanals, M., Tielmann, J.S. et al."
+    );
+
+    // Other lists are compared by position
+    let mut other = meta.clone();
+    other.ligands.as_mut().unwrap()[0].name = "Foropafant".to_string();
+    other.ligands.as_mut().unwrap()[1].name = "Foropafant".to_string();
+    let changes = meta.diff(&other)?;
+    assert_eq!(
+        changes,
+        vec![FieldChange {
+            path: "/ligands/1/name".to_string(),
+            before: Some("Vipadenant".to_string()),
+            after: Some("Foropafant".to_string()),
+        }]
+    );
+
+    // A "~" or "/" in a key is escaped
+    let mut other = meta.clone();
+    other.ligands.as_mut().unwrap()[0].name = "PEG~400/A".to_string();
+    let changes = meta.diff(&other)?;
+    assert!(changes.contains(&FieldChange {
+        path: "/ligands/PEG~0400~1A/name".to_string(),
+        before: None,
+        after: Some("PEG~400/A".to_string()),
+    }));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn canonicalize_sorts_lists() -> Result<()> {
+    let mut meta = MetaV1::example();
+    meta.canonicalize();
+    let sorted = meta.to_toml()?;
+
+    meta.contributors.as_mut().expect("contributors").reverse();
+    meta.simulation_permissions
+        .as_mut()
+        .expect("permissions")
+        .reverse();
+    meta.proteins.as_mut().expect("proteins").reverse();
+    meta.papers.as_mut().expect("papers").reverse();
+    assert_ne!(meta.to_toml()?, sorted);
+    meta.canonicalize();
+    assert_eq!(meta.to_toml()?, sorted);
+
+    let papers = meta.papers.as_ref().expect("papers");
+    assert!(papers.windows(2).all(|pair| pair[0].year <= pair[1].year));
+    let ids: Vec<_> = meta
+        .proteins
+        .iter()
+        .flatten()
+        .map(|protein| protein.molecule_id())
+        .collect();
+    assert_eq!(ids, ["7QXR", "A7M120"]);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn canonicalizes_prefixed_dois() -> Result<()> {
+    let toml = MetaV1::example().to_toml()?;
+    for prefixed in [
+        "https://doi.org/10.1038/nature12572",
+        "HTTPS://DX.DOI.ORG/10.1038/nature12572",
+        "doi: 10.1038/nature12572",
+        " 10.1038/nature12572 ",
+    ] {
+        let meta = MetaV1::from_toml(&toml.replace(
+            r#"doi = "10.1038/nature12572""#,
+            &format!(r#"doi = "{prefixed}""#),
+        ))?;
+        let papers = meta.papers.as_ref().expect("papers");
+        assert_eq!(papers[1].doi.as_deref(), Some("10.1038/nature12572"));
+        assert!(meta.find_errors().is_empty());
+    }
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn rejects_invalid_dois() -> Result<()> {
+    let mut meta = MetaV1::example();
+    for bad in ["1038/nature12572", "10.1038/nature 12572", "10.12/short"] {
+        if let Some(papers) = meta.papers.as_mut() {
+            papers[1].doi = Some(bad.to_string());
+        }
+        assert_eq!(
+            meta.find_errors(),
+            vec![MetaError::error(
+                "/papers/1/doi",
+                ErrorCode::InvalidDoi,
+                format!(r#"invalid DOI "{bad}""#),
+            )]
+        );
+    }
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn validates_pdb_ids() -> Result<()> {
+    let meta = MetaV1::from_file(MDR0002_TOML)?;
+    assert!(!meta
+        .find_errors()
+        .iter()
+        .any(|err| err.code == ErrorCode::InvalidPdbId));
+
+    let mut meta = MetaV1::example();
+    for bad in ["A7M120", "7QXR ", "7QX"] {
+        meta.proteins = Some(vec![Protein::ProteinNew {
+            primary: None,
+            molecule_id_type: "PDB".to_string(),
+            molecule_id: bad.to_string(),
+        }]);
+        assert_eq!(
+            meta.find_errors(),
+            vec![MetaError::error(
+                "/proteins/0/molecule_id",
+                ErrorCode::InvalidPdbId,
+                format!(r#"invalid PDB ID "{bad}""#),
+            )]
+        );
+    }
+
+    // Uniprot accessions are not checked against the PDB pattern
+    meta.proteins = Some(vec![Protein::ProteinNew {
+        primary: None,
+        molecule_id_type: "Uniprot".to_string(),
+        molecule_id: "A7M120".to_string(),
+    }]);
+    assert!(meta.find_errors().is_empty());
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn require_units() -> Result<()> {
+    let config = ValidationConfig {
+        require_units: true,
+        ..Default::default()
+    };
+
+    // The example declares both units
+    let mut meta = MetaV1::example();
+    assert!(meta.find_errors_with_config(&config).is_empty());
+
+    if let Some(temp) = meta.temperature.as_mut() {
+        temp.temperature_units = None;
+    }
+    if let Some(timestep) = meta.timestep_information.as_mut() {
+        timestep.timestep_units = None;
+    }
+
+    // Units are assumed by default
+    assert!(meta.find_errors().is_empty());
+    assert_eq!(
+        meta.find_errors_with_config(&config),
+        vec![
+            MetaError::error(
+                "/temperature/temperature_units",
+                ErrorCode::MissingUnits,
+                "required when temperature is given",
+            ),
+            MetaError::error(
+                "/timestep_information/timestep_units",
+                ErrorCode::MissingUnits,
+                "required when integration_time_step is given",
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn validates_uniprot_ids() -> Result<()> {
+    let mut meta = MetaV1::example();
+    for good in ["A7M120", "P02699", "A0A022YWF9"] {
+        meta.proteins = Some(vec![Protein::ProteinOldUniprot {
+            primary: None,
+            uniprot_id: good.to_string(),
+        }]);
+        assert!(meta.find_errors().is_empty());
+    }
+
+    for bad in ["A7M12", "a7m120", "1U19"] {
+        meta.proteins = Some(vec![Protein::ProteinNew {
+            primary: None,
+            molecule_id_type: "Uniprot".to_string(),
+            molecule_id: bad.to_string(),
+        }]);
+        assert_eq!(
+            meta.find_errors(),
+            vec![MetaError::error(
+                "/proteins/0/molecule_id",
+                ErrorCode::InvalidUniprotId,
+                format!(r#"invalid UniProt accession "{bad}""#),
+            )]
+        );
+    }
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn from_toml_with_log() -> Result<()> {
+    let (meta, canon_log) =
+        MetaV1::from_toml_with_log(&fs::read_to_string(MDR0002_TOML)?)?;
+    assert_eq!(meta, MetaV1::from_file(MDR0002_TOML)?);
+    assert_eq!(
+        canon_log,
+        vec![CanonChange {
+            path: "/proteins/0".to_string(),
+            from: r#"pdb_id = "1U19.A""#.to_string(),
+            to: r#"molecule_id_type = "PDB", molecule_id = "1U19.A""#.to_string(),
+        }]
+    );
+
+    // Nothing left to change once canonical
+    let (_, canon_log) = MetaV1::from_toml_with_log(&meta.to_toml()?)?;
+    assert!(canon_log.is_empty());
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn rejects_future_dates() -> Result<()> {
+    let config = ValidationConfig {
+        today: NaiveDate::from_ymd_opt(2024, 9, 20),
+        ..Default::default()
+    };
+
+    let mut meta = MetaV1::example();
+    meta.initial.date = Datelike::Stringy("2024-09-20".to_string());
+    assert!(meta.find_errors_with_config(&config).is_empty());
+
+    meta.initial.date = Datelike::Stringy("2024-09-21".to_string());
+    assert_eq!(
+        meta.find_errors_with_config(&config),
+        vec![MetaError::error(
+            "/initial/date",
+            ErrorCode::FutureDate,
+            r#""2024-09-21" is in the future"#,
+        )]
+    );
+
+    // Canonicalization doesn't hide the year 3000
+    let toml = MetaV1::example()
+        .to_toml()?
+        .replace(r#"date = "2000-01-01""#, r#"date = "Jan 1, 3000""#);
+    let meta = MetaV1::from_toml(&toml)?;
+    assert_eq!(
+        meta.find_errors(),
+        vec![MetaError::error(
+            "/initial/date",
+            ErrorCode::FutureDate,
+            r#""3000-01-01" is in the future"#,
+        )]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn unparseable_date_is_reported() -> Result<()> {
+    // The date no longer stops the rest of the file from being checked
+    let toml = MetaV1::example()
+        .to_toml()?
+        .replace(r#"date = "2000-01-01""#, r#"date = "someday""#);
+    let toml = format!("mdrepo_id = \"MDR_4423\"\n{toml}");
+    let (meta, canon_log) = MetaV1::from_toml_with_log(&toml)?;
+    assert_eq!(meta.initial.date, Datelike::Stringy("someday".to_string()));
+    assert!(canon_log
+        .iter()
+        .all(|change| change.path != "/initial/date"));
+
+    let paths: Vec<_> = meta
+        .find_errors()
+        .into_iter()
+        .map(|err| (err.path, err.code))
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            ("/mdrepo_id".to_string(), ErrorCode::InvalidMdrepoId),
+            ("/initial/date".to_string(), ErrorCode::InvalidDate),
+        ]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn rejects_impossible_dates() -> Result<()> {
+    let toml = MetaV1::example().to_toml()?;
+    let with_date = |date: &str| {
+        toml.replace(r#"date = "2000-01-01""#, &format!(r#"date = "{date}""#))
+    };
+
+    let meta = MetaV1::from_toml(&with_date("2020-02-30"))?;
+    assert_eq!(
+        meta.find_errors(),
+        vec![MetaError::error(
+            "/initial/date",
+            ErrorCode::InvalidDate,
+            r#""2020-02-30" is not a calendar date"#,
+        )]
+    );
+
+    // Leap year
+    let meta = MetaV1::from_toml(&with_date("2020-02-29"))?;
+    assert!(meta.find_errors().is_empty());
+
+    let mut meta = MetaV1::example();
+    meta.initial.date = Datelike::Stringy("2021-02-29".to_string());
+    assert_eq!(
+        meta.find_errors(),
+        vec![MetaError::error(
+            "/initial/date",
+            ErrorCode::InvalidDate,
+            r#""2021-02-29" is not a calendar date"#,
+        )]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn validates_external_link() -> Result<()> {
+    let mut meta = MetaV1::example();
+    for bad in [
+        "link goes here",
+        "ftp://files.example.org/x",
+        "https://",
+        "www.gpcrmd.org",
+    ] {
+        meta.initial.external_link = Some(bad.to_string());
+        assert_eq!(
+            meta.find_errors(),
+            vec![MetaError::error(
+                "/initial/external_link",
+                ErrorCode::InvalidUrl,
+                format!(r#"invalid URL "{bad}""#),
+            )]
+        );
+    }
+
+    // Trailing whitespace is trimmed while canonicalizing
+    let toml = MetaV1::example().to_toml()?.replace(
+        r#"external_link = "http://external.link""#,
+        r#"external_link = "http://external.link  ""#,
+    );
+    let meta = MetaV1::from_toml(&toml)?;
+    assert_eq!(
+        meta.initial.external_link.as_deref(),
+        Some("http://external.link")
+    );
+    assert!(meta.find_errors().is_empty());
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn detects_duplicate_orcids() -> Result<()> {
+    let mut meta = MetaV1::example();
+    if let Some(contributors) = meta.contributors.as_mut() {
+        contributors[1].orcid = contributors[0].orcid.clone();
+    }
+    assert_eq!(
+        meta.find_errors(),
+        vec![MetaError::error(
+            "/contributors/1/orcid",
+            ErrorCode::DuplicateOrcid,
+            r#"duplicate ORCID "0000-0000-0000-000X" appears 2 times"#,
+        )]
+    );
+
+    // A permission repeating a contributor's ORCID
+    let mut meta = MetaV1::example();
+    if let Some(perms) = meta.simulation_permissions.as_mut() {
+        perms[0].user_orcid = "0000-0000-0000-000X".to_string();
+    }
+    assert_eq!(
+        meta.find_errors(),
+        vec![MetaError::error(
+            "/simulation_permissions/0/user_orcid",
+            ErrorCode::DuplicateOrcid,
+            r#"duplicate ORCID "0000-0000-0000-000X" appears 2 times"#,
+        )]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn warns_lead_not_a_contributor() -> Result<()> {
+    let mut meta = MetaV1::example();
+    if let Some(contributors) = meta.contributors.as_mut() {
+        contributors.remove(0);
+    }
+    assert_eq!(
+        meta.find_errors(),
+        vec![MetaError::warning(
+            "/initial/lead_contributor_orcid",
+            ErrorCode::LeadNotContributor,
+            "not listed among the contributors",
+        )]
+    );
+
+    meta.contributors = None;
+    assert_eq!(meta.find_warnings().len(), 1);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn replicate_within_total() -> Result<()> {
+    let mut meta = MetaV1::example();
+    for (replicate, total) in [(Some(1), Some(1)), (Some(3), Some(3)), (None, Some(3))]
+    {
+        meta.replicates = Some(Replicates {
+            replicate,
+            total_replicates: total,
+            merged_replicates: None,
+        });
+        assert!(meta.find_errors().is_empty());
+    }
+
+    for (replicate, total, msg) in [
+        (5, Some(3), r#""5" must be in the range 1-3"#),
+        (0, Some(3), r#""0" must be in the range 1-3"#),
+        (1, None, "set without total_replicates"),
+    ] {
+        meta.replicates = Some(Replicates {
+            replicate: Some(replicate),
+            total_replicates: total,
+            merged_replicates: None,
+        });
+        assert_eq!(
+            meta.find_errors(),
+            vec![MetaError::error(
+                "/replicates/replicate",
+                ErrorCode::ReplicateOutOfRange,
+                msg,
+            )]
+        );
+    }
+
+    meta.replicates = Some(Replicates {
+        replicate: None,
+        total_replicates: Some(3),
+        merged_replicates: Some(vec![1, 2, 4]),
+    });
+    assert_eq!(
+        meta.find_errors(),
+        vec![MetaError::error(
+            "/replicates/merged_replicates/2",
+            ErrorCode::ReplicateOutOfRange,
+            r#""4" must be in the range 1-3"#,
+        )]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ion_concentration_not_negative() -> Result<()> {
+    let mut meta = MetaV1::example();
+    if let Some(solvents) = meta.solvents.as_mut() {
+        solvents[0].ion_concentration = Some(0.);
+    }
+    assert!(meta.find_errors().is_empty());
+
+    if let Some(solvents) = meta.solvents.as_mut() {
+        solvents[0].ion_concentration = Some(-0.5);
+    }
+    assert_eq!(
+        meta.find_errors(),
+        vec![MetaError::error(
+            "/solvents/0/ion_concentration",
+            ErrorCode::Negative,
+            "must not be negative",
+        )]
+    );
+
+    // Same check through the V2 schema
+    let errors: Vec<_> = meta
+        .to_v2()?
+        .find_errors()
+        .into_iter()
+        .map(|err| err.to_string())
+        .collect();
+    assert_eq!(
+        errors,
+        vec!["/solvents/0/ion_concentration_mol_liter: must not be negative"]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn paper_year_in_range() -> Result<()> {
+    let config = ValidationConfig {
+        today: NaiveDate::from_ymd_opt(2024, 9, 20),
+        ..Default::default()
+    };
+
+    let mut meta = MetaV1::example();
+    for year in [1665, 2025] {
+        if let Some(papers) = meta.papers.as_mut() {
+            papers[0].year = year;
+        }
+        assert!(meta.find_errors_with_config(&config).is_empty());
+    }
+
+    for year in [0, 1664, 2026, 20204] {
+        if let Some(papers) = meta.papers.as_mut() {
+            papers[0].year = year;
+        }
+        assert_eq!(
+            meta.find_errors_with_config(&config),
+            vec![MetaError::error(
+                "/papers/0/year",
+                ErrorCode::InvalidYear,
+                format!(r#""{year}" must be in the range 1665-2025"#),
+            )]
+        );
+    }
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn water_model_is_known() -> Result<()> {
+    let mut meta = MetaV1::example();
+    for model in ["TIP3P", "tip4p-ew", "SPC/E", "opc"] {
+        if let Some(water) = meta.water.as_mut() {
+            water.model = Some(model.to_string());
+        }
+        assert!(meta.find_warnings().is_empty());
+    }
+
+    if let Some(water) = meta.water.as_mut() {
+        water.model = Some("TIP-3P".to_string());
+    }
+    assert_eq!(
+        meta.find_warnings(),
+        vec![MetaError::warning(
+            "/water/model",
+            ErrorCode::UnknownWaterModel,
+            r#"unrecognized value "TIP-3P""#,
+        )]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn protonation_method_is_known() -> Result<()> {
+    let mut meta = MetaV1::example();
+    for method in ["PROPKA", "h++", "PDB2PQR", "pKa"] {
+        if let Some(protonation) = meta.protonation_method.as_mut() {
+            protonation.protonation_method = Some(method.to_string());
+        }
+        assert!(meta.find_warnings().is_empty());
+    }
+
+    if let Some(protonation) = meta.protonation_method.as_mut() {
+        protonation.protonation_method = Some("guesswork".to_string());
+    }
+    assert_eq!(
+        meta.find_warnings(),
+        vec![MetaError::warning(
+            "/protonation_method/protonation_method",
+            ErrorCode::UnknownProtonationMethod,
+            r#"unrecognized value "guesswork""#,
+        )]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn duplicate_additional_file_names() -> Result<()> {
+    let mut meta = MetaV1::example();
+    if let Some(files) = meta.additional_files.as_mut() {
+        let mut repeat = files[0].clone();
+        repeat.additional_file_type = "Miscellaneous".to_string();
+        files.push(repeat.clone());
+        files.push(repeat);
+    }
+    assert_eq!(
+        meta.find_errors(),
+        vec![
+            MetaError::error(
+                "/additional_files/2/additional_file_name",
+                ErrorCode::DuplicateFileName,
+                r#"duplicate file name "abc.cpt""#,
+            ),
+            MetaError::error(
+                "/additional_files/3/additional_file_name",
+                ErrorCode::DuplicateFileName,
+                r#"duplicate file name "abc.cpt""#,
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[cfg(feature = "yaml")]
+#[test]
+fn reads_yaml() -> Result<()> {
+    let from_toml = MetaV1::from_file(FULL_EXAMPLE)?;
+    let from_yaml = MetaV1::from_file("../tests/inputs/example.yaml")?;
+    assert_eq!(from_yaml, from_toml);
+
+    // from_string sniffs YAML from the "key:" lines
+    let yaml = fs::read_to_string("../tests/inputs/example.yaml")?;
+    assert_eq!(MetaV1::from_string(&yaml)?, from_toml);
+    assert_eq!(MetaV1::from_yaml(&from_toml.to_yaml()?)?, from_toml);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn json_schema_has_checks() -> Result<()> {
+    let schema = MetaV1::json_schema();
+    let defs = &schema["$defs"];
+    assert_eq!(
+        defs["Initial"]["properties"]["date"]["pattern"],
+        r"^\d{4}-\d{2}-\d{2}$"
+    );
+    assert_eq!(
+        defs["Initial"]["properties"]["lead_contributor_orcid"]["pattern"],
+        r"^\d{4}-\d{4}-\d{4}-\d{3}[\dX]$"
+    );
+    assert_eq!(
+        defs["Temperature"]["properties"]["temperature"]["minimum"],
+        273
+    );
+    assert_eq!(
+        defs["Temperature"]["properties"]["temperature"]["maximum"],
+        374
+    );
+    assert_eq!(defs["Initial"]["additionalProperties"], false);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn papers_to_bibtex() -> Result<()> {
+    let mut meta = MetaV1::example();
+    if let Some(papers) = meta.papers.as_mut() {
+        papers.truncate(1);
+        papers[0].title = "R&D at 100% #1".to_string();
+        papers[0].doi = None;
+    }
+    assert_eq!(
+        meta.papers_to_bibtex(),
+        [
+            "@article{Rodríguez2000,",
+            r"  title = {R\&D at 100\% \#1},",
+            "  author = {Rodríguez, I. and Fontanals, M. and Tielmann, J.S. and others},",
+            "  journal = {Nat Methods},",
+            "  volume = {17},",
+            "  number = {4},",
+            "  year = {2000},",
+            "  pages = {777--787},",
+            "}",
+            "",
+        ]
+        .join("\n")
+    );
+
+    meta.papers = None;
+    assert_eq!(meta.papers_to_bibtex(), "");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn structured_authors() -> Result<()> {
+    let doi = "doi = \"10.1038/x41594-020-0884-y\"\n";
+    let contents = fs::read_to_string(FULL_EXAMPLE)?.replace(
+        doi,
+        &[
+            doi,
+            "[[papers.author]]",
+            "family = \"Rodríguez-Espigares\"",
+            "given = \"Ismael\"",
+            "orcid = \"0000-0000-0000-000X\"",
+            "[[papers.author]]",
+            "family = \"Tiemann\"",
+            "orcid = \"bad\"\n",
+        ]
+        .join("\n"),
+    );
+    let meta = MetaV1::from_string(&contents)?;
+    let paper = &meta.papers.as_ref().unwrap()[0];
+    assert_eq!(
+        paper.authors,
+        "Rodríguez, I., Fontanals, M., Tielmann, J.S. et al."
+    );
+    assert_eq!(paper.author_list.as_ref().map(Vec::len), Some(2));
+
+    // The structured list is preferred for citations
+    assert!(meta
+        .papers_to_bibtex()
+        .contains("  author = {Rodríguez-Espigares, Ismael and Tiemann},\n"));
+    assert!(meta
+        .papers_to_ris()
+        .contains("AU  - Rodríguez-Espigares, Ismael\n"));
+
+    let errors = meta.find_errors();
+    assert!(errors
+        .iter()
+        .any(|err| err.path == "/papers/0/author_list/1/orcid"));
+
+    // And it survives a round trip
+    assert_eq!(MetaV1::from_string(&meta.to_toml()?)?, meta);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn parse_legacy_authors() -> Result<()> {
+    assert_eq!(
+        Author::parse_list("Tielmann, J.S., Jane Doe et al."),
+        vec![
+            Author {
+                family: "Tielmann".to_string(),
+                given: Some("J.S.".to_string()),
+                orcid: None,
+            },
+            Author {
+                family: "Doe".to_string(),
+                given: Some("Jane".to_string()),
+                orcid: None,
+            },
+        ]
+    );
+    assert!(Author::parse_list("").is_empty());
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn verify_dois() -> Result<()> {
+    let mut meta = MetaV1::example();
+    meta.papers.as_mut().unwrap()[1].doi = Some("10.1000/Timeout".to_string());
+
+    let mut cache = DoiCache::default();
+    cache.insert("10.1000/TIMEOUT", false);
+    let mut lookups = vec![];
+    let results = meta.verify_dois_with(&mut cache, |doi| {
+        lookups.push(doi.to_string());
+        Some(true)
+    });
+    assert_eq!(lookups, vec!["10.1038/x41594-020-0884-y"]);
+    assert_eq!(
+        results,
+        vec![
+            ("10.1038/x41594-020-0884-y".to_string(), Some(true)),
+            ("10.1000/Timeout".to_string(), Some(false)),
+        ]
+    );
+
+    // Unknowns aren't cached, so they're looked up again
+    let mut cache = DoiCache::default();
+    let results = meta.verify_dois_with(&mut cache, |_| None);
+    assert!(results.iter().all(|(_, resolves)| resolves.is_none()));
+    assert_eq!(cache, DoiCache::default());
+
+    let path = std::env::temp_dir().join(format!("dois-{}.json", std::process::id()));
+    cache.insert("10.1038/nature12572", true);
+    cache.to_file(&path)?;
+    assert_eq!(DoiCache::from_file(&path), cache);
+    fs::remove_file(&path)?;
+    assert_eq!(DoiCache::from_file(&path), DoiCache::default());
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn resolve_protein_names() -> Result<()> {
+    let mut meta = MetaV1::example();
+    let mut urls = vec![];
+    let names = meta.resolve_protein_names_with(|url| {
+        urls.push(url.to_string());
+        Ok(if url.contains("rcsb") {
+            serde_json::json!({"struct": {"title": " Human ACKR3 "}})
+        } else {
+            serde_json::json!({"proteinDescription": {
+                "submissionNames": [{"fullName": {"value": "Gag polyprotein"}}]
+            }})
+        })
+    })?;
+    assert_eq!(
+        urls,
+        vec![
+            "https://data.rcsb.org/rest/v1/core/entry/7QXR",
+            "https://rest.uniprot.org/uniprotkb/A7M120.json",
+        ]
+    );
+    assert_eq!(
+        names,
+        vec![
+            ("7QXR".to_string(), "Human ACKR3".to_string()),
+            ("A7M120".to_string(), "Gag polyprotein".to_string()),
+        ]
+    );
+
+    // Looked up by entry, without the chain
+    meta.proteins = Some(vec![Protein::ProteinOldPDB {
+        primary: None,
+        pdb_id: "1U19.A".to_string(),
+    }]);
+    let err = meta
+        .resolve_protein_names_with(|url| {
+            assert!(url.ends_with("/entry/1U19"));
+            Ok(serde_json::json!({}))
+        })
+        .unwrap_err();
+    assert_eq!(err.to_string(), "PDB 1U19.A: no title in entry");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn enrich_papers_fills_missing_fields() -> Result<()> {
+    let mut meta = MetaV1::example();
+    if let Some(papers) = meta.papers.as_mut() {
+        papers[0].authors = "".to_string();
+        papers[0].number = None;
+        papers[0].pages = None;
+        papers[1].doi = Some("10.1000/missing".to_string());
+    }
+
+    let failures = meta.enrich_papers_with(|doi| match doi {
+        "10.1038/x41594-020-0884-y" => Ok(serde_json::json!({
+            "title": ["A different title"],
+            "author": [
+                {"given": "Ismael", "family": "Rodríguez-Espigares"},
+                {"given": "Johanna K. S.", "family": "Tiemann"}
+            ],
+            "container-title": ["Nature Methods"],
+            "volume": "17",
+            "issue": "8",
+            "page": "777-787",
+            "published": {"date-parts": [[2020, 7, 13]]}
+        })),
+        _ => Err(anyhow::anyhow!("404 Not Found")),
+    });
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].0, "10.1000/missing");
+
+    // Present fields are kept
+    let paper = &meta.papers.as_ref().unwrap()[0];
+    assert_eq!(
+        paper.title,
+        "GPCRmd uncovers the dynamics of the 3D-GPCRome"
+    );
+    assert_eq!(paper.journal, "Nat Methods");
+    assert_eq!(paper.year, 2000);
+    assert_eq!(paper.authors, "Rodríguez-Espigares, I., Tiemann, J.K.S.");
+    assert_eq!(
+        paper.number.as_ref().map(ToString::to_string),
+        Some("8".into())
+    );
+    assert_eq!(paper.pages.as_deref(), Some("777-787"));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn strips_bom_and_crlf() -> Result<()> {
+    let json = fs::read_to_string(MDR0002_JSON)?;
+    let windows = format!("\u{feff}{}", json.replace('\n', "\r\n"));
+    assert_eq!(MetaV1::from_string(&windows)?, MetaV1::from_string(&json)?);
+
+    let toml = fs::read_to_string(FULL_EXAMPLE)?;
+    let windows = format!("\u{feff}{}", toml.replace('\n', "\r\n"));
+    assert_eq!(MetaV1::from_string(&windows)?, MetaV1::from_string(&toml)?);
+
+    let v2 = fs::read_to_string("../tests/inputs/example_v2.toml")?;
+    let (meta, _) = Meta::from_string_any_with_log(&format!("\u{feff}{v2}"))?;
+    assert_eq!(meta.version(), SchemaVersion::V2);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn sniffs_json_after_leading_whitespace() -> Result<()> {
+    let json = fs::read_to_string(MDR0002_JSON)?;
+    let padded = format!("\n  {json}");
+    assert_eq!(MetaV1::from_string(&padded)?, MetaV1::from_string(&json)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn parse_errors_have_locations() -> Result<()> {
+    let err = MetaV1::from_file("../tests/inputs/truncated.toml").unwrap_err();
+    let MdrMetaError::Parse(err) = err else {
+        panic!("expected a parse error, got {err:?}")
+    };
+    assert_eq!((err.line, err.column), (5, 43));
+    assert_eq!(err.message, "invalid basic string, expected `\"`");
+
+    let err = MetaV1::from_file("../tests/inputs/truncated.json").unwrap_err();
+    assert_eq!(err.to_string(), "4:62: EOF while parsing a string");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn numeric_volumes_round_trip() -> Result<()> {
+    let toml = fs::read_to_string(FULL_EXAMPLE)?
+        .replace(r#"volume = "17""#, "volume = 17")
+        .replace(r#"number = "7472""#, r#"number = "7472A""#);
+
+    // Stored as strings by default
+    let mut meta = MetaV1::from_toml(&toml)?;
+    assert!(meta.to_toml()?.contains("volume = \"17\"\n"));
+
+    meta.numeric_volumes();
+    let output = meta.to_toml()?;
+    assert!(output.contains("volume = 17\n"));
+    assert!(output.contains("volume = 502\n"));
+    assert!(output.contains("number = \"7472A\"\n"));
+    assert_eq!(MetaV1::from_toml(&output)?, MetaV1::from_toml(&toml)?);
+
+    let paper = &meta.papers.as_ref().unwrap()[1];
+    assert_eq!(paper.volume.as_i64(), Some(502));
+    assert_eq!(paper.number.as_ref().and_then(|n| n.as_i64()), None);
+    assert_eq!(
+        Numlike::Stringy("017".to_string()).to_numeric(),
+        Numlike::Stringy("017".to_string())
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn paper_volume_and_number_strings() -> Result<()> {
+    let mut meta = MetaV1::example();
+    meta.numeric_volumes();
+    let papers = meta.papers.as_ref().unwrap();
+    assert_eq!(papers[0].volume_str(), "17");
+    assert_eq!(papers[0].number_str().as_deref(), Some("4"));
+
+    let v2 = meta.to_v2()?;
+    let paper = &v2.papers.as_ref().unwrap()[1];
+    assert_eq!(paper.volume_str(), "502");
+    assert_eq!(paper.number_str().as_deref(), Some("7472"));
+
+    let mut paper = papers[1].clone();
+    paper.number = None;
+    assert_eq!(paper.number_str(), None);
+    assert_eq!(Numlike::Stringy("17A".to_string()).as_str(), "17A");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[cfg(feature = "test-util")]
+proptest! {
+    // Parsing canonicalizes, e.g., dates, volumes, and units, so either
+    // format must give back the record after a single fix, and the fixed
+    // record must then survive either format unchanged
+    #[test]
+    fn round_trips_toml_and_json(meta in any::<MetaV1>()) {
+        let fixed = test_util::fix(&meta)?;
+        prop_assert_eq!(&MetaV1::from_toml(&meta.to_toml().unwrap())?, &fixed);
+        prop_assert_eq!(&MetaV1::from_json(&meta.to_json().unwrap())?, &fixed);
+        prop_assert_eq!(&MetaV1::from_toml(&fixed.to_toml().unwrap())?, &fixed);
+        prop_assert_eq!(&MetaV1::from_json(&fixed.to_json().unwrap())?, &fixed);
+    }
+}
+
+// --------------------------------------------------
+#[test]
+fn from_str_and_display() -> Result<()> {
+    let meta: Meta = fs::read_to_string(MDR0002_JSON)?.parse()?;
+    assert_eq!(meta, Meta::from_file(MDR0002_JSON)?);
+    assert_eq!(meta.to_string(), meta.to_toml()?);
+    assert_eq!(meta.to_string().parse::<Meta>()?, meta);
+    assert!("".parse::<Meta>().is_err());
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn reads_gzip() -> Result<()> {
+    assert_eq!(
+        MetaV1::from_file("../tests/inputs/example.toml.gz")?,
+        MetaV1::from_file(FULL_EXAMPLE)?
+    );
+
+    let res = MetaV1::from_file("../tests/inputs/empty.toml.gz");
+    assert_eq!(res.unwrap_err().to_string(), "File is empty");
+
+    let res = MetaV1::from_file("../tests/inputs/truncated.toml.gz");
+    assert!(res
+        .unwrap_err()
+        .to_string()
+        .starts_with("Truncated or invalid gzip file"));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn normalizes_water_density() -> Result<()> {
+    let toml = fs::read_to_string(FULL_EXAMPLE)?.replace(
+        "density = 0.986",
+        "density = 0.986\nwater_density_units = \"g/cm^3\"",
+    );
+    let (meta, canon_log) = MetaV1::from_toml_with_log(&toml)?;
+    let water = meta.water.as_ref().unwrap();
+    assert_eq!(water.density, Some(986.));
+    assert_eq!(water.water_density_units.as_deref(), Some("kg/m^3"));
+    assert!(canon_log.contains(&CanonChange {
+        path: "/water/density".to_string(),
+        from: "0.986 g/cm^3".to_string(),
+        to: "986 kg/m^3".to_string(),
+    }));
+    assert!(meta.find_errors().is_empty());
+
+    // 1000 g/m^3 is 1 kg/m^3, far too light for liquid water
+    let meta = MetaV1::from_file(MDR4423_TOML)?;
+    assert_eq!(meta.water.as_ref().unwrap().density, Some(1.));
+    assert!(meta.find_warnings().contains(&MetaError::warning(
+        "/water/density",
+        ErrorCode::ImplausibleDensity,
+        "1 kg/m^3 is outside the plausible range for liquid water (800-1200 kg/m^3)",
+    )));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn normalizes_solvent_concentration() -> Result<()> {
+    let toml = fs::read_to_string(FULL_EXAMPLE)?
+        .replacen(
+            "ion_concentration = 0.157\nsolvent_concentration_units = \"mol/L\"",
+            "ion_concentration = 157\nsolvent_concentration_units = \"mM\"",
+            1,
+        )
+        .replacen(
+            "solvent_concentration_units = \"mol/L\"",
+            "solvent_concentration_units = \"M\"",
+            1,
+        );
+    let (meta, canon_log) = MetaV1::from_toml_with_log(&toml)?;
+    let solvents = meta.solvents.as_ref().unwrap();
+    assert_eq!(solvents[0].ion_concentration, Some(0.157));
+    assert_eq!(
+        solvents[0].solvent_concentration_units.as_deref(),
+        Some("mol/L")
+    );
+    assert_eq!(solvents[1].ion_concentration, Some(0.225));
+    assert_eq!(
+        solvents[1].solvent_concentration_units.as_deref(),
+        Some("mol/L")
+    );
+    assert!(canon_log.contains(&CanonChange {
+        path: "/solvents/0/ion_concentration".to_string(),
+        from: "157 mM".to_string(),
+        to: "0.157 mol/L".to_string(),
+    }));
+    assert!(canon_log.contains(&CanonChange {
+        path: "/solvents/1/ion_concentration".to_string(),
+        from: "0.225 M".to_string(),
+        to: "0.225 mol/L".to_string(),
+    }));
+    assert!(meta.find_errors().is_empty());
+
+    // Dividing keeps the result exact where multiplying by 0.001 wouldn't
+    let toml = fs::read_to_string(FULL_EXAMPLE)?.replacen(
+        "ion_concentration = 0.157\nsolvent_concentration_units = \"mol/L\"",
+        "ion_concentration = 9\nsolvent_concentration_units = \"mM\"",
+        1,
+    );
+    let meta = MetaV1::from_toml(&toml)?;
+    let solvents = meta.solvents.as_ref().unwrap();
+    assert_eq!(solvents[0].ion_concentration, Some(0.009));
+
+    // Unrecognized units are left as-is
+    let toml = fs::read_to_string(FULL_EXAMPLE)?.replacen(
+        "solvent_concentration_units = \"mol/L\"",
+        "solvent_concentration_units = \"ppm\"",
+        1,
+    );
+    let meta = MetaV1::from_toml(&toml)?;
+    let solvents = meta.solvents.as_ref().unwrap();
+    assert_eq!(
+        solvents[0].solvent_concentration_units.as_deref(),
+        Some("ppm")
+    );
+    assert!(meta.find_warnings().contains(&MetaError::warning(
+        "/solvents/0/solvent_concentration_units",
+        ErrorCode::UnknownUnits,
+        r#"unrecognized units "ppm""#,
+    )));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn normalizes_celsius_temperature() -> Result<()> {
+    let toml = fs::read_to_string(FULL_EXAMPLE)?.replace(
+        "temperature = 273\ntemperature_units = \"K\"",
+        "temperature = 25\ntemperature_units = \"°C\"",
+    );
+    let (meta, canon_log) = MetaV1::from_toml_with_log(&toml)?;
+    let temp = meta.temperature.as_ref().unwrap();
+    assert_eq!(temp.temperature, Some(Templike::Float(298.15)));
+    assert_eq!(temp.temperature_units.as_deref(), Some("K"));
+    assert!(canon_log.contains(&CanonChange {
+        path: "/temperature/temperature".to_string(),
+        from: "25 °C".to_string(),
+        to: "298.15 K".to_string(),
+    }));
+    assert!(meta.find_errors().is_empty());
+
+    // Without units, a low value is likely Celsius
+    let toml = fs::read_to_string(FULL_EXAMPLE)?.replace(
+        "temperature = 273\ntemperature_units = \"K\"",
+        "temperature = 25",
+    );
+    let meta = MetaV1::from_toml(&toml)?;
+    assert!(meta.find_warnings().contains(&MetaError::warning(
+        "/temperature/temperature",
+        ErrorCode::PossiblyCelsius,
+        r#""25" may be Celsius; set temperature_units = "C" to convert"#,
+    )));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn normalizes_whitespace() -> Result<()> {
+    let toml = fs::read_to_string(FULL_EXAMPLE)?
+        .replace(
+            r#"lead_contributor_orcid = "0000-0000-0000-000X""#,
+            r#"lead_contributor_orcid = " 0000-0000-0000-000X\t""#,
+        )
+        .replace(
+            r#"user_orcid = "0000-0000-0000-002X""#,
+            r#"user_orcid = "0000-0000-0000-002X ""#,
+        )
+        .replace(
+            r#"title = "GPCRmd uncovers the dynamics of the 3D-GPCRome""#,
+            r#"title = "GPCRmd  uncovers the\tdynamics of the 3D-GPCRome ""#,
+        );
+    let (meta, canon_log) = MetaV1::from_toml_with_log(&toml)?;
+    assert!(meta.find_errors().is_empty());
+    assert_eq!(meta, MetaV1::from_file(FULL_EXAMPLE)?);
+    assert!(canon_log.contains(&CanonChange {
+        path: "/initial/lead_contributor_orcid".to_string(),
+        from: " 0000-0000-0000-000X\t".to_string(),
+        to: "0000-0000-0000-000X".to_string(),
+    }));
+    assert!(canon_log.contains(&CanonChange {
+        path: "/papers/0/title".to_string(),
+        from: "GPCRmd  uncovers the\tdynamics of the 3D-GPCRome ".to_string(),
+        to: "GPCRmd uncovers the dynamics of the 3D-GPCRome".to_string(),
+    }));
+
+    // Multi-line text keeps its formatting
+    let mut meta = MetaV1::example();
+    meta.initial.commands = Some("gmx grompp \\\n    -f md.mdp\n".to_string());
+    let toml = meta.to_toml()?;
+    assert_eq!(
+        MetaV1::from_toml(&toml)?.initial.commands,
+        meta.initial.commands
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn fractional_temperature() -> Result<()> {
+    let toml = fs::read_to_string(FULL_EXAMPLE)?
+        .replace("temperature = 273\n", "temperature = 298.15\n");
+    let meta = MetaV1::from_toml(&toml)?;
+    let temp = meta.temperature.as_ref().unwrap();
+    assert_eq!(temp.temperature, Some(Templike::Float(298.15)));
+    assert!(meta.find_errors().is_empty());
+    assert!(meta.to_toml()?.contains("temperature = 298.15\n"));
+    assert_eq!(
+        meta.to_v2()?.temperature_kelvin,
+        Some(Templike::Float(298.15))
+    );
+
+    // The range check keeps the fraction
+    let toml = fs::read_to_string(FULL_EXAMPLE)?
+        .replace("temperature = 273\n", "temperature = 272.5\n");
+    let errors = MetaV1::from_toml(&toml)?.find_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, ErrorCode::TemperatureOutOfRange);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn normalizes_timestep() -> Result<()> {
+    let toml = fs::read_to_string(FULL_EXAMPLE)?.replace(
+        "integration_time_step = 2.0\ntimestep_units = \"fs\"",
+        "integration_time_step = 0.002\ntimestep_units = \"ps\"",
+    );
+    let (meta, canon_log) = MetaV1::from_toml_with_log(&toml)?;
+    let timestep = meta.timestep_information.as_ref().unwrap();
+    assert_eq!(timestep.integration_time_step, Some(2.));
+    assert_eq!(timestep.timestep_units.as_deref(), Some("fs"));
+    assert!(canon_log.contains(&CanonChange {
+        path: "/timestep_information/integration_time_step".to_string(),
+        from: "0.002 ps".to_string(),
+        to: "2 fs".to_string(),
+    }));
+    assert!(meta.find_errors().is_empty());
+
+    // 100 fs would blow up any simulation
+    let toml = fs::read_to_string(FULL_EXAMPLE)?
+        .replace("integration_time_step = 2.0", "integration_time_step = 100");
+    let meta = MetaV1::from_toml(&toml)?;
+    assert!(meta.find_warnings().contains(&MetaError::warning(
+        "/timestep_information/integration_time_step",
+        ErrorCode::ImplausibleTimestep,
+        "100 fs is not a plausible integration timestep",
+    )));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn mdrepo_id_format() -> Result<()> {
+    let mut meta = MetaV1::example();
+    meta.mdrepo_id = Some("MDR_00004423".to_string());
+    assert!(meta.find_errors().is_empty());
+
+    meta.mdrepo_id = Some("MDR-4423".to_string());
+    assert_eq!(
+        meta.find_errors(),
+        vec![MetaError::error(
+            "/mdrepo_id",
+            ErrorCode::InvalidMdrepoId,
+            r#"invalid MDRepo ID "MDR-4423""#,
+        )]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn is_valid_ignores_warnings() -> Result<()> {
+    let mut meta = MetaV1::example();
+    assert!(meta.is_valid());
+
+    // An unknown water model only warns
+    if let Some(water) = meta.water.as_mut() {
+        water.model = Some("MyWater".to_string());
+    }
+    assert!(!meta.find_warnings().is_empty());
+    assert!(meta.is_valid());
+
+    meta.mdrepo_id = Some("MDR-4423".to_string());
+    assert!(!meta.is_valid());
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn normalize_toml_keeps_comments() -> Result<()> {
+    let original = fs::read_to_string(MDR0002_TOML)?.replace(
+        "date = 2020-07-13",
+        "date = 2020-07-13 # from the lab notebook",
+    );
+    let normalized =
+        Meta::normalize_toml_in_place(&format!("# Submitted by hand\n{original}"))?;
+    assert!(normalized.starts_with("# Submitted by hand\n"));
+    assert!(normalized.contains(r#"date = "2020-07-13" # from the lab notebook"#));
+    assert!(!normalized.contains("pdb_id"));
+    assert!(normalized.contains(
+        r#"molecule_id_type = "PDB"
+molecule_id = "1U19.A""#
+    ));
+
+    // Nothing else changes, so it parses to the same metadata
+    assert_eq!(
+        Meta::from_toml(&normalized)?,
+        Meta::from_file(MDR0002_TOML)?
+    );
+
+    Ok(())
+}
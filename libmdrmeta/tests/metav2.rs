@@ -0,0 +1,632 @@
+use anyhow::Result;
+use libmdrmeta::{
+    common::{CanonChange, Datelike, Format, SchemaVersion},
+    metav2::MetaV2,
+    AnyMeta, Meta,
+};
+use pretty_assertions::assert_eq;
+use std::{fs, io};
+
+const FULL_EXAMPLE: &str = "../tests/inputs/example.toml";
+const FULL_EXAMPLE_V2: &str = "../tests/inputs/example_v2.toml";
+const MDR0002_JSON: &str = "../tests/inputs/MDR_00000002.json";
+
+// --------------------------------------------------
+#[test]
+fn example_round_trips() -> Result<()> {
+    let example = MetaV2::example();
+
+    let from_toml = MetaV2::from_toml(&example.to_toml()?)?;
+    assert_eq!(from_toml.to_toml()?, example.to_toml()?);
+
+    let from_json = MetaV2::from_json(&example.to_json()?)?;
+    assert_eq!(from_json.to_json()?, example.to_json()?);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn example_has_no_errors() -> Result<()> {
+    assert!(MetaV2::example().find_errors().is_empty());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn water_density_must_be_positive() -> Result<()> {
+    let mut meta = MetaV2::example();
+    for density in [0., -1.] {
+        meta.water_density_kg_m3 = Some(density);
+        let errors: Vec<(String, String)> =
+            meta.find_errors().into_iter().map(Into::into).collect();
+        assert_eq!(
+            errors,
+            vec![(
+                "/water_density_kg_m3".to_string(),
+                "must be positive".to_string()
+            )]
+        );
+    }
+
+    meta.water_density_kg_m3 = Some(997.);
+    assert!(meta.find_errors().is_empty());
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn simulation_length_and_box_must_be_positive() -> Result<()> {
+    let mut meta = MetaV2::example();
+    meta.simulation_length_ns = Some(0.);
+    meta.box_dimensions_nm = Some([7.5, -1., 9.]);
+    let errors: Vec<(String, String)> =
+        meta.find_errors().into_iter().map(Into::into).collect();
+    assert_eq!(
+        errors,
+        vec![
+            (
+                "/simulation_length_ns".to_string(),
+                "must be positive".to_string()
+            ),
+            (
+                "/box_dimensions_nm/1".to_string(),
+                "must be positive".to_string()
+            ),
+        ]
+    );
+
+    meta.simulation_length_ns = Some(f64::INFINITY);
+    meta.box_dimensions_nm = None;
+    let errors: Vec<(String, String)> =
+        meta.find_errors().into_iter().map(Into::into).collect();
+    assert_eq!(
+        errors,
+        vec![(
+            "/simulation_length_ns".to_string(),
+            "inf is not a finite value".to_string()
+        )]
+    );
+
+    // Carried through V1
+    let meta = MetaV2::example();
+    let round_trip = meta.to_v1().to_v2()?;
+    assert_eq!(round_trip.simulation_length_ns, meta.simulation_length_ns);
+    assert_eq!(round_trip.box_dimensions_nm, meta.box_dimensions_nm);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ionic_strength_uses_concentration_units() -> Result<()> {
+    // ½ (0.157 · 1² + 0.225 · (-1)²)
+    let mut meta = MetaV2::example();
+    let strength = meta.ionic_strength().expect("ionic strength");
+    assert!((strength - 0.191).abs() < 1e-9);
+
+    let solvents = meta.solvents.as_mut().expect("solvents");
+    solvents[0].ion_concentration_mol_liter = 157.;
+    solvents[0].concentration_units = Some("mM".to_string());
+    let strength = meta.ionic_strength().expect("ionic strength");
+    assert!((strength - 0.191).abs() < 1e-9);
+
+    // Units that can't be converted give no answer
+    meta.solvents.as_mut().expect("solvents")[0].concentration_units =
+        Some("ppm".to_string());
+    assert_eq!(meta.ionic_strength(), None);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ph_must_be_in_range() -> Result<()> {
+    let mut meta = MetaV2::example();
+    meta.ph = Some(14.5);
+    let errors: Vec<(String, String)> =
+        meta.find_errors().into_iter().map(Into::into).collect();
+    assert_eq!(
+        errors,
+        vec![(
+            "/ph".to_string(),
+            "14.5 must be in the range 0-14".to_string()
+        )]
+    );
+
+    meta.ph = Some(13.5);
+    let errors = meta.find_errors();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].is_warning());
+    assert_eq!(
+        errors[0].to_string(),
+        "/ph: 13.5 is outside the usual range 1-13"
+    );
+
+    // Carried through V1
+    assert_eq!(meta.to_v1().to_v2()?.ph, Some(13.5));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn detects_version() -> Result<()> {
+    let v1_toml = fs::read_to_string(FULL_EXAMPLE)?;
+    assert_eq!(Meta::detect_version(&v1_toml), SchemaVersion::V1);
+
+    let v1_json = fs::read_to_string(MDR0002_JSON)?;
+    assert_eq!(Meta::detect_version(&v1_json), SchemaVersion::V1);
+
+    let v2_toml = fs::read_to_string(FULL_EXAMPLE_V2)?;
+    assert_eq!(Meta::detect_version(&v2_toml), SchemaVersion::V2);
+
+    let v2_json = MetaV2::example().to_json()?;
+    assert_eq!(Meta::detect_version(&v2_json), SchemaVersion::V2);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn from_file_any() -> Result<()> {
+    let meta = Meta::from_file_any(FULL_EXAMPLE)?;
+    assert!(matches!(meta, AnyMeta::V1(_)));
+
+    let meta = Meta::from_file_any(FULL_EXAMPLE_V2)?;
+    assert_eq!(meta.version(), SchemaVersion::V2);
+    assert!(meta.find_errors().is_empty());
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn v1_to_v2_to_v1() -> Result<()> {
+    let mut original = Meta::from_file(FULL_EXAMPLE)?;
+    let round_trip = original.to_v2()?.to_v1();
+
+    // V2 has no place for these
+    original.initial.scientific_goal = None;
+    if let Some(water) = original.water.as_mut() {
+        water.water_density_units = None;
+    }
+    assert_eq!(round_trip, original);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn try_into_between_versions() -> Result<()> {
+    let meta = Meta::from_file(FULL_EXAMPLE)?;
+    let meta_v2: MetaV2 = meta.clone().try_into()?;
+    assert_eq!(meta_v2, meta.to_v2()?);
+
+    let round_trip: Meta = meta_v2.clone().into();
+    assert_eq!(round_trip, meta_v2.to_v1());
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn to_v1_skips_empty_tables() -> Result<()> {
+    let mut meta = MetaV2::example();
+    meta.replicate_id = None;
+    meta.total_replicates = None;
+    meta.forcefield = None;
+    meta.forcefield_comments = None;
+    meta.water_is_present = None;
+    meta.water_model = Some("TIP3P".to_string());
+    meta.water_density_kg_m3 = None;
+
+    let meta_v1 = meta.to_v1();
+    assert!(meta_v1.replicates.is_none());
+    assert!(meta_v1.forcefield.is_none());
+
+    let water = meta_v1.water.expect("water");
+    assert!(water.is_present);
+    assert_eq!(water.model, Some("TIP3P".to_string()));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn external_ids_match_v1() -> Result<()> {
+    assert_eq!(
+        MetaV2::example().external_ids(),
+        Meta::example().external_ids()
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn to_v2_parses_pages() -> Result<()> {
+    let mut meta = Meta::example();
+    if let Some(papers) = meta.papers.as_mut() {
+        papers[0].pages = Some("777–787".to_string());
+        papers[1].pages = Some("42".to_string());
+    }
+
+    let papers = meta.to_v2()?.papers.expect("papers");
+    assert_eq!(papers[0].pages.as_deref(), Some("777–787"));
+    assert_eq!(
+        (papers[0].page_start, papers[0].page_end),
+        (Some(777), Some(787))
+    );
+    assert_eq!(papers[1].pages.as_deref(), Some("42"));
+    assert_eq!((papers[1].page_start, papers[1].page_end), (Some(42), None));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn page_start_must_not_follow_end() -> Result<()> {
+    let mut meta = MetaV2::example();
+    if let Some(papers) = meta.papers.as_mut() {
+        papers[0].page_start = Some(787);
+        papers[0].page_end = Some(777);
+    }
+    let errors: Vec<(String, String)> =
+        meta.find_errors().into_iter().map(Into::into).collect();
+    assert_eq!(
+        errors,
+        vec![(
+            "/papers/0/page_end".to_string(),
+            "777 is before page_start 787".to_string()
+        )]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn molecule_id_type_must_be_known() -> Result<()> {
+    let mut meta = MetaV2::example();
+    if let Some(proteins) = meta.proteins.as_mut() {
+        proteins[0].molecule_id_type = "pdb".to_string();
+    }
+    let errors: Vec<(String, String)> =
+        meta.find_errors().into_iter().map(Into::into).collect();
+    assert_eq!(
+        errors,
+        vec![(
+            "/proteins/0/molecule_id_type".to_string(),
+            r#"unrecognized value "pdb", expected one of PDB, Uniprot, Unknown"#
+                .to_string()
+        )]
+    );
+
+    // Lenient parsing fixes the case
+    let toml = meta.to_toml()?;
+    let (meta, unknown) = MetaV2::from_string_lenient(&toml)?;
+    assert!(unknown.is_empty());
+    assert_eq!(meta.proteins.as_ref().unwrap()[0].molecule_id_type, "PDB");
+    assert!(meta.find_errors().is_empty());
+
+    let (_, canon_log, _) = Meta::from_string_any_lenient(&toml)?;
+    assert_eq!(
+        canon_log,
+        vec![CanonChange {
+            path: "/proteins/0/molecule_id_type".to_string(),
+            from: "pdb".to_string(),
+            to: "PDB".to_string(),
+        }]
+    );
+
+    // The schema allows only the same values
+    let schema = MetaV2::json_schema();
+    assert_eq!(
+        schema["$defs"]["Protein"]["properties"]["molecule_id_type"]["enum"],
+        serde_json::json!(["PDB", "Uniprot", "Unknown"])
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn validates_protein_accessions() -> Result<()> {
+    let mut meta = MetaV2::example();
+    if let Some(proteins) = meta.proteins.as_mut() {
+        proteins[0].molecule_id = "A7M120".to_string();
+        proteins[1].molecule_id = "7QXR".to_string();
+    }
+    let errors: Vec<(String, String)> =
+        meta.find_errors().into_iter().map(Into::into).collect();
+    assert_eq!(
+        errors,
+        vec![
+            (
+                "/proteins/0/molecule_id".to_string(),
+                r#"invalid PDB ID "A7M120""#.to_string()
+            ),
+            (
+                "/proteins/1/molecule_id".to_string(),
+                r#"invalid UniProt accession "7QXR""#.to_string()
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn clones_parsed_meta() -> Result<()> {
+    let meta = MetaV2::from_file(FULL_EXAMPLE_V2)?;
+    let copy = meta.clone();
+    assert_eq!(copy, meta);
+    assert_eq!(copy.to_toml()?, meta.to_toml()?);
+
+    let any = Meta::from_file_any(FULL_EXAMPLE)?;
+    assert_eq!(any.clone(), any);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn rejects_impossible_dates() -> Result<()> {
+    let mut meta = MetaV2::example();
+    meta.date = Datelike::Stringy("2020-02-30".to_string());
+    let errors: Vec<(String, String)> =
+        meta.find_errors().into_iter().map(Into::into).collect();
+    assert_eq!(
+        errors,
+        vec![(
+            "/date".to_string(),
+            r#""2020-02-30" is not a calendar date"#.to_string()
+        )]
+    );
+
+    meta.date = Datelike::Stringy("2020-02-29".to_string());
+    assert!(meta.find_errors().is_empty());
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn forcefield_is_known() -> Result<()> {
+    let mut meta = MetaV2::example();
+    for forcefield in ["Amber99SB-ILDN", "CHARMM36", "opls-aa", "gromos"] {
+        meta.forcefield = Some(vec![forcefield.to_string()]);
+        assert!(meta.find_warnings().is_empty());
+    }
+
+    meta.forcefield = Some(vec!["MyCustomFF".to_string()]);
+    let warnings: Vec<(String, String)> =
+        meta.find_warnings().into_iter().map(Into::into).collect();
+    assert_eq!(
+        warnings,
+        vec![(
+            "/forcefield".to_string(),
+            r#"unrecognized value "MyCustomFF""#.to_string()
+        )]
+    );
+
+    // Each of a list is checked
+    meta.forcefield = Some(vec!["Amber14SB".to_string(), "MyCustomFF".to_string()]);
+    let warnings: Vec<(String, String)> =
+        meta.find_warnings().into_iter().map(Into::into).collect();
+    assert_eq!(
+        warnings,
+        vec![(
+            "/forcefield/1".to_string(),
+            r#"unrecognized value "MyCustomFF""#.to_string()
+        )]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn timestep_is_plausible() -> Result<()> {
+    let mut meta = MetaV2::example();
+    meta.timestep_ns = Some(5e-6);
+    let warnings: Vec<(String, String)> =
+        meta.find_warnings().into_iter().map(Into::into).collect();
+    assert_eq!(
+        warnings,
+        vec![(
+            "/timestep_ns".to_string(),
+            "5 fs exceeds 4 fs without hydrogen mass repartitioning".to_string()
+        )]
+    );
+
+    meta.hydrogen_mass_repartitioning = Some(true);
+    assert!(meta.find_warnings().is_empty());
+
+    meta.timestep_ns = Some(1e-4);
+    let warnings: Vec<(String, String)> =
+        meta.find_warnings().into_iter().map(Into::into).collect();
+    assert_eq!(
+        warnings,
+        vec![(
+            "/timestep_ns".to_string(),
+            "100 fs is not a plausible integration timestep".to_string()
+        )]
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[cfg(feature = "yaml")]
+#[test]
+fn yaml_round_trips() -> Result<()> {
+    let example = MetaV2::example();
+    let yaml = example.to_yaml()?;
+    assert_eq!(Meta::detect_version(&yaml), SchemaVersion::V2);
+    assert_eq!(MetaV2::from_string(&yaml)?, example);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn papers_to_ris() -> Result<()> {
+    let mut meta = MetaV2::example();
+    if let Some(papers) = meta.papers.as_mut() {
+        papers.remove(0);
+        papers[0].authors = "Ring, A.; Manglik, A. and Kruse, A.".to_string();
+        papers[0].pages = None;
+    }
+    assert_eq!(
+        meta.papers_to_ris(),
+        [
+            "TY  - JOUR",
+            "TI  - Adrenaline-activated structure of β2-adrenoceptor stabilized by an engineered nanobody",
+            "AU  - Ring, A.",
+            "AU  - Manglik, A.",
+            "AU  - Kruse, A.",
+            "JO  - Nature",
+            "VL  - 502",
+            "IS  - 7472",
+            "PY  - 2013",
+            "SP  - 575",
+            "EP  - 579",
+            "DO  - 10.1038/nature12572",
+            "ER  - ",
+            "",
+        ]
+        .join("\n")
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn reader_and_writer() -> Result<()> {
+    let example = MetaV2::example();
+    for format in [Format::Json, Format::Toml] {
+        let mut buf = vec![];
+        example.to_writer(&mut buf, format)?;
+        assert_eq!(MetaV2::from_reader(buf.as_slice(), format)?, example);
+    }
+
+    let file = fs::File::open(FULL_EXAMPLE)?;
+    assert_eq!(
+        Meta::from_reader(file, Format::Toml)?,
+        Meta::from_file(FULL_EXAMPLE)?
+    );
+
+    let res = Meta::from_reader(io::empty(), Format::Json);
+    assert_eq!(res.unwrap_err().to_string(), "File is empty");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn sniffs_json_after_leading_whitespace() -> Result<()> {
+    let json = MetaV2::example().to_json()?;
+    assert_eq!(
+        MetaV2::from_string(&format!("\n  {json}"))?,
+        MetaV2::example()
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn normalize_toml_in_place() -> Result<()> {
+    let original = fs::read_to_string(FULL_EXAMPLE_V2)?.replacen(
+        r#"date = "2000-01-01""#,
+        r#"date = "Jan 1, 2000" # from the lab notebook"#,
+        1,
+    );
+    let normalized =
+        Meta::normalize_toml_in_place(&format!("# Submitted by hand\n{original}"))?;
+    assert!(normalized.starts_with("# Submitted by hand\n"));
+    assert!(normalized.contains(r#"date = "2000-01-01" # from the lab notebook"#));
+    assert_eq!(
+        MetaV2::from_toml(&normalized)?,
+        MetaV2::from_file(FULL_EXAMPLE_V2)?
+    );
+
+    let res = Meta::normalize_toml_in_place(&original.replacen(
+        r#"date = "Jan 1, 2000""#,
+        "date = 2000",
+        1,
+    ));
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "date must be a date, got integer"
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn normalizes_whitespace() -> Result<()> {
+    let toml = fs::read_to_string(FULL_EXAMPLE_V2)?
+        .replace(
+            r#"lead_contributor_orcid = "0000-0000-0000-000X""#,
+            "lead_contributor_orcid = \" 0000-0000-0000-000X\\t\"",
+        )
+        .replace(
+            r#"short_description = "Adaptive sampling of AncFT luciferase""#,
+            r#"short_description = "Adaptive  sampling of AncFT luciferase ""#,
+        );
+    let (meta, canon_log) = MetaV2::from_toml_with_log(&toml)?;
+    assert_eq!(meta, MetaV2::from_file(FULL_EXAMPLE_V2)?);
+    assert!(meta.find_errors().is_empty());
+    assert_eq!(
+        canon_log,
+        vec![
+            CanonChange {
+                path: "/lead_contributor_orcid".to_string(),
+                from: " 0000-0000-0000-000X\t".to_string(),
+                to: "0000-0000-0000-000X".to_string(),
+            },
+            CanonChange {
+                path: "/short_description".to_string(),
+                from: "Adaptive  sampling of AncFT luciferase ".to_string(),
+                to: "Adaptive sampling of AncFT luciferase".to_string(),
+            },
+        ]
+    );
+
+    // Reading either version logs the changes
+    let (_, any_log) = Meta::from_string_any_with_log(&toml)?;
+    assert_eq!(any_log, canon_log);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn normalizes_date() -> Result<()> {
+    let example = fs::read_to_string(FULL_EXAMPLE_V2)?;
+    for (date, from) in [
+        ("2000-01-01", None),
+        (r#""Jan 1, 2000""#, Some("Jan 1, 2000")),
+    ] {
+        let toml = example.replace(r#"date = "2000-01-01""#, &format!("date = {date}"));
+        let (meta, canon_log) = MetaV2::from_toml_with_log(&toml)?;
+        assert_eq!(meta.date, Datelike::Stringy("2000-01-01".to_string()));
+        assert!(meta.find_errors().is_empty());
+        let expected: Vec<_> = from
+            .map(|from| CanonChange {
+                path: "/date".to_string(),
+                from: from.to_string(),
+                to: "2000-01-01".to_string(),
+            })
+            .into_iter()
+            .collect();
+        assert_eq!(canon_log, expected);
+    }
+
+    Ok(())
+}
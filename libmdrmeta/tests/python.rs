@@ -0,0 +1,31 @@
+#![cfg(feature = "python")]
+
+use libmdrmeta::python::PyMeta;
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+const EXAMPLE_TOML: &str = "../tests/inputs/example.toml";
+const EXAMPLE_ERRORS_TOML: &str = "../tests/inputs/example_with_errors.toml";
+
+// --------------------------------------------------
+#[test]
+fn py_meta() -> PyResult<()> {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let class = py.get_type::<PyMeta>();
+        let meta = class.call_method1("from_file", (EXAMPLE_TOML,))?;
+        let toml: String = meta.call_method0("to_toml")?.extract()?;
+        let meta = class.call_method1("from_string", (toml,))?;
+        let json: String = meta.call_method0("to_json")?.extract()?;
+        assert!(json.starts_with('{'));
+
+        let meta = class.call_method1("from_file", (EXAMPLE_ERRORS_TOML,))?;
+        let findings: Vec<(String, String)> =
+            meta.call_method0("find_errors")?.extract()?;
+        assert!(!findings.is_empty());
+        assert!(findings.iter().all(|(path, _)| path.starts_with('/')));
+
+        let err = class.call_method1("from_string", ("",)).unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py));
+        Ok(())
+    })
+}
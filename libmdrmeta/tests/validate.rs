@@ -0,0 +1,128 @@
+use anyhow::Result;
+use libmdrmeta::{
+    error::{ErrorCode, ValidateError},
+    validate::{
+        validate_date, validate_doi, validate_mdrepo_id, validate_orcid,
+        validate_pdb_id, validate_smiles, validate_uniprot_id,
+    },
+};
+use pretty_assertions::assert_eq;
+
+// --------------------------------------------------
+#[test]
+fn validates_orcids() -> Result<()> {
+    // The checksum may be a digit or "X"
+    for good in ["0000-0002-1825-009X", "0000-0001-7083-8667"] {
+        assert!(validate_orcid(good).is_ok(), "{good}");
+    }
+    assert_eq!(
+        validate_orcid("0000-0002-1825"),
+        Err(ValidateError::new(
+            ErrorCode::InvalidOrcid,
+            r#"invalid ORCID "0000-0002-1825""#
+        ))
+    );
+
+    // The whole value must match, not just part of it
+    for bad in ["x0000-0000-0000-000Xjunk", "0000-0002-1825-009Y"] {
+        assert_eq!(
+            validate_orcid(bad).unwrap_err().code,
+            ErrorCode::InvalidOrcid,
+            "{bad}"
+        );
+    }
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn validates_dois() -> Result<()> {
+    assert!(validate_doi("10.1021/acs.jpcb.4c01957").is_ok());
+    let err = validate_doi("doi.org/10.1021").unwrap_err();
+    assert_eq!(err.code, ErrorCode::InvalidDoi);
+    assert_eq!(err.to_string(), r#"invalid DOI "doi.org/10.1021""#);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn validates_dates() -> Result<()> {
+    assert!(validate_date("2024-02-29").is_ok());
+    assert_eq!(
+        validate_date("Feb 5, 2000").unwrap_err().to_string(),
+        r#"invalid date "Feb 5, 2000""#
+    );
+    assert_eq!(
+        validate_date("2023-02-29").unwrap_err().to_string(),
+        r#""2023-02-29" is not a calendar date"#
+    );
+    assert_eq!(
+        validate_date("x2024-02-29y").unwrap_err().to_string(),
+        r#"invalid date "x2024-02-29y""#
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn validates_protein_ids() -> Result<()> {
+    assert!(validate_pdb_id("1U19.A").is_ok());
+    assert_eq!(
+        validate_pdb_id("A7M120").unwrap_err().code,
+        ErrorCode::InvalidPdbId
+    );
+    assert!(validate_uniprot_id("A7M120").is_ok());
+    assert_eq!(
+        validate_uniprot_id("7QXR").unwrap_err().code,
+        ErrorCode::InvalidUniprotId
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn validates_mdrepo_ids() -> Result<()> {
+    assert!(validate_mdrepo_id("MDR_00004423").is_ok());
+    for bad in ["MDR_4423", "mdr_00004423", "MDR_000044231"] {
+        let err = validate_mdrepo_id(bad).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidMdrepoId);
+        assert_eq!(err.to_string(), format!(r#"invalid MDRepo ID "{bad}""#));
+    }
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn validates_smiles() -> Result<()> {
+    for good in [
+        "CC(C)C1=CC(=C(C(=C1)C(C)C)C2=CSC(=N2)N(CCN(C)C)CC3=CN=CC=C3)C(C)C",
+        "c1ccc(cc1)CCCNC(=O)c2ccc(cc2)NC(=O)n3cc4ccccc4c3",
+        "[Na+].[Cl-]",
+        "[13CH4]",
+        "C%10CCCCC%10",
+        r"F/C=C\F",
+    ] {
+        assert!(validate_smiles(good).is_ok(), "{good}");
+    }
+
+    for (bad, reason) in [
+        ("CC(C", r#"unclosed "(""#),
+        ("CC)C", r#"unmatched ")""#),
+        ("[Na+", r#"unclosed "[""#),
+        ("Na+]", r#"unmatched "]""#),
+        ("C1CCC", "unclosed ring bond 1"),
+        ("C%1CC", r#""%" must be followed by two digits"#),
+        ("CC C", r#"illegal character " ""#),
+    ] {
+        let err = validate_smiles(bad).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidSmiles);
+        assert_eq!(err.to_string(), format!("invalid SMILES: {reason}"));
+    }
+
+    Ok(())
+}
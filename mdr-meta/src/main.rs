@@ -1,11 +1,22 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use clap::{builder::PossibleValue, Parser, ValueEnum};
-use libmdrmeta::Meta;
-use multimap::MultiMap;
+use glob::Pattern;
+use libmdrmeta::{
+    common::CanonChange,
+    config::ValidationConfig,
+    error::{ErrorCode, MdrMetaError, MetaError},
+    metav2::MetaV2,
+    AnyMeta, Meta,
+};
 //use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::{
-    fs::File,
-    io::{self, Write},
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    slice,
 };
 
 // --------------------------------------------------
@@ -29,6 +40,30 @@ pub enum Command {
 
     /// Check metadata file for errors
     Check(CheckArgs),
+
+    /// Print the JSON Schema for the metadata
+    Schema(SchemaArgs),
+
+    /// Print citations for the papers
+    Cite(CiteArgs),
+
+    /// Convert metadata between schema versions
+    Convert(ConvertArgs),
+
+    /// Show the field changes between two metadata files
+    Diff(DiffArgs),
+
+    /// Combine the metadata of replicates into one file
+    Merge(MergeArgs),
+
+    /// Score how completely the optional fields are filled in
+    Score(ScoreArgs),
+
+    /// Print a one-line summary of the metadata
+    Info(InfoArgs),
+
+    /// Summarize every metadata file under a directory
+    Stats(StatsArgs),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -50,6 +85,84 @@ impl ValueEnum for FileFormat {
     }
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub enum SchemaVersion {
+    V1,
+    V2,
+}
+
+impl ValueEnum for SchemaVersion {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[SchemaVersion::V1, SchemaVersion::V2]
+    }
+
+    fn to_possible_value<'a>(&self) -> Option<PossibleValue> {
+        Some(match self {
+            SchemaVersion::V1 => PossibleValue::new("v1"),
+            SchemaVersion::V2 => PossibleValue::new("v2"),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum CheckFormat {
+    Text,
+    Json,
+    Sarif,
+}
+
+impl ValueEnum for CheckFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[CheckFormat::Text, CheckFormat::Json, CheckFormat::Sarif]
+    }
+
+    fn to_possible_value<'a>(&self) -> Option<PossibleValue> {
+        Some(match self {
+            CheckFormat::Text => PossibleValue::new("text"),
+            CheckFormat::Json => PossibleValue::new("json"),
+            CheckFormat::Sarif => PossibleValue::new("sarif"),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum CiteFormat {
+    Bibtex,
+    Ris,
+}
+
+impl ValueEnum for CiteFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[CiteFormat::Bibtex, CiteFormat::Ris]
+    }
+
+    fn to_possible_value<'a>(&self) -> Option<PossibleValue> {
+        Some(match self {
+            CiteFormat::Bibtex => PossibleValue::new("bibtex"),
+            CiteFormat::Ris => PossibleValue::new("ris"),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum KeyOrder {
+    Declared,
+    Alpha,
+}
+
+impl ValueEnum for KeyOrder {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[KeyOrder::Declared, KeyOrder::Alpha]
+    }
+
+    fn to_possible_value<'a>(&self) -> Option<PossibleValue> {
+        Some(match self {
+            KeyOrder::Declared => PossibleValue::new("declared"),
+            KeyOrder::Alpha => PossibleValue::new("alpha"),
+        })
+    }
+}
+
 #[derive(Debug, Parser)]
 pub struct ExampleArgs {
     /// Output format
@@ -62,6 +175,191 @@ pub struct ExampleArgs {
     )]
     format: FileFormat,
 
+    /// Schema version
+    #[arg(
+        short,
+        long,
+        value_name = "VERSION",
+        default_value = "v1",
+        value_parser(clap::value_parser!(SchemaVersion)),
+    )]
+    version: SchemaVersion,
+
+    /// Key order
+    #[arg(
+        long,
+        value_name = "ORDER",
+        default_value = "declared",
+        value_parser(clap::value_parser!(KeyOrder)),
+    )]
+    order: KeyOrder,
+
+    /// Output filename
+    #[arg(short, long, value_name = "OUTPUT", default_value = "-")]
+    outfile: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct SchemaArgs {
+    /// Schema version
+    #[arg(
+        short,
+        long,
+        value_name = "VERSION",
+        default_value = "v1",
+        value_parser(clap::value_parser!(SchemaVersion)),
+    )]
+    version: SchemaVersion,
+
+    /// Output filename
+    #[arg(short, long, value_name = "OUTPUT", default_value = "-")]
+    outfile: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct CiteArgs {
+    /// Input filename, or "-" for stdin
+    #[arg(value_name = "FILE")]
+    filename: String,
+
+    /// Citation format
+    #[arg(
+        short,
+        long,
+        value_name = "FORMAT",
+        default_value = "bibtex",
+        value_parser(clap::value_parser!(CiteFormat)),
+    )]
+    format: CiteFormat,
+
+    /// Output filename
+    #[arg(short, long, value_name = "OUTPUT", default_value = "-")]
+    outfile: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct ConvertArgs {
+    /// Input filename, or "-" for stdin
+    #[arg(value_name = "FILE")]
+    filename: String,
+
+    /// Input schema version, detected when omitted
+    #[arg(
+        long,
+        value_name = "VERSION",
+        value_parser(clap::value_parser!(SchemaVersion)),
+    )]
+    from: Option<SchemaVersion>,
+
+    /// Output schema version
+    #[arg(
+        long,
+        value_name = "VERSION",
+        default_value = "v2",
+        value_parser(clap::value_parser!(SchemaVersion)),
+    )]
+    to: SchemaVersion,
+
+    /// Sort contributors, permissions, proteins, and papers for stable diffs
+    #[arg(long)]
+    sort: bool,
+
+    /// Output format
+    #[arg(
+        short,
+        long,
+        value_name = "FORMAT",
+        default_value = "toml",
+        value_parser(clap::value_parser!(FileFormat)),
+    )]
+    format: FileFormat,
+
+    /// Output filename
+    #[arg(short, long, value_name = "OUTPUT", default_value = "-")]
+    outfile: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct DiffArgs {
+    /// Original filename
+    #[arg(value_name = "FILE1")]
+    filename1: String,
+
+    /// Changed filename
+    #[arg(value_name = "FILE2")]
+    filename2: String,
+
+    /// Output filename
+    #[arg(short, long, value_name = "OUTPUT", default_value = "-")]
+    outfile: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct MergeArgs {
+    /// Replicate filenames, which may differ only in their replicate
+    /// index and MDRepo ID
+    #[arg(value_name = "FILE", num_args = 2.., required = true)]
+    filenames: Vec<String>,
+
+    /// Output format
+    #[arg(
+        short,
+        long,
+        value_name = "FORMAT",
+        default_value = "toml",
+        value_parser(clap::value_parser!(FileFormat)),
+    )]
+    format: FileFormat,
+
+    /// Output filename
+    #[arg(short, long, value_name = "OUTPUT", default_value = "-")]
+    outfile: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct InfoArgs {
+    /// Input filename, or "-" for stdin
+    #[arg(value_name = "FILE")]
+    filename: String,
+
+    /// Output filename
+    #[arg(short, long, value_name = "OUTPUT", default_value = "-")]
+    outfile: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct StatsArgs {
+    /// Directory to search for .toml/.json files
+    #[arg(value_name = "DIR")]
+    dirname: String,
+
+    /// JSON output
+    #[arg(short, long)]
+    json: bool,
+
+    /// Skip files whose path under DIR matches this glob, e.g., "*/old/*"
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Print the files that would be read and exit
+    #[arg(long)]
+    list_inputs: bool,
+
+    /// Output filename
+    #[arg(short, long, value_name = "OUTPUT", default_value = "-")]
+    outfile: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct ScoreArgs {
+    /// Input filename, or "-" for stdin
+    #[arg(value_name = "FILE")]
+    filename: String,
+
+    /// Number of empty fields to list
+    #[arg(short, long, value_name = "NUM", default_value = "5")]
+    num_fields: usize,
+
     /// Output filename
     #[arg(short, long, value_name = "OUTPUT", default_value = "-")]
     outfile: String,
@@ -69,10 +367,18 @@ pub struct ExampleArgs {
 
 #[derive(Debug, Parser)]
 pub struct ToJsonArgs {
-    /// Input filename
+    /// Input filename, or "-" for stdin
     #[arg(value_name = "FILE")]
     filename: String,
 
+    /// Write paper volumes and numbers such as "17" as numbers
+    #[arg(long)]
+    numeric_volumes: bool,
+
+    /// Sort contributors, permissions, proteins, and papers for stable diffs
+    #[arg(long)]
+    sort: bool,
+
     /// Output filename
     #[arg(short, long, value_name = "OUTPUT", default_value = "-")]
     outfile: String,
@@ -80,10 +386,18 @@ pub struct ToJsonArgs {
 
 #[derive(Debug, Parser)]
 pub struct ToTomlArgs {
-    /// Input filename
+    /// Input filename, or "-" for stdin
     #[arg(value_name = "FILE")]
     filename: String,
 
+    /// Write paper volumes and numbers such as "17" as numbers
+    #[arg(long)]
+    numeric_volumes: bool,
+
+    /// Sort contributors, permissions, proteins, and papers for stable diffs
+    #[arg(long)]
+    sort: bool,
+
     /// Output filename
     #[arg(short, long, value_name = "OUTPUT", default_value = "-")]
     outfile: String,
@@ -92,13 +406,81 @@ pub struct ToTomlArgs {
 #[derive(Debug, Parser)]
 /// Check MDRepo metadata TOML
 pub struct CheckArgs {
-    /// Input filename
+    /// Input filename, "-" for stdin, or a directory to check every
+    /// .toml/.json file within
     #[arg(value_name = "FILE")]
     filename: String,
 
-    /// JSON output
-    #[arg(short, long)]
+    /// JSON output, the same as "--format json"
+    #[arg(short, long, conflicts_with = "format")]
     json: bool,
+
+    /// Output format
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = "text",
+        value_parser(clap::value_parser!(CheckFormat)),
+    )]
+    format: CheckFormat,
+
+    /// Validation config file (TOML)
+    #[arg(short, long, value_name = "CONFIG")]
+    config: Option<String>,
+
+    /// Sections that must be present, added to the config's
+    #[arg(short, long, value_name = "SECTION", value_delimiter = ',')]
+    require: Vec<String>,
+
+    /// List referenced PDB/UniProt IDs, DOIs, and ORCIDs instead of errors
+    #[arg(long)]
+    emit_ids: bool,
+
+    /// Require explicit temperature and timestep units
+    #[arg(long)]
+    require_units: bool,
+
+    /// Lowest accepted temperature in Kelvin [default: 273]
+    #[arg(long, value_name = "KELVIN")]
+    temp_min: Option<f64>,
+
+    /// Highest accepted temperature in Kelvin [default: 374]
+    #[arg(long, value_name = "KELVIN")]
+    temp_max: Option<f64>,
+
+    /// Treat warnings as failures
+    #[arg(long)]
+    strict: bool,
+
+    /// Apply safe auto-fixes and write the corrected file here
+    #[arg(long, value_name = "FILE")]
+    fix_output: Option<String>,
+
+    /// Read one JSON record per line, assumed for a .ndjson file
+    #[arg(long)]
+    ndjson: bool,
+
+    /// Report unknown fields as warnings instead of failing to parse
+    #[arg(long)]
+    lenient: bool,
+
+    /// For a directory, skip files whose path under it matches this glob
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Print the files that would be checked and exit
+    #[arg(long)]
+    list_inputs: bool,
+}
+
+impl CheckArgs {
+    fn output_format(&self) -> CheckFormat {
+        if self.json {
+            CheckFormat::Json
+        } else {
+            self.format.clone()
+        }
+    }
 }
 
 // --------------------------------------------------
@@ -114,60 +496,885 @@ fn run(args: Cli) -> Result<()> {
     match &args.command {
         Some(Command::Example(args)) => {
             let mut out_file = open_outfile(&args.outfile)?;
-            let meta = Meta::example();
-            write!(
-                out_file,
-                "{}",
-                if args.format == FileFormat::Json {
-                    meta.to_json()?
-                } else {
-                    meta.to_toml()?
+            let example = match (&args.version, &args.format, &args.order) {
+                (SchemaVersion::V1, format, KeyOrder::Alpha) => {
+                    sort_keys(&Meta::example().to_json()?, format)?
                 }
-            )?;
+                (SchemaVersion::V2, format, KeyOrder::Alpha) => {
+                    sort_keys(&MetaV2::example().to_json()?, format)?
+                }
+                (SchemaVersion::V1, FileFormat::Json, _) => {
+                    Meta::example().to_json()?
+                }
+                (SchemaVersion::V1, FileFormat::Toml, _) => {
+                    Meta::example().to_toml()?
+                }
+                (SchemaVersion::V2, FileFormat::Json, _) => {
+                    MetaV2::example().to_json()?
+                }
+                (SchemaVersion::V2, FileFormat::Toml, _) => {
+                    MetaV2::example().to_toml()?
+                }
+            };
+            write!(out_file, "{example}")?;
         }
-        Some(Command::ToJson(args)) => {
+        Some(Command::Schema(args)) => {
             let mut out_file = open_outfile(&args.outfile)?;
+            let schema = match args.version {
+                SchemaVersion::V1 => Meta::json_schema(),
+                SchemaVersion::V2 => MetaV2::json_schema(),
+            };
+            writeln!(out_file, "{}", serde_json::to_string_pretty(&schema)?)?;
+        }
+        Some(Command::Cite(args)) => {
+            let mut out_file = open_outfile(&args.outfile)?;
+            let meta = parse_file(&args.filename)?;
+            let citations = match args.format {
+                CiteFormat::Bibtex => meta.papers_to_bibtex(),
+                CiteFormat::Ris => meta.papers_to_ris(),
+            };
+            write!(out_file, "{citations}")?;
+        }
+        Some(Command::Convert(args)) => {
             let meta = parse_file(&args.filename)?;
+            let found = match meta {
+                AnyMeta::V1(_) => SchemaVersion::V1,
+                AnyMeta::V2(_) => SchemaVersion::V2,
+            };
+            if let Some(from) = &args.from
+                && *from != found
+            {
+                bail!("{}: expected {from:?} but found {found:?}", args.filename);
+            }
+
+            let mut converted = match (meta, &args.to) {
+                (AnyMeta::V1(meta), SchemaVersion::V2) => AnyMeta::V2(meta.to_v2()?),
+                (AnyMeta::V2(meta), SchemaVersion::V1) => AnyMeta::V1(meta.to_v1()),
+                (meta, _) => meta,
+            };
+            if args.sort {
+                converted.canonicalize();
+            }
+            let contents = match args.format {
+                FileFormat::Json => converted.to_json()?,
+                FileFormat::Toml => converted.to_toml()?,
+            };
+            write!(open_outfile(&args.outfile)?, "{contents}")?;
+        }
+        Some(Command::Diff(args)) => {
+            // Both are canonicalized on reading, so formatting doesn't show
+            let meta1 = parse_file(&args.filename1)?;
+            let changes = meta1.diff(&parse_file(&args.filename2)?)?;
+
+            let mut out_file = open_outfile(&args.outfile)?;
+            if changes.is_empty() {
+                writeln!(out_file, "No differences")?;
+            }
+            for change in changes {
+                writeln!(out_file, "{change}")?;
+            }
+        }
+        Some(Command::Merge(args)) => {
+            let merged = merge_replicates(&args.filenames)?;
+            let contents = match args.format {
+                FileFormat::Json => merged.to_json()?,
+                FileFormat::Toml => merged.to_toml()?,
+            };
+            write!(open_outfile(&args.outfile)?, "{contents}")?;
+        }
+        Some(Command::Score(args)) => {
+            let meta = match parse_file(&args.filename)? {
+                AnyMeta::V1(meta) => meta,
+                AnyMeta::V2(meta) => meta.to_v1(),
+            };
+            let mut out_file = open_outfile(&args.outfile)?;
+            writeln!(out_file, "Completeness: {:.0}%", meta.completeness() * 100.)?;
+
+            let empty = meta.empty_fields();
+            if !empty.is_empty() {
+                writeln!(out_file, "Most impactful empty fields:")?;
+                for (field, weight) in empty.iter().take(args.num_fields) {
+                    writeln!(out_file, "  {field} (weight {weight})")?;
+                }
+            }
+        }
+        Some(Command::Info(args)) => {
+            let meta = match parse_file(&args.filename)? {
+                AnyMeta::V1(meta) => meta,
+                AnyMeta::V2(meta) => meta.to_v1(),
+            };
+            writeln!(open_outfile(&args.outfile)?, "{}", meta.summary())?;
+        }
+        Some(Command::Stats(args)) => {
+            stats(args)?;
+        }
+        Some(Command::ToJson(args)) => {
+            let mut out_file = open_outfile(&args.outfile)?;
+            let mut meta = parse_file(&args.filename)?;
+            if args.numeric_volumes {
+                meta.numeric_volumes();
+            }
+            if args.sort {
+                meta.canonicalize();
+            }
             write!(out_file, "{}", meta.to_json()?)?;
         }
         Some(Command::ToToml(args)) => {
             let mut out_file = open_outfile(&args.outfile)?;
-            let meta = parse_file(&args.filename)?;
+            let mut meta = parse_file(&args.filename)?;
+            if args.numeric_volumes {
+                meta.numeric_volumes();
+            }
+            if args.sort {
+                meta.canonicalize();
+            }
             write!(out_file, "{}", meta.to_toml()?)?;
         }
+        Some(Command::Check(args)) if args.list_inputs => {
+            if Path::new(&args.filename).is_dir() {
+                for filename in meta_files(&args.filename, &args.exclude)? {
+                    println!("{}", filename.display());
+                }
+            } else {
+                println!("{}", args.filename);
+            }
+        }
+        Some(Command::Check(args)) if Path::new(&args.filename).is_dir() => {
+            check_dir(args)?;
+        }
+        Some(Command::Check(args))
+            if args.ndjson || args.filename.ends_with(".ndjson") =>
+        {
+            check_ndjson(args)?;
+        }
         Some(Command::Check(args)) => {
-            let meta = parse_file(&args.filename)?;
-            let errors = meta.find_errors();
-            if errors.is_empty() {
-                println!("No errors");
-            } else if args.json {
-                let mut json_errors = MultiMap::new();
-                for (field, msg) in &errors {
-                    json_errors.insert(field, msg)
+            let (mut meta, canon_log, unknown) =
+                read_checked(&args.filename, args.lenient)?;
+            let format = args.output_format();
+            if args.emit_ids {
+                let ids = meta.external_ids();
+                if format == CheckFormat::Json {
+                    println!("{}", serde_json::to_string_pretty(&ids)?);
+                } else {
+                    for (id_type, vals) in [
+                        ("PDB", &ids.pdb),
+                        ("UniProt", &ids.uniprot),
+                        ("DOI", &ids.doi),
+                        ("ORCID", &ids.orcid),
+                    ] {
+                        if !vals.is_empty() {
+                            println!("{id_type}: {}", vals.join(", "));
+                        }
+                    }
                 }
-                println!("{}", serde_json::to_string_pretty(&json_errors).unwrap())
+                return Ok(());
+            }
+
+            let config = validation_config(args)?;
+            if let Some(fix_output) = &args.fix_output {
+                let fixed = meta.auto_fix();
+                let contents = if fix_output.ends_with(".json") {
+                    meta.to_json()?
+                } else {
+                    meta.to_toml()?
+                };
+                write!(open_outfile(fix_output)?, "{contents}")?;
+                if format == CheckFormat::Text && !fixed.is_empty() {
+                    let num_fixed = fixed.len();
+                    println!(
+                        "Fixed {num_fixed} issue{}:\n{}",
+                        if num_fixed == 1 { "" } else { "s" },
+                        fixed
+                            .iter()
+                            .map(|finding| finding.to_string())
+                            .collect::<Vec<String>>()
+                            .join("\n")
+                    );
+                }
+            }
+
+            let mut findings = meta.find_errors_with_config(&config);
+            findings.extend(check_filename_id(&args.filename, meta.mdrepo_id()));
+            findings.extend(unknown);
+            let (warnings, errors): (Vec<_>, Vec<_>) =
+                findings.iter().partition(|finding| finding.is_warning());
+            if format == CheckFormat::Json {
+                let report = serde_json::json!({
+                    "findings": findings,
+                    "canon_log": canon_log,
+                });
+                println!("{}", serde_json::to_string_pretty(&report)?)
+            } else if format == CheckFormat::Sarif {
+                let results = findings
+                    .iter()
+                    .map(|finding| sarif_finding(&args.filename, None, finding))
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&sarif_log(results))?)
+            } else if findings.is_empty() {
+                println!("No errors");
             } else {
-                let num_errors = errors.len();
-                println!(
-                    "Found {num_errors} error{}:\n{}",
-                    if num_errors == 1 { "" } else { "s" },
-                    errors
+                for (label, found) in [("error", &errors), ("warning", &warnings)] {
+                    if !found.is_empty() {
+                        let num_found = found.len();
+                        println!(
+                            "Found {num_found} {label}{}:\n{}",
+                            if num_found == 1 { "" } else { "s" },
+                            found
+                                .iter()
+                                .map(|finding| finding.to_string())
+                                .collect::<Vec<String>>()
+                                .join("\n")
+                        );
+                    }
+                }
+            }
+
+            // Exit 1 on errors; warnings only fail (exit 2) with --strict
+            if !errors.is_empty() {
+                std::process::exit(1);
+            } else if args.strict && !warnings.is_empty() {
+                std::process::exit(2);
+            }
+        }
+        _ => unreachable!(),
+    };
+
+    Ok(())
+}
+
+// --------------------------------------------------
+fn validation_config(args: &CheckArgs) -> Result<ValidationConfig> {
+    let mut config = match &args.config {
+        Some(filename) => ValidationConfig::from_file(filename)
+            .map_err(|e| anyhow!("{filename}: {e}"))?,
+        _ => ValidationConfig::default(),
+    };
+    config
+        .required_sections
+        .extend(args.require.iter().cloned());
+    config.require_units |= args.require_units;
+    config.temp_min = args.temp_min.or(config.temp_min);
+    config.temp_max = args.temp_max.or(config.temp_max);
+    Ok(config)
+}
+
+// --------------------------------------------------
+// Validate every metadata file under a directory, reporting files that
+// fail to parse rather than stopping at the first
+fn check_dir(args: &CheckArgs) -> Result<()> {
+    if args.emit_ids || args.fix_output.is_some() {
+        bail!("--emit-ids and --fix-output need a single file, not a directory");
+    }
+
+    let config = validation_config(args)?;
+    let format = args.output_format();
+    let filenames = meta_files(&args.filename, &args.exclude)?;
+
+    let (mut reports, mut results) = (vec![], vec![]);
+    let (mut num_with_errors, mut num_with_warnings) = (0, 0);
+    for filename in &filenames {
+        let filename = filename.to_string_lossy();
+        match read_checked(&filename, args.lenient) {
+            Ok((meta, _, unknown)) => {
+                let mut findings = meta.find_errors_with_config(&config);
+                findings.extend(check_filename_id(&filename, meta.mdrepo_id()));
+                findings.extend(unknown);
+                let num_warnings = findings
+                    .iter()
+                    .filter(|finding| finding.is_warning())
+                    .count();
+                let num_errors = findings.len() - num_warnings;
+                if num_errors > 0 {
+                    num_with_errors += 1;
+                } else if num_warnings > 0 {
+                    num_with_warnings += 1;
+                }
+
+                if format == CheckFormat::Text {
+                    if findings.is_empty() {
+                        println!("{filename}: ok");
+                    } else {
+                        println!(
+                            "{filename}: {num_errors} error{}, {num_warnings} warning{}",
+                            if num_errors == 1 { "" } else { "s" },
+                            if num_warnings == 1 { "" } else { "s" },
+                        );
+                        for finding in &findings {
+                            println!("  {finding}");
+                        }
+                    }
+                }
+                results.extend(
+                    findings
                         .iter()
-                        .map(|(fld, msg)| format!("{fld}: {msg}"))
-                        .collect::<Vec<String>>()
-                        .join("\n")
+                        .map(|finding| sarif_finding(&filename, None, finding)),
                 );
+                reports.push(serde_json::json!({
+                    "filename": filename,
+                    "findings": findings,
+                }));
+            }
+            Err(e) => {
+                num_with_errors += 1;
+                if format == CheckFormat::Text {
+                    println!("{e}");
+                }
+                results.push(sarif_result(
+                    &filename,
+                    None,
+                    "parse_error",
+                    "error",
+                    &e.to_string(),
+                ));
+                reports.push(serde_json::json!({
+                    "filename": filename,
+                    "error": e.to_string(),
+                }));
             }
         }
-        _ => unreachable!(),
+    }
+
+    let num_files = filenames.len();
+    if format == CheckFormat::Json {
+        let report = serde_json::json!({
+            "files": reports,
+            "num_files": num_files,
+            "num_with_errors": num_with_errors,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if format == CheckFormat::Sarif {
+        println!("{}", serde_json::to_string_pretty(&sarif_log(results))?);
+    } else {
+        println!(
+            "Checked {num_files} file{}, {num_with_errors} with errors",
+            if num_files == 1 { "" } else { "s" },
+        );
+    }
+
+    // Same exit codes as a single file
+    if num_with_errors > 0 {
+        std::process::exit(1);
+    } else if args.strict && num_with_warnings > 0 {
+        std::process::exit(2);
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+// Validate each line of newline-delimited JSON as its own record. Blank
+// lines are skipped, and a line that fails to parse doesn't stop the rest.
+fn check_ndjson(args: &CheckArgs) -> Result<()> {
+    if args.emit_ids || args.fix_output.is_some() {
+        bail!("--emit-ids and --fix-output need a single record, not NDJSON");
+    }
+
+    let config = validation_config(args)?;
+    let format = args.output_format();
+    let filename = &args.filename;
+    let contents = match filename.as_str() {
+        "-" => {
+            let mut contents = String::new();
+            io::stdin().read_to_string(&mut contents)?;
+            contents
+        }
+        _ => fs::read_to_string(filename).map_err(|e| anyhow!("{filename}: {e}"))?,
     };
 
+    let (mut reports, mut results) = (vec![], vec![]);
+    let (mut num_records, mut num_with_errors, mut num_with_warnings) = (0, 0, 0);
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        num_records += 1;
+        let line_num = index + 1;
+        let parsed = if args.lenient {
+            Meta::from_string_any_lenient(line)
+                .map(|(meta, _, unknown)| (meta, unknown.warnings()))
+        } else {
+            Meta::from_string_any_with_log(line).map(|(meta, _)| (meta, vec![]))
+        };
+        match parsed {
+            Ok((meta, unknown)) => {
+                let mut findings = meta.find_errors_with_config(&config);
+                findings.extend(unknown);
+                let num_warnings = findings
+                    .iter()
+                    .filter(|finding| finding.is_warning())
+                    .count();
+                if findings.len() > num_warnings {
+                    num_with_errors += 1;
+                } else if num_warnings > 0 {
+                    num_with_warnings += 1;
+                }
+
+                if format == CheckFormat::Text {
+                    for finding in &findings {
+                        println!("{filename}:{line_num}: {finding}");
+                    }
+                }
+                results.extend(
+                    findings.iter().map(|finding| {
+                        sarif_finding(filename, Some(line_num), finding)
+                    }),
+                );
+                reports.push(serde_json::json!({
+                    "line": line_num,
+                    "findings": findings,
+                }));
+            }
+            Err(e) => {
+                num_with_errors += 1;
+                // The parser only sees the one line, so only its column is kept
+                let (location, message) = match e {
+                    MdrMetaError::Parse(err) if err.line > 0 => {
+                        (format!("{line_num}:{}", err.column), err.message)
+                    }
+                    _ => (line_num.to_string(), e.to_string()),
+                };
+                if format == CheckFormat::Text {
+                    println!("{filename}:{location}: {message}");
+                }
+                results.push(sarif_result(
+                    filename,
+                    Some(line_num),
+                    "parse_error",
+                    "error",
+                    &message,
+                ));
+                reports.push(serde_json::json!({
+                    "line": line_num,
+                    "error": message,
+                }));
+            }
+        }
+    }
+
+    if format == CheckFormat::Json {
+        let report = serde_json::json!({
+            "records": reports,
+            "num_records": num_records,
+            "num_with_errors": num_with_errors,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if format == CheckFormat::Sarif {
+        println!("{}", serde_json::to_string_pretty(&sarif_log(results))?);
+    } else {
+        println!(
+            "Checked {num_records} record{}, {num_with_errors} with errors",
+            if num_records == 1 { "" } else { "s" },
+        );
+    }
+
+    // Same exit codes as a single file
+    if num_with_errors > 0 {
+        std::process::exit(1);
+    } else if args.strict && num_with_warnings > 0 {
+        std::process::exit(2);
+    }
     Ok(())
 }
 
 // --------------------------------------------------
-fn parse_file(filename: &str) -> Result<Meta> {
-    Meta::from_file(filename).map_err(|e| anyhow!("{filename}: {e}"))
+// A SARIF 2.1.0 log for code-scanning tools such as GitHub's, with a rule
+// for each error code found
+fn sarif_log(results: Vec<Value>) -> Value {
+    let rule_ids: BTreeSet<_> = results
+        .iter()
+        .filter_map(|result| result["ruleId"].as_str())
+        .collect();
+    serde_json::json!({
+        "version": "2.1.0",
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rule_ids
+                        .iter()
+                        .map(|id| serde_json::json!({"id": id}))
+                        .collect::<Vec<_>>(),
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+// --------------------------------------------------
+// The field's JSON pointer is the logical location, and the severity is
+// already named as SARIF's levels are
+fn sarif_finding(uri: &str, line: Option<usize>, finding: &MetaError) -> Value {
+    let code = serde_json::to_value(finding.code).unwrap_or_default();
+    let severity = serde_json::to_value(finding.severity).unwrap_or_default();
+    let mut result = sarif_result(
+        uri,
+        line,
+        code.as_str().unwrap_or_default(),
+        severity.as_str().unwrap_or_default(),
+        &finding.message,
+    );
+    result["locations"][0]["logicalLocations"] = serde_json::json!([{
+        "fullyQualifiedName": finding.path,
+        "kind": "member",
+    }]);
+    result
+}
+
+// --------------------------------------------------
+// A line is only known for NDJSON records
+fn sarif_result(
+    uri: &str,
+    line: Option<usize>,
+    rule_id: &str,
+    level: &str,
+    message: &str,
+) -> Value {
+    let mut physical = serde_json::json!({"artifactLocation": {"uri": uri}});
+    if let Some(line) = line {
+        physical["region"] = serde_json::json!({"startLine": line});
+    }
+    serde_json::json!({
+        "ruleId": rule_id,
+        "level": level,
+        "message": {"text": message},
+        "locations": [{"physicalLocation": physical}],
+    })
+}
+
+// --------------------------------------------------
+// Tally the values that describe a collection of simulations. Files that
+// fail to parse are reported on STDERR and left out of the counts.
+fn stats(args: &StatsArgs) -> Result<()> {
+    let filenames = meta_files(&args.dirname, &args.exclude)?;
+    if args.list_inputs {
+        let mut out_file = open_outfile(&args.outfile)?;
+        for filename in &filenames {
+            writeln!(out_file, "{}", filename.display())?;
+        }
+        return Ok(());
+    }
+
+    let mut forcefields = BTreeMap::new();
+    let mut water_models = BTreeMap::new();
+    let mut software = BTreeMap::new();
+    let mut temperatures: BTreeMap<i64, usize> = BTreeMap::new();
+    let mut total_replicates: BTreeMap<u32, usize> = BTreeMap::new();
+    let (mut num_records, mut num_with_papers) = (0, 0);
+    for filename in &filenames {
+        let filename = filename.to_string_lossy();
+        let meta = match parse_file(&filename) {
+            Ok(AnyMeta::V1(meta)) => meta,
+            Ok(AnyMeta::V2(meta)) => meta.to_v1(),
+            Err(e) => {
+                eprintln!("{e}");
+                continue;
+            }
+        };
+
+        num_records += 1;
+        if meta
+            .papers
+            .as_ref()
+            .is_some_and(|papers| !papers.is_empty())
+        {
+            num_with_papers += 1;
+        }
+
+        *software.entry(meta.software.name.clone()).or_insert(0) += 1;
+        // Each forcefield of a combination is counted on its own
+        for (counts, names) in [
+            (
+                &mut forcefields,
+                meta.forcefield
+                    .as_ref()
+                    .and_then(|f| f.forcefield.as_deref())
+                    .unwrap_or_default(),
+            ),
+            (
+                &mut water_models,
+                meta.water
+                    .as_ref()
+                    .and_then(|w| w.model.as_ref())
+                    .map(slice::from_ref)
+                    .unwrap_or_default(),
+            ),
+        ] {
+            for name in names.iter().filter(|name| !name.trim().is_empty()) {
+                *counts.entry(name.trim().to_string()).or_insert(0) += 1;
+            }
+        }
+
+        // Binned by 10 K
+        if let Some(temp) = meta.temperature.as_ref().and_then(|t| t.temperature) {
+            let low = (temp.as_f64() / 10.).floor() as i64 * 10;
+            *temperatures.entry(low).or_insert(0) += 1;
+        }
+        if let Some(total) = meta.replicates.as_ref().and_then(|r| r.total_replicates) {
+            *total_replicates.entry(total).or_insert(0) += 1;
+        }
+    }
+
+    let mut out_file = open_outfile(&args.outfile)?;
+    if args.json {
+        let report = serde_json::json!({
+            "num_records": num_records,
+            "num_with_papers": num_with_papers,
+            "num_without_papers": num_records - num_with_papers,
+            "forcefields": forcefields,
+            "water_models": water_models,
+            "software": software,
+            "temperatures": temperatures
+                .iter()
+                .map(|(low, count)| {
+                    serde_json::json!({"min": low, "max": low + 9, "count": count})
+                })
+                .collect::<Vec<_>>(),
+            "total_replicates": total_replicates
+                .iter()
+                .map(|(total, count)| serde_json::json!({"total": total, "count": count}))
+                .collect::<Vec<_>>(),
+        });
+        writeln!(out_file, "{}", serde_json::to_string_pretty(&report)?)?;
+    } else {
+        writeln!(
+            out_file,
+            "Records: {num_records} ({num_with_papers} with papers, {} without)",
+            num_records - num_with_papers
+        )?;
+        for (title, counts) in [
+            ("Software", &software),
+            ("Forcefields", &forcefields),
+            ("Water models", &water_models),
+        ] {
+            // Most common first, with ties in alphabetical order
+            let mut rows: Vec<_> =
+                counts.iter().map(|(k, v)| (k.clone(), *v)).collect();
+            rows.sort_by_key(|(_, count)| Reverse(*count));
+            write_counts(&mut out_file, title, &rows)?;
+        }
+        // Histograms keep the order of their bins, e.g., "300-309"
+        let rows: Vec<_> = temperatures
+            .iter()
+            .map(|(low, count)| (format!("{low}-{}", low + 9), *count))
+            .collect();
+        write_counts(&mut out_file, "Temperatures (K)", &rows)?;
+        let rows: Vec<_> = total_replicates
+            .iter()
+            .map(|(total, count)| (total.to_string(), *count))
+            .collect();
+        write_counts(&mut out_file, "Total replicates", &rows)?;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+fn write_counts(
+    out_file: &mut Box<dyn Write>,
+    title: &str,
+    rows: &[(String, usize)],
+) -> Result<()> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let width = rows.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+    writeln!(out_file, "\n{title}:")?;
+    for (key, count) in rows {
+        writeln!(out_file, "  {key:<width$}  {count}")?;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+// The .toml/.json files under a directory in sorted order, leaving out those
+// whose path relative to it matches an exclude glob
+fn meta_files(dirname: &str, exclude: &[String]) -> Result<Vec<PathBuf>> {
+    let patterns = exclude
+        .iter()
+        .map(|glob| Pattern::new(glob).map_err(|e| anyhow!(r#""{glob}": {e}"#)))
+        .collect::<Result<Vec<_>>>()?;
+    let dir = Path::new(dirname);
+    let mut filenames = vec![];
+    find_meta_files(dir, &mut filenames)?;
+    filenames.retain(|path| {
+        let relative = path.strip_prefix(dir).unwrap_or(path);
+        !patterns
+            .iter()
+            .any(|pattern| pattern.matches_path(relative))
+    });
+    filenames.sort();
+    Ok(filenames)
+}
+
+// --------------------------------------------------
+// Symlinked directories are skipped, as one may link back to an ancestor
+fn find_meta_files(dir: &Path, filenames: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir).map_err(|e| anyhow!("{}: {e}", dir.display()))? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            find_meta_files(&path, filenames)?;
+        } else if path.is_file()
+            && path
+                .extension()
+                .is_some_and(|ext| ext == "toml" || ext == "json")
+        {
+            filenames.push(path);
+        }
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+// Replicates may differ only in their index and MDRepo ID. The merged record
+// has no ID and lists the indices in place of a single one.
+fn merge_replicates(filenames: &[String]) -> Result<AnyMeta> {
+    let mut merged: Option<AnyMeta> = None;
+    let mut replicates = BTreeSet::new();
+    for filename in filenames {
+        let meta = match (&merged, parse_file(filename)?) {
+            (Some(AnyMeta::V1(_)), AnyMeta::V2(meta)) => AnyMeta::V1(meta.to_v1()),
+            (Some(AnyMeta::V2(_)), AnyMeta::V1(meta)) => AnyMeta::V2(meta.to_v2()?),
+            (_, meta) => meta,
+        };
+        let (shared, indices) = split_replicates(meta);
+        replicates.extend(indices);
+
+        match &merged {
+            Some(merged) => {
+                let changes = merged.diff(&shared)?;
+                if !changes.is_empty() {
+                    bail!(
+                        "{filename}: differs from {}:\n{}",
+                        filenames[0],
+                        changes
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    );
+                }
+            }
+            _ => merged = Some(shared),
+        }
+    }
+
+    let mut merged = merged.ok_or_else(|| anyhow!("No files to merge"))?;
+    let replicates = (!replicates.is_empty()).then(|| replicates.into_iter().collect());
+    match &mut merged {
+        AnyMeta::V1(meta) => {
+            if let Some(rep) = meta.replicates.as_mut() {
+                rep.merged_replicates = replicates;
+            }
+        }
+        AnyMeta::V2(meta) => meta.merged_replicate_ids = replicates,
+    }
+    Ok(merged)
+}
+
+// --------------------------------------------------
+// Remove the fields each replicate may have its own value for, returning
+// the replicate indices
+fn split_replicates(meta: AnyMeta) -> (AnyMeta, Vec<u32>) {
+    match meta {
+        AnyMeta::V1(mut meta) => {
+            meta.mdrepo_id = None;
+            let mut indices = vec![];
+            if let Some(rep) = meta.replicates.as_mut() {
+                indices.extend(rep.replicate.take());
+                indices.extend(rep.merged_replicates.take().into_iter().flatten());
+            }
+            (AnyMeta::V1(meta), indices)
+        }
+        AnyMeta::V2(mut meta) => {
+            meta.mdrepo_id = None;
+            let mut indices: Vec<_> = meta.replicate_id.take().into_iter().collect();
+            indices.extend(meta.merged_replicate_ids.take().into_iter().flatten());
+            (AnyMeta::V2(meta), indices)
+        }
+    }
+}
+
+// --------------------------------------------------
+fn parse_file(filename: &str) -> Result<AnyMeta> {
+    read_input(filename).map(|(meta, _)| meta)
+}
+
+// --------------------------------------------------
+// A filename of "-" reads stdin, sniffing JSON vs TOML from the contents
+fn read_input(filename: &str) -> Result<(AnyMeta, Vec<CanonChange>)> {
+    let result = match filename {
+        "-" => {
+            let mut contents = String::new();
+            io::stdin().read_to_string(&mut contents)?;
+            Meta::from_string_any_with_log(&contents)
+        }
+        _ => Meta::from_file_any_with_log(filename),
+    };
+    result.map_err(|e| input_error(filename, e))
+}
+
+// --------------------------------------------------
+// Lenient reading sets aside unknown fields, returned as warnings
+fn read_checked(
+    filename: &str,
+    lenient: bool,
+) -> Result<(AnyMeta, Vec<CanonChange>, Vec<MetaError>)> {
+    if !lenient {
+        return read_input(filename).map(|(meta, canon_log)| (meta, canon_log, vec![]));
+    }
+
+    let result = match filename {
+        "-" => {
+            let mut contents = String::new();
+            io::stdin().read_to_string(&mut contents)?;
+            Meta::from_string_any_lenient(&contents)
+        }
+        _ => Meta::from_file_any_lenient(filename),
+    };
+    result
+        .map(|(meta, canon_log, unknown)| (meta, canon_log, unknown.warnings()))
+        .map_err(|e| input_error(filename, e))
+}
+
+// --------------------------------------------------
+fn input_error(filename: &str, e: MdrMetaError) -> anyhow::Error {
+    match e {
+        MdrMetaError::Parse(err) if err.line > 0 => anyhow!("{filename}:{err}"),
+        MdrMetaError::Empty if filename == "-" => anyhow!("-: Input is empty"),
+        _ => anyhow!("{filename}: {e}"),
+    }
+}
+
+// --------------------------------------------------
+// A file copied from another record often keeps its old ID, so the ID
+// should match the filename, e.g., "MDR_00004423.toml"
+fn check_filename_id(filename: &str, mdrepo_id: Option<&str>) -> Option<MetaError> {
+    let mdrepo_id = mdrepo_id?;
+    let name = Path::new(filename).file_name()?.to_str()?;
+    let name = name.strip_suffix(".gz").unwrap_or(name);
+    let stem = Path::new(name).file_stem()?.to_str()?;
+    (filename != "-" && stem != mdrepo_id).then(|| {
+        MetaError::warning(
+            "/mdrepo_id",
+            ErrorCode::MdrepoIdMismatch,
+            format!(r#""{mdrepo_id}" does not match the filename "{stem}""#),
+        )
+    })
+}
+
+// --------------------------------------------------
+// serde_json objects are ordered by key, so a trip through a Value
+// alphabetizes the keys at every level
+fn sort_keys(json: &str, format: &FileFormat) -> Result<String> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    match format {
+        FileFormat::Json => Ok(serde_json::to_string_pretty(&value)?),
+        FileFormat::Toml => Ok(toml::to_string_pretty(&value)?),
+    }
 }
 
 // --------------------------------------------------
@@ -4,6 +4,7 @@ use predicates::prelude::*;
 //use pretty_assertions::assert_eq;
 use rand::distr::{Alphanumeric, SampleString};
 use std::fs;
+use tempfile::{tempdir, TempDir};
 
 const PRG: &str = "mdr-meta";
 const TRUNCATED_TOML: &str = "../tests/inputs/truncated.toml";
@@ -11,6 +12,13 @@ const TRUNCATED_JSON: &str = "../tests/inputs/truncated.json";
 const EMPTY: &str = "../tests/inputs/empty";
 const EMPTY_TOML: &str = "../tests/inputs/empty.toml";
 const EMPTY_JSON: &str = "../tests/inputs/empty.json";
+const EXAMPLE_TOML: &str = "../tests/inputs/example.toml";
+const EXAMPLE_V2_TOML: &str = "../tests/inputs/example_v2.toml";
+const EXAMPLE_FIXABLE_TOML: &str = "../tests/inputs/example_fixable.toml";
+const EXAMPLE_ERRORS_TOML: &str = "../tests/inputs/example_with_errors.toml";
+const EXAMPLE_WARNINGS_TOML: &str = "../tests/inputs/example_warnings.toml";
+const MDR4423_TOML: &str = "../tests/inputs/MDR_00004423.toml";
+const EXAMPLE_NDJSON: &str = "../tests/inputs/example.ndjson";
 
 // --------------------------------------------------
 fn gen_bad_file() -> String {
@@ -24,6 +32,12 @@ fn gen_bad_file() -> String {
     }
 }
 
+// --------------------------------------------------
+// A path in a directory that is removed when the test ends, pass or fail
+fn temp_path(dir: &TempDir, name: &str) -> String {
+    dir.path().join(name).to_string_lossy().to_string()
+}
+
 // --------------------------------------------------
 #[test]
 fn dies_no_args() -> Result<()> {
@@ -49,7 +63,7 @@ fn dies_bad_file() -> Result<()> {
 #[test]
 fn dies_no_file_extension() -> Result<()> {
     Command::cargo_bin(PRG)?
-        .args(&["check", EMPTY])
+        .args(["check", EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains("No file extension"));
@@ -60,7 +74,7 @@ fn dies_no_file_extension() -> Result<()> {
 #[test]
 fn dies_empty_json() -> Result<()> {
     Command::cargo_bin(PRG)?
-        .args(&["check", EMPTY_JSON])
+        .args(["check", EMPTY_JSON])
         .assert()
         .failure()
         .stderr(predicate::str::contains("File is empty"));
@@ -71,7 +85,7 @@ fn dies_empty_json() -> Result<()> {
 #[test]
 fn dies_empty_toml() -> Result<()> {
     Command::cargo_bin(PRG)?
-        .args(&["check", EMPTY_TOML])
+        .args(["check", EMPTY_TOML])
         .assert()
         .failure()
         .stderr(predicate::str::contains("File is empty"));
@@ -82,10 +96,12 @@ fn dies_empty_toml() -> Result<()> {
 #[test]
 fn dies_trucated_toml() -> Result<()> {
     Command::cargo_bin(PRG)?
-        .args(&["check", TRUNCATED_TOML])
+        .args(["check", TRUNCATED_TOML])
         .assert()
         .failure()
-        .stderr(predicate::str::contains("TOML parse error"));
+        .stderr(predicate::str::starts_with(format!(
+            "{TRUNCATED_TOML}:5:43: invalid basic string"
+        )));
     Ok(())
 }
 
@@ -93,9 +109,815 @@ fn dies_trucated_toml() -> Result<()> {
 #[test]
 fn dies_truncated_json() -> Result<()> {
     Command::cargo_bin(PRG)?
-        .args(&["check", TRUNCATED_JSON])
+        .args(["check", TRUNCATED_JSON])
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with(format!(
+            "{TRUNCATED_JSON}:4:62: EOF while parsing a string"
+        )));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn example_v2() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["example", "--version", "v2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("water_density_kg_m3 = "))
+        .stdout(predicate::str::contains("[initial]").not());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn example_alpha_order() -> Result<()> {
+    for (format, additional_files, initial) in [
+        ("toml", "[[additional_files]]", "[initial]"),
+        ("json", r#""additional_files""#, r#""initial""#),
+    ] {
+        let output = Command::cargo_bin(PRG)?
+            .args(["example", "--order", "alpha", "--format", format])
+            .output()?;
+        assert!(output.status.success());
+
+        let stdout = String::from_utf8(output.stdout)?;
+        let additional_files_pos = stdout.find(additional_files).unwrap();
+        let initial_pos = stdout.find(initial).unwrap();
+        assert!(additional_files_pos < initial_pos);
+    }
+
+    // Declared order is the default
+    let output = Command::cargo_bin(PRG)?.args(["example"]).output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.starts_with("[initial]"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn check_require_papers() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["check", "--require", "proteins,papers", MDR4423_TOML])
+        .assert()
+        .stdout(predicate::str::contains(
+            "papers: required section is missing",
+        ))
+        .stdout(predicate::str::contains("proteins:").not());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn check_detects_v2() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["check", EXAMPLE_V2_TOML])
+        .assert()
+        .success()
+        .stdout("No errors\n");
+
+    // Dates are canonicalized as for V1, whether bare TOML dates or free-form
+    let dir = tempdir()?;
+    for date in ["2000-01-01", r#""Jan 1, 2000""#] {
+        let changed = temp_path(&dir, "changed.toml");
+        fs::write(
+            &changed,
+            fs::read_to_string(EXAMPLE_V2_TOML)?
+                .replace(r#"date = "2000-01-01""#, &format!("date = {date}")),
+        )?;
+        Command::cargo_bin(PRG)?
+            .args(["check", &changed])
+            .assert()
+            .success()
+            .stdout("No errors\n");
+        Command::cargo_bin(PRG)?
+            .args(["convert", "--to", "v1", &changed])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(r#"date = "2000-01-01""#));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn check_emit_ids() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["check", "--emit-ids", EXAMPLE_V2_TOML])
+        .assert()
+        .success()
+        .stdout(
+            "PDB: 7QXR\n\
+            UniProt: A7M120\n\
+            DOI: 10.1038/x41594-020-0884-y, 10.1038/nature12572\n\
+            ORCID: 0000-0000-0000-000X, 0000-0000-0000-001X, 0000-0000-0000-002X, 0000-0000-0000-003X\n",
+        );
+
+    Command::cargo_bin(PRG)?
+        .args(["check", "--emit-ids", "--json", EXAMPLE_V2_TOML])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""pdb": ["#));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn check_json_errors() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["check", "--json", "--require", "papers", MDR4423_TOML])
+        .assert()
+        .stdout(predicate::str::contains(r#""path": "/papers""#))
+        .stdout(predicate::str::contains(r#""severity": "error""#))
+        .stdout(predicate::str::contains(r#""code": "missing_section""#));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn check_warnings_pass_unless_strict() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["check", EXAMPLE_WARNINGS_TOML])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Found 2 warnings:\n"))
+        .stdout(predicate::str::contains(
+            "/water/model: should not be present if water.is_present is false",
+        ));
+
+    Command::cargo_bin(PRG)?
+        .args(["check", "--strict", EXAMPLE_WARNINGS_TOML])
+        .assert()
+        .code(2);
+
+    Command::cargo_bin(PRG)?
+        .args(["check", "--strict", EXAMPLE_V2_TOML])
+        .assert()
+        .success();
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn check_fix_output() -> Result<()> {
+    let dir = tempdir()?;
+    let outfile = temp_path(&dir, "fixed.toml");
+    Command::cargo_bin(PRG)?
+        .args(["check", "--fix-output", &outfile, EXAMPLE_FIXABLE_TOML])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::starts_with("Fixed 2 issues:\n"))
+        .stdout(predicate::str::contains(
+            r#"/temperature/temperature: "500" must be in the range 273-374"#,
+        ))
+        .stdout(predicate::str::contains("Found 1 error:\n"))
+        .stdout(predicate::str::contains("warning").not());
+
+    let fixed = fs::read_to_string(&outfile)?;
+    assert!(fixed.contains("is_present = false"));
+    assert!(!fixed.contains("TIP3P"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn check_fails_on_bad_orcid() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["check", EXAMPLE_ERRORS_TOML])
+        .assert()
+        .failure()
+        .stdout(predicate::str::starts_with("Found 8 errors:\n"))
+        .stdout(predicate::str::contains(
+            r#"/initial/lead_contributor_orcid: invalid ORCID "0000-0000-0000-00X""#,
+        ))
+        .stdout(predicate::str::contains(
+            "/simulation_permissions/0/can_view: must be true when can_edit is true",
+        ));
+
+    Command::cargo_bin(PRG)?
+        .args(["check", "--json", EXAMPLE_ERRORS_TOML])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(r#""code": "invalid_orcid""#));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn check_require_units() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["check", "--require-units", EXAMPLE_WARNINGS_TOML])
+        .assert()
+        .success();
+
+    Command::cargo_bin(PRG)?
+        .args(["check", "--require-units", MDR4423_TOML])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "/temperature/temperature_units: required when temperature is given",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn check_temp_range() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["check", "--temp-min", "280", EXAMPLE_TOML])
         .assert()
         .failure()
-        .stderr(predicate::str::contains("EOF while parsing a string"));
+        .stdout(predicate::str::contains(
+            r#"/temperature/temperature: "273" must be in the range 280-374"#,
+        ));
+
+    Command::cargo_bin(PRG)?
+        .args([
+            "check",
+            "--temp-min",
+            "200",
+            "--temp-max",
+            "280",
+            EXAMPLE_TOML,
+        ])
+        .assert()
+        .success();
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn check_json_canon_log() -> Result<()> {
+    let output = Command::cargo_bin(PRG)?
+        .args(["check", "--json", EXAMPLE_TOML])
+        .output()?;
+    assert!(output.status.success());
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(report["findings"], serde_json::json!([]));
+    assert_eq!(
+        report["canon_log"],
+        serde_json::json!([{
+            "path": "/initial/date",
+            "from": "Feb 5, 2000",
+            "to": "2000-02-05",
+        }])
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn to_toml_sort() -> Result<()> {
+    let dir = tempdir()?;
+    let filename = temp_path(&dir, "unsorted.toml");
+    fs::write(
+        &filename,
+        fs::read_to_string(EXAMPLE_TOML)?
+            .replace(r#"name = "Contributor1""#, r#"name = "Contributor3""#),
+    )?;
+    let unsorted = Command::cargo_bin(PRG)?
+        .args(["to-toml", &filename])
+        .output()?;
+    let sorted = Command::cargo_bin(PRG)?
+        .args(["to-toml", "--sort", &filename])
+        .output()?;
+
+    let pos = |stdout: &[u8], name: &str| {
+        String::from_utf8_lossy(stdout).find(name).expect(name)
+    };
+    assert!(
+        pos(&unsorted.stdout, "Contributor3") < pos(&unsorted.stdout, "Contributor2")
+    );
+    assert!(pos(&sorted.stdout, "Contributor3") > pos(&sorted.stdout, "Contributor2"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn check_lenient() -> Result<()> {
+    let dir = tempdir()?;
+    let filename = temp_path(&dir, "unknown_field.toml");
+    fs::write(
+        &filename,
+        fs::read_to_string(EXAMPLE_TOML)?.replacen(
+            "[initial]\n",
+            "[initial]\nexperimental_flag = true\n",
+            1,
+        ),
+    )?;
+    let strict = Command::cargo_bin(PRG)?
+        .args(["check", &filename])
+        .output()?;
+    let lenient = Command::cargo_bin(PRG)?
+        .args(["check", "--lenient", &filename])
+        .output()?;
+
+    assert!(!strict.status.success());
+    assert!(
+        String::from_utf8(strict.stderr)?.contains("unknown field `experimental_flag`")
+    );
+    assert!(lenient.status.success());
+    assert_eq!(
+        String::from_utf8(lenient.stdout)?,
+        "Found 1 warning:\n/initial/experimental_flag: unrecognized field\n"
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn check_sarif() -> Result<()> {
+    let output = Command::cargo_bin(PRG)?
+        .args(["check", "--format", "sarif", EXAMPLE_WARNINGS_TOML])
+        .output()?;
+    assert!(output.status.success());
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(report["version"], "2.1.0");
+    let run = &report["runs"][0];
+    assert_eq!(
+        run["tool"]["driver"]["rules"],
+        serde_json::json!([{"id": "water_inconsistent"}])
+    );
+    assert_eq!(
+        run["results"][0],
+        serde_json::json!({
+            "ruleId": "water_inconsistent",
+            "level": "warning",
+            "message": {
+                "text": "should not be present if water.is_present is false"
+            },
+            "locations": [{
+                "physicalLocation": {
+                    "artifactLocation": {"uri": EXAMPLE_WARNINGS_TOML},
+                },
+                "logicalLocations": [{
+                    "fullyQualifiedName": "/water/model",
+                    "kind": "member",
+                }],
+            }],
+        })
+    );
+
+    // NDJSON records are located by line, including those that don't parse
+    let output = Command::cargo_bin(PRG)?
+        .args(["check", "--format", "sarif", EXAMPLE_NDJSON])
+        .output()?;
+    assert_eq!(output.status.code(), Some(1));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let results = &report["runs"][0]["results"];
+    assert_eq!(results[0]["ruleId"], "parse_error");
+    assert_eq!(
+        results[0]["locations"][0]["physicalLocation"]["region"]["startLine"],
+        3
+    );
+    assert_eq!(results[1]["ruleId"], "invalid_mdrepo_id");
+    assert_eq!(results[1]["level"], "error");
+
+    // --json is shorthand for one format, so it can't be given with another
+    Command::cargo_bin(PRG)?
+        .args([
+            "check",
+            "--json",
+            "--format",
+            "sarif",
+            EXAMPLE_WARNINGS_TOML,
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn schema_v2() -> Result<()> {
+    let output = Command::cargo_bin(PRG)?
+        .args(["schema", "--version", "v2"])
+        .output()?;
+    assert!(output.status.success());
+
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(schema["title"], "MetaV2");
+    assert_eq!(schema["properties"]["temperature_kelvin"]["minimum"], 273);
+    assert_eq!(schema["properties"]["temperature_kelvin"]["maximum"], 374);
+    assert!(schema["properties"]["lead_contributor_orcid"]["pattern"].is_string());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn cite_bibtex() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["cite", EXAMPLE_V2_TOML, "--format", "bibtex"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("@article{10.1038/nature12572,"))
+        .stdout(predicate::str::contains("  pages = {575--579},"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn cite_ris() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["cite", EXAMPLE_TOML, "--format", "ris"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "AU  - Tielmann, J.S.\nJO  - Nat Methods\n",
+        ))
+        .stdout(predicate::str::contains("SP  - 575\nEP  - 579\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn check_stdin() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["check", "-"])
+        .write_stdin(fs::read_to_string(EXAMPLE_TOML)?)
+        .assert()
+        .success()
+        .stdout("No errors\n");
+
+    Command::cargo_bin(PRG)?
+        .args(["to-json", "-"])
+        .write_stdin(fs::read_to_string(EXAMPLE_V2_TOML)?)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""lead_contributor_orcid""#));
+
+    Command::cargo_bin(PRG)?
+        .args(["check", "-"])
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr("-: Input is empty\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn check_gzip() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["check", "../tests/inputs/example.toml.gz"])
+        .assert()
+        .success()
+        .stdout("No errors\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn check_mdrepo_id_matches_filename() -> Result<()> {
+    let dir = tempdir()?;
+    let filename = temp_path(&dir, "MDR_00000001.toml");
+    fs::write(
+        &filename,
+        format!(
+            "mdrepo_id = \"MDR_00004423\"\n{}",
+            fs::read_to_string(EXAMPLE_TOML)?
+        ),
+    )?;
+    let output = Command::cargo_bin(PRG)?
+        .args(["check", &filename])
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "Found 1 warning:\n/mdrepo_id: \"MDR_00004423\" does not match the \
+        filename \"MDR_00000001\"\n"
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn check_dir() -> Result<()> {
+    let tmp = tempdir()?;
+    let dir = temp_path(&tmp, "records");
+    fs::create_dir_all(format!("{dir}/sub"))?;
+    fs::copy(EXAMPLE_TOML, format!("{dir}/example.toml"))?;
+    fs::copy(
+        EXAMPLE_ERRORS_TOML,
+        format!("{dir}/example_with_errors.toml"),
+    )?;
+    fs::copy(TRUNCATED_TOML, format!("{dir}/sub/truncated.toml"))?;
+    fs::write(format!("{dir}/notes.txt"), "not metadata")?;
+
+    let output = Command::cargo_bin(PRG)?.args(["check", &dir]).output()?;
+    assert_eq!(output.status.code(), Some(1));
+
+    // Every file is reported despite the parse failure
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains(&format!("{dir}/example.toml: ok\n")));
+    assert!(stdout.contains(&format!(
+        "{dir}/example_with_errors.toml: 8 errors, 4 warnings\n"
+    )));
+    assert!(stdout.contains(&format!(
+        "{dir}/sub/truncated.toml:5:43: invalid basic string"
+    )));
+    assert!(stdout.ends_with("Checked 3 files, 2 with errors\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[cfg(unix)]
+#[test]
+fn check_dir_skips_symlink_cycles() -> Result<()> {
+    let tmp = tempdir()?;
+    let dir = temp_path(&tmp, "records");
+    fs::create_dir_all(format!("{dir}/sub"))?;
+    fs::copy(EXAMPLE_TOML, format!("{dir}/sub/example.toml"))?;
+    std::os::unix::fs::symlink(&dir, format!("{dir}/sub/loop"))?;
+
+    let output = Command::cargo_bin(PRG)?.args(["check", &dir]).output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert_eq!(
+        stdout,
+        format!("{dir}/sub/example.toml: ok\nChecked 1 file, 0 with errors\n")
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn check_ndjson() -> Result<()> {
+    let output = Command::cargo_bin(PRG)?
+        .args(["check", EXAMPLE_NDJSON])
+        .output()?;
+    assert_eq!(output.status.code(), Some(1));
+
+    // The blank line is skipped and the truncated line doesn't stop the last
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        format!(
+            concat!(
+                "{0}:3:27: EOF while parsing a string\n",
+                "{0}:4: /mdrepo_id: invalid MDRepo ID \"MDR_1\"\n",
+                "{0}:4: /initial/lead_contributor_orcid: invalid ORCID \"0000-0000\"\n",
+                "{0}:4: /initial/lead_contributor_orcid: not listed among the contributors\n",
+                "Checked 3 records, 2 with errors\n",
+            ),
+            EXAMPLE_NDJSON
+        )
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn stats() -> Result<()> {
+    let tmp = tempdir()?;
+    let dir = temp_path(&tmp, "records");
+    fs::create_dir_all(format!("{dir}/sub"))?;
+    fs::copy(EXAMPLE_TOML, format!("{dir}/example.toml"))?;
+    fs::copy(EXAMPLE_V2_TOML, format!("{dir}/example_v2.toml"))?;
+    fs::copy(MDR4423_TOML, format!("{dir}/sub/MDR_00004423.toml"))?;
+    fs::copy(TRUNCATED_TOML, format!("{dir}/sub/truncated.toml"))?;
+
+    let output = Command::cargo_bin(PRG)?.args(["stats", &dir]).output()?;
+    let json = Command::cargo_bin(PRG)?
+        .args(["stats", "--json", &dir])
+        .output()?;
+
+    // The unparsable file is reported but not counted
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stderr)?.contains("truncated.toml:5:43"));
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        concat!(
+            "Records: 3 (2 with papers, 1 without)\n",
+            "\nSoftware:\n",
+            "  GROMACS  3\n",
+            "\nForcefields:\n",
+            "  Amber99SB-ILDN  2\n",
+            "  charmm36        1\n",
+            "\nWater models:\n",
+            "  TIP3P  3\n",
+            "\nTemperatures (K):\n",
+            "  270-279  2\n",
+            "  300-309  1\n",
+            "\nTotal replicates:\n",
+            "  4   1\n",
+            "  10  2\n",
+        )
+    );
+
+    let report: serde_json::Value = serde_json::from_slice(&json.stdout)?;
+    assert_eq!(report["num_records"], 3);
+    assert_eq!(report["num_without_papers"], 1);
+    assert_eq!(
+        report["forcefields"],
+        serde_json::json!({"Amber99SB-ILDN": 2, "charmm36": 1})
+    );
+    assert_eq!(
+        report["temperatures"],
+        serde_json::json!([
+            {"min": 270, "max": 279, "count": 2},
+            {"min": 300, "max": 309, "count": 1},
+        ])
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn list_inputs() -> Result<()> {
+    let tmp = tempdir()?;
+    let dir = temp_path(&tmp, "records");
+    fs::create_dir_all(format!("{dir}/sub/old"))?;
+    fs::copy(EXAMPLE_TOML, format!("{dir}/example.toml"))?;
+    fs::copy(EXAMPLE_V2_TOML, format!("{dir}/sub/example_v2.toml"))?;
+    fs::copy(MDR4423_TOML, format!("{dir}/sub/old/MDR_00004423.toml"))?;
+    fs::copy(TRUNCATED_TOML, format!("{dir}/sub/truncated.toml"))?;
+    fs::write(format!("{dir}/notes.txt"), "not metadata")?;
+
+    // Nothing is read, so the truncated file is no failure
+    let expected = format!("{dir}/example.toml\n{dir}/sub/example_v2.toml\n");
+    for cmd in ["check", "stats"] {
+        Command::cargo_bin(PRG)?
+            .args([cmd, "--list-inputs", &dir])
+            .args(["--exclude", "*/old/*", "--exclude", "*truncated*"])
+            .assert()
+            .success()
+            .stdout(expected.clone());
+    }
+
+    Command::cargo_bin(PRG)?
+        .args(["check", "--list-inputs", &dir])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("sub/old/MDR_00004423.toml\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn convert_versions() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["convert", "--from", "v1", "--to", "v2", EXAMPLE_TOML])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#"temperature_kelvin = 273"#))
+        .stdout(predicate::str::contains("[initial]").not());
+
+    // The input version is detected, and converting to it changes nothing
+    Command::cargo_bin(PRG)?
+        .args(["convert", "--to", "v1", "--format", "json", EXAMPLE_V2_TOML])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""initial": {"#));
+
+    Command::cargo_bin(PRG)?
+        .args(["convert", "--to", "v2", EXAMPLE_V2_TOML])
+        .assert()
+        .success()
+        .stdout(fs::read_to_string(EXAMPLE_V2_TOML)?);
+
+    Command::cargo_bin(PRG)?
+        .args(["convert", "--from", "v1", EXAMPLE_V2_TOML])
+        .assert()
+        .failure()
+        .stderr(format!("{EXAMPLE_V2_TOML}: expected V1 but found V2\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn diff_files() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["diff", EXAMPLE_TOML, EXAMPLE_TOML])
+        .assert()
+        .success()
+        .stdout("No differences\n");
+
+    // The same date written differently is no change
+    let dir = tempdir()?;
+    let changed = temp_path(&dir, "changed.toml");
+    fs::write(
+        &changed,
+        fs::read_to_string(EXAMPLE_TOML)?
+            .replace(r#"date = "Feb 5, 2000""#, "date = 2000-02-05")
+            .replace("temperature = 273", "temperature = 310"),
+    )?;
+    let output = Command::cargo_bin(PRG)?
+        .args(["diff", EXAMPLE_TOML, &changed])
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "/temperature/temperature: 273 -> 310\n"
+    );
+
+    Command::cargo_bin(PRG)?
+        .args(["diff", EXAMPLE_TOML, EXAMPLE_V2_TOML])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "+ /papers/10.1038~1x41594-020-0884-y/primary: true\n",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn merge_replicates() -> Result<()> {
+    let example = fs::read_to_string(EXAMPLE_TOML)?;
+    let dir = tempdir()?;
+    let replicates: Vec<String> = (2..4)
+        .map(|num| temp_path(&dir, &format!("replicate{num}.toml")))
+        .collect();
+    fs::write(
+        &replicates[0],
+        example.replace("replicate = 1", "replicate = 3"),
+    )?;
+    fs::write(
+        &replicates[1],
+        example
+            .replace("replicate = 1", "replicate = 2")
+            .replace("temperature = 273", "temperature = 310"),
+    )?;
+
+    let merged = Command::cargo_bin(PRG)?
+        .args(["merge", EXAMPLE_TOML, &replicates[0]])
+        .output()?;
+    let diverged = Command::cargo_bin(PRG)?
+        .args(["merge", EXAMPLE_TOML, &replicates[0], &replicates[1]])
+        .output()?;
+    assert!(merged.status.success());
+    let stdout = String::from_utf8(merged.stdout)?;
+    assert!(stdout.contains("merged_replicates = [\n    1,\n    3,\n]"));
+    assert!(!stdout.contains("replicate = "));
+
+    // Differences other than the replicate are reported by field
+    assert!(!diverged.status.success());
+    assert_eq!(
+        String::from_utf8(diverged.stderr)?,
+        format!(
+            "{}: differs from {EXAMPLE_TOML}:\n/temperature/temperature: 273 -> 310\n",
+            replicates[1]
+        )
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn score() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["score", "-n", "2", MDR4423_TOML])
+        .assert()
+        .success()
+        .stdout(concat!(
+            "Completeness: 74%\n",
+            "Most impactful empty fields:\n",
+            "  papers (weight 3)\n",
+            "  timestep_information (weight 2)\n",
+        ));
+
+    Command::cargo_bin(PRG)?
+        .args(["score", EXAMPLE_V2_TOML])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Completeness: 97%\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn info() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["info", MDR4423_TOML])
+        .assert()
+        .success()
+        .stdout("GROMACS 2024, 1 protein, 1 ligand, 300 K, charmm36, replicate 1/4\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn to_toml_numeric_volumes() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["to-toml", EXAMPLE_TOML])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("volume = \"17\"\n"));
+
+    Command::cargo_bin(PRG)?
+        .args(["to-toml", "--numeric-volumes", EXAMPLE_TOML])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("volume = 17\nnumber = 4\n"));
+
+    Command::cargo_bin(PRG)?
+        .args(["to-toml", "--numeric-volumes", EXAMPLE_V2_TOML])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("volume = 17\nnumber = 4\n"));
     Ok(())
 }